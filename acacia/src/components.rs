@@ -0,0 +1,828 @@
+//! Headless UI components built on native HTML elements and HTMX.
+//!
+//! Each component renders accessible, unstyled markup with a default
+//! `acacia-*` class that can be replaced with `.class(...)`. Components are
+//! builders that render wherever a [`RenderHtml`] value is accepted.
+//!
+//! # Example
+//! ```ignore
+//! html! {
+//!     <main>
+//!         {Accordion::new("faq")
+//!             .item("What is Acacia?", html! { <p>A hypermedia framework.</p> })
+//!             .exclusive()}
+//!         {Toast::region()}
+//!     </main>
+//! }
+//! ```
+
+use acacia_core::{
+    escape_html, loads, Endpoint, Fragment, HtmxAction, RenderHtml, Swap, TableRow, Target,
+};
+use acacia_db::{AuditAction, AuditEntry, Cursor};
+use acacia_macros::html;
+
+/// Id of the container that [`Toast`]s are appended to.
+pub const TOAST_REGION: &str = "toasts";
+
+/// A dialog with a title, body and close button.
+///
+/// Renders an open `<dialog>`, so it is usually swapped into the page by an
+/// action. The close button uses `<form method="dialog">`, which needs no script.
+///
+/// # Example
+/// ```ignore
+/// #[action("/tasks/{id}/confirm-delete")]
+/// async fn confirm_delete(Path(id): Path<i32>) -> Fragment {
+///     Modal::new("confirm", html! { <button {removes(DELETE_TASK(id))}>Delete</button> })
+///         .title("Delete task?")
+///         .into()
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Modal {
+    id: String,
+    title: Option<String>,
+    body: Fragment,
+    class: String,
+    open: bool,
+}
+
+impl Modal {
+    pub fn new(id: &str, body: Fragment) -> Self {
+        Self {
+            id: id.to_string(),
+            title: None,
+            body,
+            class: "acacia-modal".to_string(),
+            open: true,
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+
+    /// Render the dialog closed, for pages that open it themselves.
+    pub fn closed(mut self) -> Self {
+        self.open = false;
+        self
+    }
+}
+
+impl RenderHtml for Modal {
+    fn render_html(&self) -> String {
+        let title_id = format!("{}-title", self.id);
+        let open = if self.open { "open" } else { "" };
+        html! {
+            <dialog id={&self.id} class={&self.class} aria-labelledby={&title_id} {open}>
+                {self.title.as_ref().map(|title| html! { <h2 id={&title_id}>{title}</h2> })}
+                {self.body}
+                <form method="dialog">
+                    <button type="submit" aria-label="Close">"×"</button>
+                </form>
+            </dialog>
+        }
+        .0
+    }
+}
+
+/// Tabs whose panels are loaded from the server.
+///
+/// Each tab requests its endpoint and replaces the whole component, so the
+/// handler renders the same tabs with a different selection and panel.
+///
+/// # Example
+/// ```ignore
+/// #[action("/settings/billing")]
+/// async fn billing_tab() -> Fragment {
+///     Tabs::new("settings")
+///         .tab("Profile", PROFILE_TAB)
+///         .tab("Billing", BILLING_TAB)
+///         .selected(1)
+///         .panel(html! { <p>No invoices yet.</p> })
+///         .into()
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tabs {
+    id: String,
+    tabs: Vec<(String, Endpoint)>,
+    selected: usize,
+    panel: Fragment,
+    class: String,
+}
+
+impl Tabs {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            tabs: Vec::new(),
+            selected: 0,
+            panel: Fragment::empty(),
+            class: "acacia-tabs".to_string(),
+        }
+    }
+
+    pub fn tab(mut self, label: &str, endpoint: Endpoint) -> Self {
+        self.tabs.push((label.to_string(), endpoint));
+        self
+    }
+
+    /// Index of the active tab.
+    pub fn selected(mut self, index: usize) -> Self {
+        self.selected = index;
+        self
+    }
+
+    /// Content of the active tab.
+    pub fn panel(mut self, panel: Fragment) -> Self {
+        self.panel = panel;
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+}
+
+impl RenderHtml for Tabs {
+    fn render_html(&self) -> String {
+        let panel_id = format!("{}-panel", self.id);
+        let container = format!("#{}", self.id);
+        html! {
+            <div id={&self.id} class={&self.class}>
+                <div role="tablist">
+                    {for (index, (label, endpoint)) in self.tabs.iter().enumerate() {
+                        html! {
+                            <button
+                                type="button"
+                                role="tab"
+                                id={format!("{}-tab-{}", self.id, index)}
+                                aria-selected={index == self.selected}
+                                aria-controls={&panel_id}
+                                {loads(endpoint.clone()).into(&container).swap(Swap::OuterHtml)}
+                            >
+                                {label}
+                            </button>
+                        }
+                    }}
+                </div>
+                <div
+                    role="tabpanel"
+                    id={&panel_id}
+                    aria-labelledby={format!("{}-tab-{}", self.id, self.selected)}
+                >
+                    {self.panel}
+                </div>
+            </div>
+        }
+        .0
+    }
+}
+
+enum DropdownItem {
+    Link(String, Endpoint),
+    Action(String, Box<HtmxAction>),
+}
+
+/// A menu revealed by a button, built on `<details>`.
+///
+/// # Example
+/// ```ignore
+/// Dropdown::new("Actions")
+///     .link("Edit", EDIT_TASK(task.id))
+///     .action("Delete", removes(DELETE_TASK(task.id)))
+/// ```
+pub struct Dropdown {
+    label: String,
+    items: Vec<DropdownItem>,
+    class: String,
+}
+
+impl Dropdown {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            items: Vec::new(),
+            class: "acacia-dropdown".to_string(),
+        }
+    }
+
+    /// Add an item navigating to a page.
+    pub fn link(mut self, label: &str, endpoint: Endpoint) -> Self {
+        self.items
+            .push(DropdownItem::Link(label.to_string(), endpoint));
+        self
+    }
+
+    /// Add an item triggering an HTMX request.
+    pub fn action(mut self, label: &str, action: HtmxAction) -> Self {
+        self.items
+            .push(DropdownItem::Action(label.to_string(), Box::new(action)));
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+}
+
+impl RenderHtml for Dropdown {
+    fn render_html(&self) -> String {
+        html! {
+            <details class={&self.class}>
+                <summary aria-haspopup="menu">{self.label}</summary>
+                <ul role="menu">
+                    {for item in &self.items {
+                        match item {
+                            DropdownItem::Link(label, endpoint) => html! {
+                                <li role="none">
+                                    <a role="menuitem" href={endpoint.href()}>{label}</a>
+                                </li>
+                            },
+                            DropdownItem::Action(label, action) => html! {
+                                <li role="none">
+                                    <button type="button" role="menuitem" {action}>{label}</button>
+                                </li>
+                            },
+                        }
+                    }}
+                </ul>
+            </details>
+        }
+        .0
+    }
+}
+
+/// Collapsible sections built on `<details>`.
+///
+/// # Example
+/// ```ignore
+/// Accordion::new("faq")
+///     .item("Shipping", html! { <p>Ships in 2 days.</p> })
+///     .item("Returns", html! { <p>30 days.</p> })
+///     .open(0)
+/// ```
+#[derive(Clone, Debug)]
+pub struct Accordion {
+    id: String,
+    items: Vec<(String, Fragment)>,
+    open: Option<usize>,
+    exclusive: bool,
+    class: String,
+}
+
+impl Accordion {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            items: Vec::new(),
+            open: None,
+            exclusive: false,
+            class: "acacia-accordion".to_string(),
+        }
+    }
+
+    pub fn item(mut self, title: &str, content: Fragment) -> Self {
+        self.items.push((title.to_string(), content));
+        self
+    }
+
+    /// Index of the section expanded initially.
+    pub fn open(mut self, index: usize) -> Self {
+        self.open = Some(index);
+        self
+    }
+
+    /// Allow only one section to be expanded at a time.
+    pub fn exclusive(mut self) -> Self {
+        self.exclusive = true;
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+}
+
+impl RenderHtml for Accordion {
+    fn render_html(&self) -> String {
+        // Details elements sharing a name close each other natively
+        let name = if self.exclusive {
+            format!("name=\"{}\"", escape_html(&self.id))
+        } else {
+            String::new()
+        };
+        html! {
+            <div id={&self.id} class={&self.class}>
+                {for (index, (title, content)) in self.items.iter().enumerate() {
+                    html! {
+                        <details {&name} {if self.open == Some(index) { "open" } else { "" }}>
+                            <summary>{title}</summary>
+                            {content}
+                        </details>
+                    }
+                }}
+            </div>
+        }
+        .0
+    }
+}
+
+/// Severity of a [`Toast`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToastLevel {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            ToastLevel::Info => "info",
+            ToastLevel::Success => "success",
+            ToastLevel::Warning => "warning",
+            ToastLevel::Error => "error",
+        }
+    }
+}
+
+/// A notification appended to the toast region out of band.
+///
+/// Return it next to an action's main fragment; htmx moves it into the
+/// [`Toast::region`] rendered once in the layout.
+///
+/// # Example
+/// ```ignore
+/// Ok(html! {
+///     {TaskItem(&task)}
+///     {Toast::success("Task created")}
+/// })
+/// ```
+#[derive(Clone, Debug)]
+pub struct Toast {
+    message: String,
+    level: ToastLevel,
+    class: String,
+}
+
+impl Toast {
+    pub fn new(message: &str, level: ToastLevel) -> Self {
+        Self {
+            message: message.to_string(),
+            level,
+            class: format!("acacia-toast acacia-toast-{}", level.as_str()),
+        }
+    }
+
+    pub fn info(message: &str) -> Self {
+        Self::new(message, ToastLevel::Info)
+    }
+
+    pub fn success(message: &str) -> Self {
+        Self::new(message, ToastLevel::Success)
+    }
+
+    pub fn warning(message: &str) -> Self {
+        Self::new(message, ToastLevel::Warning)
+    }
+
+    pub fn error(message: &str) -> Self {
+        Self::new(message, ToastLevel::Error)
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+
+    /// The live region toasts are appended to.
+    pub fn region() -> Fragment {
+        html! {
+            <div id={TOAST_REGION} class="acacia-toasts" aria-live="polite"></div>
+        }
+    }
+}
+
+impl RenderHtml for Toast {
+    fn render_html(&self) -> String {
+        // Problems interrupt assistive technology, the rest wait politely
+        let role = match self.level {
+            ToastLevel::Warning | ToastLevel::Error => "alert",
+            ToastLevel::Info | ToastLevel::Success => "status",
+        };
+        html! {
+            <div id={TOAST_REGION} hx-swap-oob="beforeend">
+                <div class={&self.class} role={role}>{self.message}</div>
+            </div>
+        }
+        .0
+    }
+}
+
+/// A placeholder that loads its content once scrolled into view.
+///
+/// Renders a busy skeleton `<div>` that requests its endpoint when revealed
+/// and is replaced by the response, so expensive sections don't hold up the
+/// initial page.
+///
+/// # Example
+/// ```ignore
+/// html! {
+///     <main>
+///         <h1>Dashboard</h1>
+///         {lazy(STATS)}
+///         {lazy(ACTIVITY).placeholder(html! { <p>Loading activity…</p> })}
+///     </main>
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Lazy {
+    endpoint: Endpoint,
+    placeholder: Fragment,
+    class: String,
+    trigger: &'static str,
+}
+
+impl Lazy {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self {
+            endpoint,
+            placeholder: Fragment::default(),
+            class: "acacia-lazy".to_string(),
+            trigger: "revealed",
+        }
+    }
+
+    /// Content shown until the section has loaded.
+    pub fn placeholder(mut self, placeholder: Fragment) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+
+    /// Load right after the page does instead of when scrolled into view.
+    pub fn eager(mut self) -> Self {
+        self.trigger = "load";
+        self
+    }
+}
+
+/// Shorthand for [`Lazy::new`].
+pub fn lazy(endpoint: Endpoint) -> Lazy {
+    Lazy::new(endpoint)
+}
+
+impl RenderHtml for Lazy {
+    fn render_html(&self) -> String {
+        let load = loads(self.endpoint.clone())
+            .target(Target::This)
+            .swap(Swap::OuterHtml);
+        html! {
+            <div class={&self.class} {load} hx-trigger={self.trigger} aria-busy="true">
+                {self.placeholder}
+            </div>
+        }
+        .0
+    }
+}
+
+/// Loads the next page of a feed in its place, for infinite scroll.
+///
+/// Requests the endpoint with `?after=<cursor>` once scrolled into view and is
+/// replaced by the response, which renders the next items and another
+/// `LoadMore`. Renders nothing on the last page.
+///
+/// # Example
+/// ```ignore
+/// #[page("/feed")]
+/// async fn feed(db: Db, axum::extract::Query(feed): axum::extract::Query<Feed>) -> Result<Fragment> {
+///     let page = db.page_after::<Post>(feed.after, 20).await?;
+///     Ok(html! {
+///         {for post in &page.items { html! { <article>{&post.title}</article> } }}
+///         {LoadMore::new(FEED, page.next.as_ref())}
+///     })
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoadMore {
+    endpoint: Endpoint,
+    cursor: Option<Cursor>,
+    param: String,
+    label: Option<String>,
+    class: String,
+}
+
+impl LoadMore {
+    pub fn new(endpoint: Endpoint, cursor: Option<&Cursor>) -> Self {
+        Self {
+            endpoint,
+            cursor: cursor.cloned(),
+            param: "after".to_string(),
+            label: None,
+            class: "acacia-load-more".to_string(),
+        }
+    }
+
+    /// Query parameter the cursor is sent in (default `after`).
+    pub fn param(mut self, name: &str) -> Self {
+        self.param = name.to_string();
+        self
+    }
+
+    /// Render a button that loads on click instead of when scrolled into view.
+    pub fn button(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+}
+
+impl RenderHtml for LoadMore {
+    fn render_html(&self) -> String {
+        let Some(cursor) = &self.cursor else {
+            return String::new();
+        };
+        let separator = if self.endpoint.path.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        let url = format!(
+            "{}{}{}={}",
+            self.endpoint.path, separator, self.param, cursor
+        );
+        let load = loads(Endpoint::get(url).in_module(self.endpoint.module))
+            .target(Target::This)
+            .swap(Swap::OuterHtml);
+        match &self.label {
+            Some(label) => html! {
+                <button type="button" class={&self.class} {load}>{label}</button>
+            },
+            None => html! {
+                <div class={&self.class} {load} hx-trigger="revealed" aria-busy="true"></div>
+            },
+        }
+        .0
+    }
+}
+
+/// A list of changes to `#[audited]` models, newest first as given.
+///
+/// Each entry names who made the change and the row, and updates list the
+/// columns that changed with their old and new values.
+///
+/// # Example
+/// ```ignore
+/// #[page("/tasks/{id}/history")]
+/// async fn history(Path(id): Path<i32>, db: Db) -> Result<Fragment> {
+///     let entries = db.activity_for::<Task>(id).await?;
+///     Ok(ActivityFeed::new(&entries).into())
+/// }
+/// ```
+pub struct ActivityFeed<'a> {
+    entries: &'a [AuditEntry],
+    class: String,
+}
+
+impl<'a> ActivityFeed<'a> {
+    pub fn new(entries: &'a [AuditEntry]) -> Self {
+        Self {
+            entries,
+            class: "acacia-activity".to_string(),
+        }
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+}
+
+/// A logged value as text, without quotes around strings.
+fn audit_value(value: &sea_orm::JsonValue) -> String {
+    match value {
+        sea_orm::JsonValue::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn audit_verb(action: AuditAction) -> &'static str {
+    match action {
+        AuditAction::Insert => "created",
+        AuditAction::Update => "updated",
+        AuditAction::Delete => "deleted",
+    }
+}
+
+impl RenderHtml for ActivityFeed<'_> {
+    fn render_html(&self) -> String {
+        html! {
+            <ol class={&self.class}>
+                {for entry in self.entries {
+                    html! {
+                        <li>
+                            <strong>{entry.actor.as_deref().unwrap_or("Someone")}</strong>
+                            {format!(" {} {} #{} ", audit_verb(entry.action), entry.table, entry.row)}
+                            <time datetime={entry.at.format("%Y-%m-%dT%H:%M:%S")}>
+                                {entry.at.format("%Y-%m-%d %H:%M").to_string()}
+                            </time>
+                            {if entry.action == AuditAction::Update {
+                                html! {
+                                    <ul>
+                                        {for change in &entry.changes {
+                                            html! {
+                                                <li>
+                                                    <code>{&change.column}</code>
+                                                    {format!(
+                                                        ": {} \u{2192} {}",
+                                                        audit_value(&change.before),
+                                                        audit_value(&change.after)
+                                                    )}
+                                                </li>
+                                            }
+                                        }}
+                                    </ul>
+                                }
+                            } else {
+                                Fragment::empty()
+                            }}
+                        </li>
+                    }
+                }}
+            </ol>
+        }
+        .0
+    }
+}
+
+impl From<ActivityFeed<'_>> for Fragment {
+    fn from(feed: ActivityFeed<'_>) -> Self {
+        Fragment(feed.render_html())
+    }
+}
+
+type CellRenderer<'a, M> = Box<dyn Fn(&M) -> Fragment + 'a>;
+type RowAction<'a, M> = Box<dyn Fn(&M) -> HtmxAction + 'a>;
+
+/// A `<table>` with a column per model field.
+///
+/// Columns come from `#[model]` and can be narrowed with `.columns(...)`,
+/// relabeled, or rendered by hand. Row actions add a trailing button cell.
+///
+/// # Example
+/// ```ignore
+/// DataTable::new(&tasks)
+///     .columns(&["title", "done"])
+///     .cell("done", |task| html! { <input type="checkbox" checked={task.done} disabled /> })
+///     .action("Delete", |task| removes(DELETE_TASK(task.id)).target(Target::Closest("tr".into())))
+/// ```
+pub struct DataTable<'a, M> {
+    rows: &'a [M],
+    columns: Vec<&'a str>,
+    headers: Vec<(&'a str, String)>,
+    cells: Vec<(&'a str, CellRenderer<'a, M>)>,
+    actions: Vec<(String, RowAction<'a, M>)>,
+    class: String,
+}
+
+impl<'a, M: TableRow> DataTable<'a, M> {
+    pub fn new(rows: &'a [M]) -> Self {
+        Self {
+            rows,
+            columns: M::COLUMNS.to_vec(),
+            headers: Vec::new(),
+            cells: Vec::new(),
+            actions: Vec::new(),
+            class: "acacia-table".to_string(),
+        }
+    }
+
+    /// Show only these columns, in this order.
+    pub fn columns(mut self, columns: &[&'a str]) -> Self {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    /// Replace a column's header label.
+    pub fn header(mut self, column: &'a str, label: &str) -> Self {
+        self.headers.push((column, label.to_string()));
+        self
+    }
+
+    /// Render a column's cells with a custom function.
+    pub fn cell(mut self, column: &'a str, render: impl Fn(&M) -> Fragment + 'a) -> Self {
+        self.cells.push((column, Box::new(render)));
+        self
+    }
+
+    /// Add a button to every row, triggering the action built for that row.
+    pub fn action(mut self, label: &str, action: impl Fn(&M) -> HtmxAction + 'a) -> Self {
+        self.actions.push((label.to_string(), Box::new(action)));
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+
+    fn label(&self, column: &str) -> String {
+        match self.headers.iter().find(|(name, _)| *name == column) {
+            Some((_, label)) => label.clone(),
+            None => M::label(column),
+        }
+    }
+
+    fn render_cell(&self, row: &M, column: &str) -> String {
+        match self.cells.iter().find(|(name, _)| *name == column) {
+            Some((_, render)) => render(row).0,
+            None => row.cell(column),
+        }
+    }
+}
+
+impl<M: TableRow> RenderHtml for DataTable<'_, M> {
+    fn render_html(&self) -> String {
+        html! {
+            <table class={&self.class}>
+                <thead>
+                    <tr>
+                        {for column in &self.columns {
+                            html! { <th scope="col">{self.label(column)}</th> }
+                        }}
+                        {if !self.actions.is_empty() {
+                            html! { <th scope="col"><span class="sr-only">"Actions"</span></th> }
+                        } else {
+                            Fragment::empty()
+                        }}
+                    </tr>
+                </thead>
+                <tbody>
+                    {for row in self.rows {
+                        html! {
+                            <tr>
+                                {for column in &self.columns {
+                                    html! { <td>{Fragment(self.render_cell(row, column))}</td> }
+                                }}
+                                {if !self.actions.is_empty() {
+                                    html! {
+                                        <td>
+                                            {for (label, action) in &self.actions {
+                                                html! { <button type="button" {action(row)}>{label}</button> }
+                                            }}
+                                        </td>
+                                    }
+                                } else {
+                                    Fragment::empty()
+                                }}
+                            </tr>
+                        }
+                    }}
+                </tbody>
+            </table>
+        }
+        .0
+    }
+}
+
+impl<M: TableRow> From<DataTable<'_, M>> for Fragment {
+    fn from(table: DataTable<'_, M>) -> Self {
+        Fragment(table.render_html())
+    }
+}
+
+macro_rules! into_fragment {
+    ($($component:ty),*) => {
+        $(
+            impl From<$component> for Fragment {
+                fn from(component: $component) -> Self {
+                    Fragment(component.render_html())
+                }
+            }
+        )*
+    };
+}
+
+into_fragment!(Modal, Tabs, Dropdown, Accordion, Toast, Lazy);
@@ -14,42 +14,76 @@
 //! #[tokio::main]
 //! async fn main() {
 //!     Acacia::new()
-//!         .serve("0.0.0.0:3000")
+//!         .serve_or_exit("0.0.0.0:3000")
 //!         .await;
 //! }
 //! ```
 
+pub mod components;
+
 pub mod prelude {
     // Core types
     pub use acacia_core::{
-        escape_html, loads, removes, submits, AppError, AppState, Children, Endpoint, Error,
-        Fragment, HtmxAction, Method, OptionExt, Page, RenderHtml, Response, Result,
-        RouteDefinition, Swap, Target, Valid,
+        body_limit, broadcast, csp_nonce, ctx, escape_html, links, live, loads, removes, submits,
+        update_ctx, AppError, AppState, Children, Cookie, Cookies, Download, Email, Endpoint,
+        Error, ErrorReport, Event, FieldError, FormErrors, FormField, FormSchema, Fragment,
+        FragmentStream, Htmx, HtmxAction, HtmxRequest, Inject, Layout, Link, Location, Mailer,
+        Method, OptionExt, Page, PageOrFragment, Permission, Profile, RenderHtml, RequestContext,
+        Respond, Response, Result, ResultExt, Roles, RouteDefinition, SameSite, Scroll, Swap,
+        TableRow, Target, Trigger, Updates, Upload, Valid, Validate,
+    };
+
+    #[cfg(feature = "chrono")]
+    pub use acacia_core::{
+        format_date, set_date_format, set_datetime_format, set_time_format, FormatDate,
     };
 
     // Macros
     #[cfg(feature = "tailwind")]
     pub use acacia_macros::tw;
-    pub use acacia_macros::{action, component, form, html, model, page, Form};
+    pub use acacia_macros::{
+        action, component, form, html, html_into, model, page, view, Form, RenderHtml,
+    };
 
     // Database
-    pub use acacia_db::{Db, Form as FormTrait, MigratePolicy, Set};
+    pub use acacia_db::{
+        AuditEntry, Cursor, Db, DbConfig, Form as FormTrait, MigratePolicy, Order, Paged, Query,
+        Set,
+    };
 
     // SeaORM re-exports for entity definitions and queries
     pub use sea_orm::entity::prelude::*;
     pub use sea_orm::IntoActiveModel;
 
+    // Sessions
+    #[cfg(feature = "magic-link")]
+    pub use acacia_auth::{MagicLink, VerifiedEmail};
+    #[cfg(feature = "oauth")]
+    pub use acacia_auth::{OAuthProfile, Provider};
+    pub use acacia_auth::{Session, SessionConfig};
+
+    pub use std::time::Duration;
+
     // Server
-    pub use acacia_server::Acacia;
+    #[cfg(feature = "tls")]
+    pub use acacia_server::Tls;
+    #[cfg(feature = "embed")]
+    pub use acacia_server::{include_dir, Dir};
+    pub use acacia_server::{
+        Acacia, AcaciaError, AcaciaModule, AccessLog, Assets, Csp, Http, RequestInfo, Security,
+        Sitemap, TrailingSlash,
+    };
 
     // Re-export axum extractors
     pub use axum::extract::Path;
+    pub use axum::http::StatusCode;
 
     // Re-export serde for derive
     pub use serde::{Deserialize, Serialize};
 }
 
 // Re-export sub-crates
+pub use acacia_auth;
 pub use acacia_core;
 pub use acacia_db;
 pub use acacia_macros;
@@ -0,0 +1,25 @@
+//! Authentication for the Acacia web framework.
+//!
+//! - [`session`] - Cookie sessions with pluggable stores
+//! - `oauth` - OAuth2 and OpenID Connect login (feature `oauth`)
+//! - `magic_link` - Passwordless email sign-in (feature `magic-link`)
+
+#[cfg(feature = "magic-link")]
+pub mod magic_link;
+#[cfg(feature = "oauth")]
+pub mod oauth;
+pub mod session;
+
+#[cfg(feature = "magic-link")]
+pub use magic_link::{MagicLink, VerifiedEmail};
+#[cfg(feature = "oauth")]
+#[allow(deprecated)]
+pub use oauth::Profile;
+#[cfg(feature = "oauth")]
+pub use oauth::{OAuth, OAuthProfile, Provider};
+
+#[cfg(feature = "redis")]
+pub use session::RedisStore;
+pub use session::{
+    DbStore, MemoryStore, Session, SessionConfig, SessionData, SessionError, SessionStore,
+};
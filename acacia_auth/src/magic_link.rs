@@ -0,0 +1,299 @@
+//! Passwordless sign-in with single-use links sent by email.
+
+use crate::Session;
+use acacia_core::{escape_html, AppError, AppState, Email, Fragment, Mailer};
+use acacia_db::Db;
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Path, Request, State},
+    http::request::Parts,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post, MethodRouter},
+    Form, Router,
+};
+use sea_orm::sea_query::{ColumnDef, Expr, ExprTrait, Query, Table, TableCreateStatement};
+use sea_orm::{ConnectionTrait, Schema};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+/// Session key holding the email when no login handler is registered.
+pub const EMAIL_KEY: &str = "magic_link_email";
+
+const TABLE: &str = "acacia_magic_links";
+
+fn create_table(_schema: &Schema) -> TableCreateStatement {
+    Table::create()
+        .table(TABLE)
+        .if_not_exists()
+        .col(
+            ColumnDef::new("token_hash")
+                .string()
+                .not_null()
+                .primary_key(),
+        )
+        .col(ColumnDef::new("email").string().not_null())
+        .col(ColumnDef::new("expires_at").big_integer().not_null())
+        .to_owned()
+}
+
+inventory::submit! {
+    acacia_db::EntityRegistration::new(create_table)
+}
+
+type Template = dyn Fn(&str, &str) -> Email + Send + Sync;
+
+/// Magic link settings for `Acacia::magic_link`.
+///
+/// Users submit their address to `POST /auth/magic`, and the emailed link
+/// (`/auth/magic/{token}`) signs them in once within the link's lifetime.
+/// Links point at the configured base URL, never at the request's `Host`,
+/// so a forged header can't send a real token to another site.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new()
+///     .mailer(Mailer::log("noreply@example.com"))
+///     .magic_link(
+///         MagicLink::new("https://example.com")
+///             .ttl(Duration::from_secs(10 * 60))
+///             .email(|to, url| {
+///                 Email::new(to, "Your sign-in link")
+///                     .html(html! { <a href={url}>Sign in to Example</a> })
+///             })
+///             .login(login),
+///     )
+/// ```
+#[derive(Clone)]
+pub struct MagicLink {
+    base_url: String,
+    path: String,
+    ttl: Duration,
+    template: Arc<Template>,
+    login: Option<MethodRouter<AppState>>,
+}
+
+impl MagicLink {
+    /// Links to `base_url` under `/auth/magic`, valid for 15 minutes.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            path: "/auth/magic".to_string(),
+            ttl: Duration::from_secs(15 * 60),
+            template: Arc::new(default_email),
+            login: None,
+        }
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// How long a link can be used for.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Build the email from the recipient and the sign-in URL.
+    pub fn email(mut self, template: impl Fn(&str, &str) -> Email + Send + Sync + 'static) -> Self {
+        self.template = Arc::new(template);
+        self
+    }
+
+    /// Run `handler` once a link is verified; it can extract [`VerifiedEmail`].
+    ///
+    /// Without one, the address is stored in the session and the user is sent to `/`.
+    pub fn login<H, T>(mut self, handler: H) -> Self
+    where
+        H: axum::handler::Handler<T, AppState>,
+        T: 'static,
+    {
+        self.login = Some(get(handler));
+        self
+    }
+
+    /// `POST {path}` to request a link and `GET {path}/{token}` to use it.
+    pub fn router(self) -> Router<AppState> {
+        let config = Arc::new(self.clone());
+        let login = self.login.unwrap_or_else(|| get(store_email));
+        Router::new()
+            .route(
+                &self.path,
+                post(move |mailer: Mailer, db: Db, form: Form<RequestLink>| {
+                    issue(config.clone(), mailer, db, form)
+                }),
+            )
+            .route(
+                &format!("{}/:token", self.path),
+                get(move |State(state): State<AppState>, req: Request| {
+                    verify(login.clone(), state, req)
+                }),
+            )
+    }
+}
+
+fn default_email(to: &str, url: &str) -> Email {
+    Email::new(to, "Your sign-in link")
+        .html(Fragment(format!(
+            r#"<p><a href="{}">Sign in</a></p><p>If you didn't ask for this link, you can ignore this email.</p>"#,
+            escape_html(url)
+        )))
+        .text(format!("Sign in: {}", url))
+}
+
+#[derive(Deserialize)]
+struct RequestLink {
+    email: String,
+}
+
+fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn db_error(err: sea_orm::DbErr) -> AppError {
+    AppError::Database(err.to_string())
+}
+
+/// Store a token and email its link.
+async fn issue(
+    config: Arc<MagicLink>,
+    mailer: Mailer,
+    db: Db,
+    Form(form): Form<RequestLink>,
+) -> acacia_core::Result<Fragment> {
+    let email = form.email.trim();
+    if !email.contains('@') {
+        return Err(AppError::BadRequest(
+            "Enter a valid email address".to_string(),
+        ));
+    }
+
+    // Only the hash is stored, so a leaked table can't be used to sign in
+    let token = crate::session::new_id();
+    let insert = Query::insert()
+        .into_table(TABLE)
+        .columns(["token_hash", "email", "expires_at"])
+        .values_panic([
+            hash(&token).into(),
+            email.into(),
+            (crate::session::now_secs() + config.ttl.as_secs() as i64).into(),
+        ])
+        .to_owned();
+    db.connection().execute(&insert).await.map_err(db_error)?;
+
+    let url = format!("{}{}/{}", config.base_url, config.path, token);
+    mailer.send((config.template)(email, &url)).await?;
+
+    Ok(Fragment(format!(
+        r#"<p role="status">Check {} for a sign-in link.</p>"#,
+        escape_html(email)
+    )))
+}
+
+/// An email address proven by following a magic link, extracted in the login handler.
+#[derive(Clone, Debug)]
+pub struct VerifiedEmail(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for VerifiedEmail
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<VerifiedEmail>()
+            .cloned()
+            .ok_or(AppError::Unauthorized)
+    }
+}
+
+/// Consume the token and hand its address to the login handler.
+async fn verify(login: MethodRouter<AppState>, state: AppState, req: Request) -> Response {
+    let (mut parts, body) = req.into_parts();
+    let email = match check(&mut parts, &state).await {
+        Ok(email) => email,
+        Err(err) => return err.into_response(),
+    };
+    parts.extensions.insert(VerifiedEmail(email));
+    let req = Request::from_parts(parts, body);
+    match login.with_state(state).oneshot(req).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
+}
+
+async fn check(parts: &mut Parts, state: &AppState) -> acacia_core::Result<String> {
+    let Path(token) = Path::<String>::from_request_parts(parts, state)
+        .await
+        .map_err(|_| AppError::NotFound)?;
+    let session = Session::from_request_parts(parts, state).await?;
+    let email = consume(&Db::from_ref(state), &token)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest("This sign-in link is invalid or has expired".to_string())
+        })?;
+
+    // A fresh session id guards against fixation
+    session.regenerate();
+    Ok(email)
+}
+
+/// Delete an unexpired token, returning its email.
+async fn consume(db: &Db, token: &str) -> acacia_core::Result<Option<String>> {
+    let conn = db.connection();
+    let token_hash = hash(token);
+    let now = crate::session::now_secs();
+    let select = Query::select()
+        .column("email")
+        .from(TABLE)
+        .and_where(Expr::col("token_hash").eq(token_hash.as_str()))
+        .and_where(Expr::col("expires_at").gt(now))
+        .to_owned();
+    let email = match conn.query_one(&select).await.map_err(db_error)? {
+        Some(row) => Some(row.try_get::<String>("", "email").map_err(db_error)?),
+        None => None,
+    };
+
+    // Only the request that deletes the row may use it; a concurrent one
+    // that loses the race deletes nothing
+    let delete = Query::delete()
+        .from_table(TABLE)
+        .and_where(Expr::col("token_hash").eq(token_hash.as_str()))
+        .and_where(Expr::col("expires_at").gt(now))
+        .to_owned();
+    let deleted = conn.execute(&delete).await.map_err(db_error)?;
+
+    // Expired tokens are swept separately, so they don't count above
+    let sweep = Query::delete()
+        .from_table(TABLE)
+        .and_where(Expr::col("expires_at").lte(now))
+        .to_owned();
+    conn.execute(&sweep).await.map_err(db_error)?;
+
+    if deleted.rows_affected() == 0 {
+        return Ok(None);
+    }
+    Ok(email)
+}
+
+/// Default login handler: keep the address in the session and go home.
+async fn store_email(
+    session: Session,
+    VerifiedEmail(email): VerifiedEmail,
+) -> acacia_core::Result<Redirect> {
+    session.insert(EMAIL_KEY, &email)?;
+    Ok(Redirect::to("/"))
+}
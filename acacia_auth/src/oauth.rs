@@ -0,0 +1,501 @@
+//! OAuth2 and OpenID Connect login.
+
+use crate::Session;
+use acacia_core::{AppError, AppState};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query, Request},
+    http::request::Parts,
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, MethodRouter},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+/// Session key holding the profile when no login handler is registered.
+pub const PROFILE_KEY: &str = "oauth_profile";
+
+/// Session key holding the pending `state` parameter.
+const STATE_KEY: &str = "oauth_state";
+
+/// Authorization, token and user info URLs of a provider.
+#[derive(Clone, Debug, Deserialize)]
+struct Endpoints {
+    #[serde(rename = "authorization_endpoint")]
+    authorize: String,
+    #[serde(rename = "token_endpoint")]
+    token: String,
+    #[serde(rename = "userinfo_endpoint")]
+    userinfo: String,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    GitHub,
+    /// Any OpenID Connect provider, with endpoints discovered from the issuer.
+    Oidc {
+        issuer: String,
+        endpoints: Arc<OnceCell<Endpoints>>,
+    },
+}
+
+/// An OAuth2 login provider.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new()
+///     .oauth(Provider::github(&env("GITHUB_ID")?, &env("GITHUB_SECRET")?))
+///     .oauth(Provider::google(&env("GOOGLE_ID")?, &env("GOOGLE_SECRET")?))
+/// ```
+#[derive(Clone, Debug)]
+pub struct Provider {
+    name: String,
+    client_id: String,
+    client_secret: String,
+    scopes: Vec<String>,
+    redirect_url: Option<String>,
+    kind: Kind,
+}
+
+impl Provider {
+    /// Sign in with GitHub; the callback URL is `/auth/github/callback`.
+    pub fn github(client_id: &str, client_secret: &str) -> Self {
+        Self {
+            name: "github".to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            scopes: vec!["read:user".to_string(), "user:email".to_string()],
+            redirect_url: None,
+            kind: Kind::GitHub,
+        }
+    }
+
+    /// Sign in with Google; the callback URL is `/auth/google/callback`.
+    pub fn google(client_id: &str, client_secret: &str) -> Self {
+        Self::oidc(
+            "google",
+            "https://accounts.google.com",
+            client_id,
+            client_secret,
+        )
+    }
+
+    /// Sign in with any OpenID Connect provider, e.g. Keycloak or Auth0.
+    ///
+    /// Endpoints are discovered from `{issuer}/.well-known/openid-configuration`.
+    /// The callback URL is `/auth/{name}/callback`.
+    pub fn oidc(name: &str, issuer: &str, client_id: &str, client_secret: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            scopes: vec![
+                "openid".to_string(),
+                "email".to_string(),
+                "profile".to_string(),
+            ],
+            redirect_url: None,
+            kind: Kind::Oidc {
+                issuer: issuer.trim_end_matches('/').to_string(),
+                endpoints: Arc::default(),
+            },
+        }
+    }
+
+    /// Request these scopes instead of the defaults.
+    pub fn scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+
+    /// The full callback URL, when it can't be derived from the request's `Host`.
+    pub fn redirect_url(mut self, url: &str) -> Self {
+        self.redirect_url = Some(url.to_string());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn endpoints(&self, client: &reqwest::Client) -> acacia_core::Result<Endpoints> {
+        match &self.kind {
+            Kind::GitHub => Ok(Endpoints {
+                authorize: "https://github.com/login/oauth/authorize".to_string(),
+                token: "https://github.com/login/oauth/access_token".to_string(),
+                userinfo: "https://api.github.com/user".to_string(),
+            }),
+            Kind::Oidc { issuer, endpoints } => endpoints
+                .get_or_try_init(|| async {
+                    let url = format!("{}/.well-known/openid-configuration", issuer);
+                    client
+                        .get(url)
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status())
+                        .map_err(oauth_error)?
+                        .json::<Endpoints>()
+                        .await
+                        .map_err(oauth_error)
+                })
+                .await
+                .cloned(),
+        }
+    }
+
+    /// Fetch the signed-in user's profile with an access token.
+    async fn profile(
+        &self,
+        client: &reqwest::Client,
+        endpoints: &Endpoints,
+        token: &str,
+    ) -> acacia_core::Result<OAuthProfile> {
+        let raw: serde_json::Value = client
+            .get(&endpoints.userinfo)
+            .bearer_auth(token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(oauth_error)?
+            .json()
+            .await
+            .map_err(oauth_error)?;
+        let text = |key: &str| {
+            raw.get(key)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        };
+
+        let profile = match self.kind {
+            Kind::GitHub => {
+                // The public profile omits private addresses
+                let email = match text("email") {
+                    Some(email) => Some(email),
+                    None => github_email(client, token).await?,
+                };
+                OAuthProfile {
+                    provider: self.name.clone(),
+                    id: raw.get("id").map(|id| id.to_string()).unwrap_or_default(),
+                    email,
+                    name: text("name").or_else(|| text("login")),
+                    avatar_url: text("avatar_url"),
+                    raw: raw.clone(),
+                }
+            }
+            Kind::Oidc { .. } => {
+                // Unverified or unreported addresses could belong to someone
+                // else. Some providers send the claim as a string.
+                let verified = raw.get("email_verified").is_some_and(|value| {
+                    value
+                        .as_bool()
+                        .unwrap_or_else(|| value.as_str() == Some("true"))
+                });
+                OAuthProfile {
+                    provider: self.name.clone(),
+                    id: text("sub").unwrap_or_default(),
+                    email: text("email").filter(|_| verified),
+                    name: text("name"),
+                    avatar_url: text("picture"),
+                    raw: raw.clone(),
+                }
+            }
+        };
+        if profile.id.is_empty() {
+            return Err(AppError::Internal(format!(
+                "{} returned a profile without an id",
+                self.name
+            )));
+        }
+        Ok(profile)
+    }
+
+    /// The callback URL for this request.
+    fn callback_url(&self, parts: &Parts) -> String {
+        if let Some(url) = &self.redirect_url {
+            return url.clone();
+        }
+        let header = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let scheme = header("x-forwarded-proto").unwrap_or("http");
+        let host = header("host").unwrap_or("localhost");
+        format!("{}://{}/auth/{}/callback", scheme, host, self.name)
+    }
+}
+
+/// The primary verified address from GitHub's email API.
+async fn github_email(
+    client: &reqwest::Client,
+    token: &str,
+) -> acacia_core::Result<Option<String>> {
+    #[derive(Deserialize)]
+    struct GitHubEmail {
+        email: String,
+        primary: bool,
+        verified: bool,
+    }
+
+    let emails: Vec<GitHubEmail> = client
+        .get("https://api.github.com/user/emails")
+        .bearer_auth(token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(oauth_error)?
+        .json()
+        .await
+        .map_err(oauth_error)?;
+    Ok(emails
+        .into_iter()
+        .find(|email| email.primary && email.verified)
+        .map(|email| email.email))
+}
+
+fn oauth_error(err: reqwest::Error) -> AppError {
+    AppError::Internal(format!("OAuth request failed: {}", err))
+}
+
+/// The signed-in user as reported by the provider, extracted in the login handler.
+///
+/// `email` is only set when the provider has verified it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OAuthProfile {
+    /// Provider name, e.g. `"github"`.
+    pub provider: String,
+    /// The user's id at the provider, stable across logins.
+    pub id: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
+    /// The full user info response.
+    pub raw: serde_json::Value,
+}
+
+/// The signed-in user, under its name before the core `Profile` took it.
+#[deprecated(note = "renamed to `OAuthProfile`")]
+pub type Profile = OAuthProfile;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for OAuthProfile
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<OAuthProfile>()
+            .cloned()
+            .ok_or(AppError::Unauthorized)
+    }
+}
+
+/// Registered providers and the handler run after a successful login.
+#[derive(Clone, Default)]
+pub struct OAuth {
+    providers: HashMap<String, Provider>,
+    login: Option<MethodRouter<AppState>>,
+}
+
+impl OAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, provider: Provider) {
+        self.providers.insert(provider.name.clone(), provider);
+    }
+
+    /// Run `login` after the provider confirms the user; it can extract [`OAuthProfile`].
+    pub fn login(&mut self, login: MethodRouter<AppState>) {
+        self.login = Some(login);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// `/auth/{provider}` and `/auth/{provider}/callback` for every provider.
+    ///
+    /// Needs sessions to carry the `state` parameter between the two.
+    pub fn router(self) -> Router<AppState> {
+        let providers = Arc::new(self.providers);
+        let client = reqwest::Client::builder()
+            .user_agent("acacia")
+            .build()
+            .expect("Failed to build HTTP client");
+        let login = self.login.unwrap_or_else(|| get(store_profile));
+
+        let (start_providers, start_client) = (providers.clone(), client.clone());
+        Router::new()
+            .route(
+                "/auth/:provider",
+                get(move |path: Path<String>, session: Session, parts: Parts| {
+                    start(
+                        start_providers.clone(),
+                        start_client.clone(),
+                        path,
+                        session,
+                        parts,
+                    )
+                }),
+            )
+            .route(
+                "/auth/:provider/callback",
+                login.layer(axum::middleware::from_fn(move |req, next| {
+                    callback(providers.clone(), client.clone(), req, next)
+                })),
+            )
+    }
+}
+
+fn provider<'a>(
+    providers: &'a HashMap<String, Provider>,
+    name: &str,
+) -> acacia_core::Result<&'a Provider> {
+    providers.get(name).ok_or(AppError::NotFound)
+}
+
+#[derive(Serialize, Deserialize)]
+struct PendingLogin {
+    provider: String,
+    state: String,
+}
+
+/// Redirect to the provider's consent screen.
+async fn start(
+    providers: Arc<HashMap<String, Provider>>,
+    client: reqwest::Client,
+    Path(name): Path<String>,
+    session: Session,
+    parts: Parts,
+) -> acacia_core::Result<Redirect> {
+    let provider = provider(&providers, &name)?;
+    let endpoints = provider.endpoints(&client).await?;
+
+    let state = crate::session::new_id();
+    session.insert(
+        STATE_KEY,
+        &PendingLogin {
+            provider: name,
+            state: state.clone(),
+        },
+    )?;
+
+    let query = serde_urlencoded::to_string([
+        ("response_type", "code"),
+        ("client_id", provider.client_id.as_str()),
+        ("redirect_uri", provider.callback_url(&parts).as_str()),
+        ("scope", provider.scopes.join(" ").as_str()),
+        ("state", state.as_str()),
+    ])
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(Redirect::to(&format!("{}?{}", endpoints.authorize, query)))
+}
+
+#[derive(Deserialize)]
+struct CallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Check the callback, exchange the code and hand the profile to the login handler.
+async fn callback(
+    providers: Arc<HashMap<String, Provider>>,
+    client: reqwest::Client,
+    req: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = req.into_parts();
+    match verify(&providers, &client, &mut parts).await {
+        Ok(profile) => {
+            parts.extensions.insert(profile);
+            next.run(Request::from_parts(parts, body)).await
+        }
+        Err(err) => err.into_response(),
+    }
+}
+
+async fn verify(
+    providers: &HashMap<String, Provider>,
+    client: &reqwest::Client,
+    parts: &mut Parts,
+) -> acacia_core::Result<OAuthProfile> {
+    let Path(name) = Path::<String>::from_request_parts(parts, &())
+        .await
+        .map_err(|_| AppError::NotFound)?;
+    let provider = provider(providers, &name)?;
+    let Query(params) = Query::<CallbackParams>::from_request_parts(parts, &())
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let session = Session::from_request_parts(parts, &()).await?;
+
+    if let Some(error) = params.error {
+        return Err(AppError::BadRequest(format!(
+            "{} login failed: {}",
+            provider.name, error
+        )));
+    }
+
+    // The state must match the login this browser started
+    let pending: Option<PendingLogin> = session.get(STATE_KEY);
+    session.remove(STATE_KEY);
+    let matches = pending.is_some_and(|pending| {
+        pending.provider == name && Some(pending.state.as_str()) == params.state.as_deref()
+    });
+    if !matches {
+        return Err(AppError::BadRequest("Invalid OAuth state".to_string()));
+    }
+    let code = params
+        .code
+        .ok_or_else(|| AppError::BadRequest("Missing OAuth code".to_string()))?;
+
+    let endpoints = provider.endpoints(client).await?;
+    let redirect_uri = provider.callback_url(parts);
+    let token: TokenResponse = client
+        .post(&endpoints.token)
+        .header(http::header::ACCEPT, "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("client_secret", provider.client_secret.as_str()),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(oauth_error)?
+        .json()
+        .await
+        .map_err(oauth_error)?;
+
+    // A fresh session id guards against fixation
+    session.regenerate();
+    provider
+        .profile(client, &endpoints, &token.access_token)
+        .await
+}
+
+/// Default login handler: keep the profile in the session and go home.
+async fn store_profile(session: Session, profile: OAuthProfile) -> acacia_core::Result<Redirect> {
+    session.insert(PROFILE_KEY, &profile)?;
+    Ok(Redirect::to("/"))
+}
@@ -0,0 +1,466 @@
+//! Cookie sessions backed by a pluggable [`SessionStore`].
+
+use acacia_core::{AppError, Cookie, Cookies, SameSite};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::request::Parts,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rand::RngCore;
+use sea_orm::sea_query::{ColumnDef, Expr, ExprTrait, OnConflict, Query, Table};
+use sea_orm::{ConnectionTrait, DatabaseConnection};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Values stored in a session, keyed by name.
+pub type SessionData = HashMap<String, serde_json::Value>;
+
+/// Session storage error.
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Session store error: {0}")]
+    Store(String),
+
+    #[error("Invalid session data: {0}")]
+    Data(#[from] serde_json::Error),
+}
+
+impl From<SessionError> for AppError {
+    fn from(err: SessionError) -> Self {
+        AppError::Internal(err.to_string())
+    }
+}
+
+/// A backend that keeps session data between requests.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Prepare the store once at startup, e.g. by creating its table.
+    ///
+    /// Receives the application's database connection, if one is configured.
+    async fn init(&self, _db: Option<&DatabaseConnection>) -> Result<(), SessionError> {
+        Ok(())
+    }
+
+    /// Load an unexpired session.
+    async fn load(&self, id: &str) -> Result<Option<SessionData>, SessionError>;
+
+    /// Create or replace a session, expiring after `ttl`.
+    async fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> Result<(), SessionError>;
+
+    async fn destroy(&self, id: &str) -> Result<(), SessionError>;
+}
+
+/// Keeps sessions in process memory; they are lost on restart.
+#[derive(Default)]
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, (SessionData, SystemTime)>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemoryStore {
+    async fn load(&self, id: &str) -> Result<Option<SessionData>, SessionError> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let now = SystemTime::now();
+        Ok(sessions
+            .get(id)
+            .filter(|(_, expires)| *expires > now)
+            .map(|(data, _)| data.clone()))
+    }
+
+    async fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> Result<(), SessionError> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let now = SystemTime::now();
+        sessions.insert(id.to_string(), (data.clone(), now + ttl));
+
+        // Sweep expired sessions now and then
+        if rand::random::<u8>() == 0 {
+            sessions.retain(|_, (_, expires)| *expires > now);
+        }
+        Ok(())
+    }
+
+    async fn destroy(&self, id: &str) -> Result<(), SessionError> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.remove(id);
+        Ok(())
+    }
+}
+
+/// Keeps sessions in an `acacia_sessions` table of the application database.
+///
+/// The table is created at startup, so `Acacia::database` must be set.
+#[derive(Default)]
+pub struct DbStore {
+    conn: OnceLock<DatabaseConnection>,
+}
+
+impl DbStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn conn(&self) -> Result<&DatabaseConnection, SessionError> {
+        self.conn
+            .get()
+            .ok_or_else(|| SessionError::Store("DbStore used before Acacia::serve".to_string()))
+    }
+}
+
+const TABLE: &str = "acacia_sessions";
+
+pub(crate) fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn store_error(err: sea_orm::DbErr) -> SessionError {
+    SessionError::Store(err.to_string())
+}
+
+#[async_trait]
+impl SessionStore for DbStore {
+    async fn init(&self, db: Option<&DatabaseConnection>) -> Result<(), SessionError> {
+        let db = db.ok_or_else(|| {
+            SessionError::Store("DbStore needs a database; call Acacia::database".to_string())
+        })?;
+        let create = Table::create()
+            .table(TABLE)
+            .if_not_exists()
+            .col(ColumnDef::new("id").string().not_null().primary_key())
+            .col(ColumnDef::new("data").text().not_null())
+            .col(ColumnDef::new("expires_at").big_integer().not_null())
+            .to_owned();
+        db.execute(&create).await.map_err(store_error)?;
+        let _ = self.conn.set(db.clone());
+        Ok(())
+    }
+
+    async fn load(&self, id: &str) -> Result<Option<SessionData>, SessionError> {
+        let db = self.conn()?;
+        let select = Query::select()
+            .column("data")
+            .from(TABLE)
+            .and_where(Expr::col("id").eq(id))
+            .and_where(Expr::col("expires_at").gt(now_secs()))
+            .to_owned();
+        let Some(row) = db.query_one(&select).await.map_err(store_error)? else {
+            return Ok(None);
+        };
+        let data: String = row.try_get("", "data").map_err(store_error)?;
+        Ok(Some(serde_json::from_str(&data)?))
+    }
+
+    async fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> Result<(), SessionError> {
+        let db = self.conn()?;
+        let expires_at = now_secs() + ttl.as_secs() as i64;
+        let insert = Query::insert()
+            .into_table(TABLE)
+            .columns(["id", "data", "expires_at"])
+            .values_panic([
+                id.into(),
+                serde_json::to_string(data)?.into(),
+                expires_at.into(),
+            ])
+            .on_conflict(
+                OnConflict::column("id")
+                    .update_columns(["data", "expires_at"])
+                    .to_owned(),
+            )
+            .to_owned();
+        db.execute(&insert).await.map_err(store_error)?;
+
+        // Sweep expired sessions now and then
+        if rand::random::<u8>() == 0 {
+            let sweep = Query::delete()
+                .from_table(TABLE)
+                .and_where(Expr::col("expires_at").lte(now_secs()))
+                .to_owned();
+            db.execute(&sweep).await.map_err(store_error)?;
+        }
+        Ok(())
+    }
+
+    async fn destroy(&self, id: &str) -> Result<(), SessionError> {
+        let delete = Query::delete()
+            .from_table(TABLE)
+            .and_where(Expr::col("id").eq(id))
+            .to_owned();
+        self.conn()?.execute(&delete).await.map_err(store_error)?;
+        Ok(())
+    }
+}
+
+/// Keeps sessions in Redis, which expires them itself.
+#[cfg(feature = "redis")]
+#[derive(Clone)]
+pub struct RedisStore {
+    conn: redis::aio::ConnectionManager,
+}
+
+#[cfg(feature = "redis")]
+impl RedisStore {
+    /// Connect to the Redis server at `url`, e.g. `redis://127.0.0.1/`.
+    pub async fn connect(url: &str) -> Result<Self, SessionError> {
+        let client = redis::Client::open(url).map_err(redis_error)?;
+        let conn = redis::aio::ConnectionManager::new(client)
+            .await
+            .map_err(redis_error)?;
+        Ok(Self { conn })
+    }
+
+    fn key(id: &str) -> String {
+        format!("acacia:session:{}", id)
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_error(err: redis::RedisError) -> SessionError {
+    SessionError::Store(err.to_string())
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl SessionStore for RedisStore {
+    async fn load(&self, id: &str) -> Result<Option<SessionData>, SessionError> {
+        let data: Option<String> = redis::AsyncCommands::get(&mut self.conn.clone(), Self::key(id))
+            .await
+            .map_err(redis_error)?;
+        data.map(|data| serde_json::from_str(&data))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    async fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> Result<(), SessionError> {
+        let data = serde_json::to_string(data)?;
+        redis::AsyncCommands::set_ex::<_, _, ()>(
+            &mut self.conn.clone(),
+            Self::key(id),
+            data,
+            std::cmp::max(ttl.as_secs(), 1),
+        )
+        .await
+        .map_err(redis_error)
+    }
+
+    async fn destroy(&self, id: &str) -> Result<(), SessionError> {
+        redis::AsyncCommands::del::<_, ()>(&mut self.conn.clone(), Self::key(id))
+            .await
+            .map_err(redis_error)
+    }
+}
+
+/// Session settings for `Acacia::sessions`.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new()
+///     .database("sqlite://app.db?mode=rwc")
+///     .sessions(SessionConfig {
+///         store: Arc::new(DbStore::new()),
+///         ttl: Duration::from_secs(30 * 24 * 60 * 60),
+///         cookie_name: "app_session".into(),
+///         ..SessionConfig::default()
+///     })
+/// ```
+#[derive(Clone)]
+pub struct SessionConfig {
+    pub store: Arc<dyn SessionStore>,
+    /// How long a session lives after its last change.
+    pub ttl: Duration,
+    pub cookie_name: String,
+    /// Whether the cookie is only sent over HTTPS. `None` marks it `Secure`
+    /// in the Prod profile.
+    pub secure: Option<bool>,
+}
+
+/// In-memory sessions lasting a week, in an `acacia_session` cookie.
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            store: Arc::new(MemoryStore::new()),
+            ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            cookie_name: "acacia_session".to_string(),
+            secure: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct SessionState {
+    id: Option<String>,
+    data: SessionData,
+    changed: bool,
+    /// The previous id, to destroy after `regenerate` or `destroy`.
+    stale: Option<String>,
+}
+
+/// The current visitor's session.
+///
+/// Changes are saved when the response is sent; a session is only stored
+/// (and its cookie set) once something is inserted.
+///
+/// # Example
+/// ```ignore
+/// #[action("/cart", method = "POST")]
+/// async fn add_to_cart(session: Session, form: Valid<AddItem>) -> Result<Fragment> {
+///     let mut cart: Vec<i32> = session.get("cart").unwrap_or_default();
+///     cart.push(form.item_id);
+///     session.insert("cart", &cart)?;
+///     Ok(CartCount(cart.len()))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Session(Arc<Mutex<SessionState>>);
+
+impl Session {
+    fn state(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.state().data.get(key)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    pub fn insert<T: Serialize>(&self, key: &str, value: &T) -> Result<(), SessionError> {
+        let value = serde_json::to_value(value)?;
+        let mut state = self.state();
+        state.data.insert(key.to_string(), value);
+        state.changed = true;
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &str) {
+        let mut state = self.state();
+        if state.data.remove(key).is_some() {
+            state.changed = true;
+        }
+    }
+
+    /// Move the data to a new session id; call after logging in to prevent session fixation.
+    pub fn regenerate(&self) {
+        let mut state = self.state();
+        if state.stale.is_none() {
+            state.stale = state.id.take();
+        }
+        state.id = None;
+        state.changed = true;
+    }
+
+    /// Delete the session and its cookie, e.g. when logging out.
+    pub fn destroy(&self) {
+        let mut state = self.state();
+        if state.stale.is_none() {
+            state.stale = state.id.take();
+        }
+        state.id = None;
+        state.data.clear();
+        state.changed = true;
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Session
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        parts.extensions.get::<Session>().cloned().ok_or_else(|| {
+            AppError::Internal("Sessions are not enabled; call Acacia::sessions".to_string())
+        })
+    }
+}
+
+pub(crate) fn new_id() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl SessionConfig {
+    /// Middleware that loads the session before the handler and saves it after.
+    pub async fn middleware(self, mut req: Request, next: Next) -> Response {
+        let cookie = Cookies::from_headers(req.headers())
+            .get(&self.cookie_name)
+            .map(str::to_string);
+        let mut state = SessionState::default();
+        if let Some(id) = cookie {
+            match self.store.load(&id).await {
+                Ok(Some(data)) => {
+                    state.id = Some(id);
+                    state.data = data;
+                }
+                Ok(None) => {}
+                Err(err) => return AppError::from(err).into_response(),
+            }
+        }
+
+        let session = Session(Arc::new(Mutex::new(state)));
+        req.extensions_mut().insert(session.clone());
+        let mut response = next.run(req).await;
+
+        let (id, data, stale) = {
+            let mut state = session.state();
+            if !state.changed {
+                return response;
+            }
+            (
+                state.id.take(),
+                std::mem::take(&mut state.data),
+                state.stale.take(),
+            )
+        };
+
+        if let Some(stale) = stale {
+            if let Err(err) = self.store.destroy(&stale).await {
+                return AppError::from(err).into_response();
+            }
+        }
+
+        let cookie = if data.is_empty() {
+            if let Some(id) = &id {
+                if let Err(err) = self.store.destroy(id).await {
+                    return AppError::from(err).into_response();
+                }
+            }
+            Cookie::remove(&self.cookie_name)
+        } else {
+            let id = id.unwrap_or_else(new_id);
+            if let Err(err) = self.store.save(&id, &data, self.ttl).await {
+                return AppError::from(err).into_response();
+            }
+            Cookie::build(&self.cookie_name, id).max_age(self.ttl)
+        };
+
+        let secure = self
+            .secure
+            .unwrap_or_else(|| acacia_core::profile().is_prod());
+        let cookie = cookie
+            .http_only(true)
+            .secure(secure)
+            .same_site(SameSite::Lax);
+        if let Ok(value) = http::HeaderValue::from_str(&cookie.to_string()) {
+            response
+                .headers_mut()
+                .append(http::header::SET_COOKIE, value);
+        }
+        response
+    }
+}
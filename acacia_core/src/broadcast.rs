@@ -0,0 +1,203 @@
+//! Topic-based broadcasting of fragments to connected browsers.
+//!
+//! Elements marked with [`live`] open a server-sent events stream for their
+//! topic; every fragment passed to [`broadcast`] is swapped into them.
+//!
+//! The stream URL carries a signature of the topic, so browsers can only
+//! subscribe to topics a page rendered for them.
+
+use crate::{Fragment, Swap};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::task::{Context, Poll};
+use tokio::sync::broadcast::{self, Sender};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::Stream;
+
+/// Buffered messages per topic before slow subscribers start skipping.
+const CAPACITY: usize = 64;
+
+fn topics() -> &'static Mutex<HashMap<String, Sender<String>>> {
+    static TOPICS: OnceLock<Mutex<HashMap<String, Sender<String>>>> = OnceLock::new();
+    TOPICS.get_or_init(Default::default)
+}
+
+/// The key topics are signed with, random per process unless set.
+static SECRET: RwLock<Vec<u8>> = RwLock::new(Vec::new());
+
+/// Set the key `live` signs topics with. Called by `Acacia::serve` with
+/// `Acacia::live_secret`, so that every instance of an app accepts the others' links.
+pub fn set_secret(secret: &[u8]) {
+    *SECRET.write().unwrap_or_else(|e| e.into_inner()) = secret.to_vec();
+}
+
+/// HMAC-SHA256 of `topic`, as hex.
+fn sign(topic: &str) -> String {
+    const BLOCK: usize = 64;
+    let mut key = {
+        let secret = SECRET.read().unwrap_or_else(|e| e.into_inner());
+        secret.clone()
+    };
+    if key.is_empty() {
+        let mut secret = SECRET.write().unwrap_or_else(|e| e.into_inner());
+        if secret.is_empty() {
+            let mut random = vec![0u8; 32];
+            rand::rngs::OsRng.fill_bytes(&mut random);
+            *secret = random;
+        }
+        key = secret.clone();
+    }
+    if key.len() > BLOCK {
+        key = Sha256::digest(&key).to_vec();
+    }
+    key.resize(BLOCK, 0);
+
+    let pad = |byte: u8| key.iter().map(|k| k ^ byte).collect::<Vec<u8>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(topic.as_bytes())
+        .finalize();
+    let outer = Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize();
+    outer.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Whether `signature` is the one [`live`] rendered for `topic`.
+pub fn verify(topic: &str, signature: &str) -> bool {
+    let expected = sign(topic);
+    // Compare every byte, so the time taken doesn't reveal a matching prefix
+    expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Send a fragment to every subscriber of `topic`.
+///
+/// # Example
+/// ```ignore
+/// #[action("/tasks", method = "POST")]
+/// async fn create_task(db: Db, form: Valid<NewTask>) -> Result<Fragment> {
+///     let task = db.insert::<Task, _>(form.into_inner()).await?;
+///     broadcast("tasks", TaskItem(&task));
+///     Ok(Fragment::empty())
+/// }
+/// ```
+pub fn broadcast(topic: &str, fragment: Fragment) {
+    let mut topics = topics().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(sender) = topics.get(topic) {
+        // Sending only fails once everyone has disconnected
+        if sender.send(fragment.0).is_err() {
+            topics.remove(topic);
+        }
+    }
+}
+
+/// Receive the fragments broadcast to `topic` from now on.
+pub fn subscribe(topic: &str) -> Subscription {
+    let mut topics = topics().lock().unwrap_or_else(|e| e.into_inner());
+    let receiver = topics
+        .entry(topic.to_string())
+        .or_insert_with(|| broadcast::channel(CAPACITY).0)
+        .subscribe();
+    Subscription {
+        topic: topic.to_string(),
+        stream: Some(BroadcastStream::new(receiver)),
+    }
+}
+
+/// The fragments broadcast to a topic, from [`subscribe`].
+///
+/// Subscribers that fall behind skip the messages they missed. The topic is
+/// forgotten once its last subscription is dropped.
+pub struct Subscription {
+    topic: String,
+    stream: Option<BroadcastStream<String>>,
+}
+
+impl Stream for Subscription {
+    type Item = String;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<String>> {
+        let Some(stream) = self.stream.as_mut() else {
+            return Poll::Ready(None);
+        };
+        loop {
+            match Pin::new(&mut *stream).poll_next(cx) {
+                Poll::Ready(Some(Err(_lagged))) => continue,
+                Poll::Ready(Some(Ok(html))) => return Poll::Ready(Some(html)),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Drop the receiver first, so it no longer counts
+        self.stream.take();
+        let mut topics = topics().lock().unwrap_or_else(|e| e.into_inner());
+        if topics
+            .get(&self.topic)
+            .is_some_and(|sender| sender.receiver_count() == 0)
+        {
+            topics.remove(&self.topic);
+        }
+    }
+}
+
+/// Attributes subscribing an element to a topic, created with [`live`].
+#[derive(Clone, Debug)]
+pub struct Live {
+    topic: String,
+    swap: Swap,
+}
+
+impl Live {
+    /// How broadcast fragments are swapped in (default: appended).
+    pub fn swap(mut self, swap: Swap) -> Self {
+        self.swap = swap;
+        self
+    }
+}
+
+impl fmt::Display for Live {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "data-acacia-live=\"/__acacia__/live/{}?sig={}\" data-acacia-swap=\"{}\"",
+            html_escape::encode_double_quoted_attribute(&self.topic),
+            sign(&self.topic),
+            self.swap
+        )
+    }
+}
+
+/// Swap fragments broadcast to `topic` into this element as they arrive.
+///
+/// Only pages that render `live(topic)` can subscribe to it, so a topic like
+/// `user:42` is private to the pages that show it.
+///
+/// # Example
+/// ```ignore
+/// html! {
+///     <ul id="tasks" {live("tasks")}>
+///         {for task in &tasks { TaskItem(task) }}
+///     </ul>
+/// }
+/// ```
+pub fn live(topic: &str) -> Live {
+    Live {
+        topic: topic.to_string(),
+        swap: Swap::BeforeEnd,
+    }
+}
@@ -0,0 +1,91 @@
+//! Per-request values readable from any component with [`ctx`].
+
+use std::cell::RefCell;
+
+/// Values about the current request for components deep in the tree, like a
+/// navigation bar showing the signed-in user, without threading props.
+///
+/// Acacia fills in `htmx` and `locale`; the rest is set by the hook given to
+/// `Acacia::context`, or with [`update_ctx`] from handlers and extractors.
+///
+/// # Example
+/// ```ignore
+/// #[component]
+/// fn UserMenu() -> Fragment {
+///     match ctx().user {
+///         Some(name) => html! { <span>"Signed in as " {name}</span> },
+///         None => html! { <a href="/login">"Sign in"</a> },
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RequestContext {
+    /// The display name of the signed-in user.
+    pub user: Option<String>,
+    /// The preferred language, from the first tag of `Accept-Language`.
+    pub locale: Option<String>,
+    /// Messages to show once, like "Post saved".
+    pub flash: Vec<String>,
+    /// Whether the request was made by htmx.
+    pub htmx: bool,
+    extensions: http::Extensions,
+}
+
+impl RequestContext {
+    /// The context for a request with these headers, before any hook runs.
+    pub fn from_headers(headers: &http::HeaderMap) -> Self {
+        let locale = headers
+            .get(http::header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split([',', ';']).next())
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty() && *tag != "*")
+            .map(str::to_string);
+        Self {
+            locale,
+            htmx: headers.contains_key("HX-Request"),
+            ..Self::default()
+        }
+    }
+
+    /// A value of the app's own type, e.g. the full user record.
+    pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.extensions.get::<T>().cloned()
+    }
+
+    /// Store a value for [`RequestContext::get`], replacing one of the same type.
+    pub fn insert<T: Clone + Send + Sync + 'static>(&mut self, value: T) {
+        self.extensions.insert(value);
+    }
+}
+
+tokio::task_local! {
+    static CONTEXT: RefCell<RequestContext>;
+}
+
+/// The current request's context, or an empty one outside a request.
+pub fn ctx() -> RequestContext {
+    CONTEXT
+        .try_with(|context| context.borrow().clone())
+        .unwrap_or_default()
+}
+
+/// Change the current request's context; does nothing outside a request.
+///
+/// # Example
+/// ```ignore
+/// #[action("/posts", method = "POST")]
+/// async fn create_post(form: Valid<NewPost>, db: Db) -> Result<Fragment> {
+///     let post = db.insert::<Post, _>(form.into_inner()).await?;
+///     update_ctx(|ctx| ctx.flash.push("Post saved".into()));
+///     Ok(PostList(&db.all::<Post>().await?))
+/// }
+/// ```
+pub fn update_ctx(f: impl FnOnce(&mut RequestContext)) {
+    let _ = CONTEXT.try_with(|context| f(&mut context.borrow_mut()));
+}
+
+/// Run `future` with `context` as the request's context. Called by `Acacia::serve`.
+pub async fn with_context<F: std::future::Future>(context: RequestContext, future: F) -> F::Output {
+    CONTEXT.scope(RefCell::new(context), future).await
+}
@@ -0,0 +1,118 @@
+//! HTML rendering for chrono date and time types.
+
+use crate::{escape_html, RenderHtml};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use std::fmt::Display;
+use std::sync::RwLock;
+
+static DATE_FORMAT: RwLock<&str> = RwLock::new("%Y-%m-%d");
+static DATETIME_FORMAT: RwLock<&str> = RwLock::new("%Y-%m-%d %H:%M");
+static TIME_FORMAT: RwLock<&str> = RwLock::new("%H:%M");
+
+/// Set the format used when rendering a `NaiveDate` (default `%Y-%m-%d`).
+///
+/// # Example
+/// ```ignore
+/// set_date_format("%d %B %Y");
+/// ```
+pub fn set_date_format(format: &'static str) {
+    *DATE_FORMAT.write().unwrap_or_else(|e| e.into_inner()) = format;
+}
+
+/// Set the format used when rendering a `NaiveDateTime` or `DateTime` (default `%Y-%m-%d %H:%M`).
+pub fn set_datetime_format(format: &'static str) {
+    *DATETIME_FORMAT.write().unwrap_or_else(|e| e.into_inner()) = format;
+}
+
+/// Set the format used when rendering a `NaiveTime` (default `%H:%M`).
+pub fn set_time_format(format: &'static str) {
+    *TIME_FORMAT.write().unwrap_or_else(|e| e.into_inner()) = format;
+}
+
+fn current(format: &RwLock<&'static str>) -> &'static str {
+    *format.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Date and time values that can be formatted with a strftime pattern.
+pub trait FormatDate {
+    /// The configured format for this type.
+    fn default_format() -> &'static str;
+
+    fn format_with(&self, format: &str) -> String;
+}
+
+impl FormatDate for NaiveDate {
+    fn default_format() -> &'static str {
+        current(&DATE_FORMAT)
+    }
+
+    fn format_with(&self, format: &str) -> String {
+        self.format(format).to_string()
+    }
+}
+
+impl FormatDate for NaiveDateTime {
+    fn default_format() -> &'static str {
+        current(&DATETIME_FORMAT)
+    }
+
+    fn format_with(&self, format: &str) -> String {
+        self.format(format).to_string()
+    }
+}
+
+impl FormatDate for NaiveTime {
+    fn default_format() -> &'static str {
+        current(&TIME_FORMAT)
+    }
+
+    fn format_with(&self, format: &str) -> String {
+        self.format(format).to_string()
+    }
+}
+
+impl<Tz: TimeZone> FormatDate for DateTime<Tz>
+where
+    Tz::Offset: Display,
+{
+    fn default_format() -> &'static str {
+        current(&DATETIME_FORMAT)
+    }
+
+    fn format_with(&self, format: &str) -> String {
+        self.format(format).to_string()
+    }
+}
+
+/// Format a date or time with an explicit strftime pattern.
+///
+/// # Example
+/// ```ignore
+/// html! { <span>{format_date(&task.created_at, "%b %e")}</span> }
+/// ```
+pub fn format_date<T: FormatDate>(value: &T, format: &str) -> String {
+    value.format_with(format)
+}
+
+macro_rules! render_with_default_format {
+    ($($ty:ty),*) => {
+        $(
+            impl RenderHtml for $ty {
+                fn render_html(&self) -> String {
+                    escape_html(&self.format_with(<$ty>::default_format()))
+                }
+            }
+        )*
+    };
+}
+
+render_with_default_format!(NaiveDate, NaiveDateTime, NaiveTime);
+
+impl<Tz: TimeZone> RenderHtml for DateTime<Tz>
+where
+    Tz::Offset: Display,
+{
+    fn render_html(&self) -> String {
+        escape_html(&self.format_with(Self::default_format()))
+    }
+}
@@ -0,0 +1,324 @@
+//! Deserializer for submitted form data, including multipart file uploads.
+//!
+//! Values arrive as text (or files), so scalars are parsed on demand. Errors
+//! remember which field they came from so they can be shown next to the input.
+
+use crate::Upload;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+
+/// One submitted value: plain text or an uploaded file.
+#[derive(Clone, Debug)]
+pub(crate) enum FormValue {
+    Text(String),
+    File(Upload),
+}
+
+/// A deserialization failure, tagged with the field it occurred in.
+#[derive(Debug)]
+pub(crate) struct Error {
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl Error {
+    fn in_field(mut self, field: &str) -> Self {
+        if self.field.is_none() {
+            self.field = Some(field.to_string());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{}: {}", field, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error {
+            field: None,
+            message: msg.to_string(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error {
+            field: Some(field.to_string()),
+            message: "is required".to_string(),
+        }
+    }
+}
+
+/// Deserialize a form from its submitted values.
+///
+/// Repeated names are grouped, so `Vec<T>` fields collect every value.
+pub(crate) fn from_values<T: de::DeserializeOwned>(
+    values: Vec<(String, FormValue)>,
+) -> Result<T, Error> {
+    let mut fields: Vec<(String, Vec<FormValue>)> = Vec::new();
+    for (name, value) in values {
+        match fields.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, group)) => group.push(value),
+            None => fields.push((name, vec![value])),
+        }
+    }
+
+    T::deserialize(FormDeserializer { fields })
+}
+
+struct FormDeserializer {
+    fields: Vec<(String, Vec<FormValue>)>,
+}
+
+impl<'de> de::Deserializer<'de> for FormDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(FieldsAccess {
+            fields: self.fields.into_iter(),
+            current: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct FieldsAccess {
+    fields: std::vec::IntoIter<(String, Vec<FormValue>)>,
+    current: Option<(String, Vec<FormValue>)>,
+}
+
+impl<'de> MapAccess<'de> for FieldsAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.fields.next() {
+            Some((name, values)) => {
+                let key = seed.deserialize(name.clone().into_deserializer())?;
+                self.current = Some((name, values));
+                Ok(Some(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (name, values) = self
+            .current
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(ValueDeserializer { values })
+            .map_err(|e| e.in_field(&name))
+    }
+}
+
+/// All values submitted under one name.
+struct ValueDeserializer {
+    values: Vec<FormValue>,
+}
+
+impl ValueDeserializer {
+    /// The last value, used when a scalar is expected.
+    fn last(self) -> FormValue {
+        self.values
+            .into_iter()
+            .last()
+            .unwrap_or(FormValue::Text(String::new()))
+    }
+
+    fn text(self) -> Result<String, Error> {
+        match self.last() {
+            FormValue::Text(text) => Ok(text),
+            FormValue::File(_) => Err(de::Error::custom("must be text, not a file")),
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(self, expected: &str) -> Result<T, Error> {
+        let text = self.text()?;
+        text.trim()
+            .parse()
+            .map_err(|_| de::Error::custom(format!("must be {}", expected)))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident, $expected:literal;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.$visit(self.parse($expected)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.values.len() > 1 {
+            return self.deserialize_seq(visitor);
+        }
+        match self.last() {
+            FormValue::Text(text) => visitor.visit_string(text),
+            FormValue::File(upload) => visitor.visit_map(UploadAccess::new(upload)),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8, "an integer";
+        deserialize_i16 => visit_i16, "an integer";
+        deserialize_i32 => visit_i32, "an integer";
+        deserialize_i64 => visit_i64, "an integer";
+        deserialize_i128 => visit_i128, "an integer";
+        deserialize_u8 => visit_u8, "a positive integer";
+        deserialize_u16 => visit_u16, "a positive integer";
+        deserialize_u32 => visit_u32, "a positive integer";
+        deserialize_u64 => visit_u64, "a positive integer";
+        deserialize_u128 => visit_u128, "a positive integer";
+        deserialize_f32 => visit_f32, "a number";
+        deserialize_f64 => visit_f64, "a number";
+        deserialize_char => visit_char, "a single character";
+        deserialize_bool => visit_bool, "true or false";
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.text()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.text()?)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.text()?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Empty inputs submit an empty string, which means "no value"
+        let empty = self.values.iter().all(|value| match value {
+            FormValue::Text(text) => text.is_empty(),
+            FormValue::File(_) => false,
+        });
+        if empty {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(ValuesAccess {
+            values: self.values.into_iter(),
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.text()?.into_deserializer())
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit_struct tuple tuple_struct map struct
+    }
+}
+
+struct ValuesAccess {
+    values: std::vec::IntoIter<FormValue>,
+}
+
+impl<'de> SeqAccess<'de> for ValuesAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.values.next() {
+            Some(value) => seed
+                .deserialize(ValueDeserializer {
+                    values: vec![value],
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Presents an upload as a map of `filename`, `content_type` and `bytes`.
+struct UploadAccess {
+    upload: Upload,
+    index: usize,
+}
+
+impl UploadAccess {
+    const KEYS: [&'static str; 3] = ["filename", "content_type", "bytes"];
+
+    fn new(upload: Upload) -> Self {
+        Self { upload, index: 0 }
+    }
+}
+
+impl<'de> MapAccess<'de> for UploadAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match Self::KEYS.get(self.index) {
+            Some(key) => seed.deserialize((*key).into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let index = self.index;
+        self.index += 1;
+        let text = |value: Option<String>| ValueDeserializer {
+            values: value.map(FormValue::Text).into_iter().collect(),
+        };
+        match index {
+            0 => seed.deserialize(text(self.upload.filename.take())),
+            1 => seed.deserialize(text(self.upload.content_type.take())),
+            _ => {
+                let bytes = std::mem::take(&mut self.upload.bytes);
+                seed.deserialize(de::value::BytesDeserializer::<Error>::new(&bytes))
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! File downloads, from memory or streamed from disk.
+
+use crate::{AppError, Response, Result};
+use axum::body::Body;
+use axum::response::IntoResponse;
+use http::{header, StatusCode};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// A file response, from [`Response::download`] or [`Response::file`].
+///
+/// Like [`Response`], but with a binary body instead of HTML.
+#[derive(Clone, Debug)]
+pub struct Download {
+    pub status: StatusCode,
+    pub headers: Vec<(String, String)>,
+    content: Content,
+}
+
+#[derive(Clone, Debug)]
+enum Content {
+    Bytes(Vec<u8>),
+    /// Streamed from disk as the response is sent.
+    File(PathBuf),
+}
+
+impl Download {
+    fn new(content: Content) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            content,
+        }
+    }
+
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl IntoResponse for Download {
+    fn into_response(self) -> axum::response::Response {
+        let response = match self.content {
+            Content::Bytes(bytes) => (self.status, bytes).into_response(),
+            Content::File(path) => (self.status, stream_file(path)).into_response(),
+        };
+        crate::with_headers(response, self.headers)
+    }
+}
+
+impl Response {
+    /// Send `bytes` as a file the browser saves as `filename`.
+    ///
+    /// The content type is guessed from the file extension.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[action("/tasks/export", method = "POST")]
+    /// async fn export_tasks(db: Db) -> Result<Download> {
+    ///     let csv = tasks_csv(&db.all::<Task>().await?);
+    ///     Ok(Response::download(csv, "tasks.csv"))
+    /// }
+    /// ```
+    pub fn download(bytes: impl Into<Vec<u8>>, filename: &str) -> Download {
+        Download::new(Content::Bytes(bytes.into()))
+            .with_header(header::CONTENT_TYPE.as_str(), content_type(filename))
+            .with_header(header::CONTENT_DISPOSITION.as_str(), attachment(filename))
+    }
+
+    /// Stream the file at `path` as a download, under its own file name.
+    ///
+    /// Fails with a 404 if the file doesn't exist. Nothing is read into
+    /// memory up front, so large exports are fine.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[page("/reports/{id}/download")]
+    /// async fn download_report(Path(id): Path<i32>) -> Result<Download> {
+    ///     Response::file(format!("reports/{}.pdf", id)).await
+    /// }
+    /// ```
+    pub async fn file(path: impl AsRef<Path>) -> Result<Download> {
+        let path = path.as_ref();
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => AppError::NotFound,
+                _ => AppError::Internal(e.to_string()),
+            })?;
+        if !metadata.is_file() {
+            return Err(AppError::NotFound);
+        }
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Download::new(Content::File(path.to_path_buf()))
+            .with_header(header::CONTENT_TYPE.as_str(), content_type(&filename))
+            .with_header(header::CONTENT_LENGTH.as_str(), metadata.len().to_string())
+            .with_header(header::CONTENT_DISPOSITION.as_str(), attachment(&filename)))
+    }
+}
+
+fn content_type(filename: &str) -> String {
+    mime_guess::from_path(filename)
+        .first_or_octet_stream()
+        .to_string()
+}
+
+/// Characters escaped in `filename*`: all but RFC 5987's `attr-char`.
+const FILENAME: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// `Content-Disposition` for saving as `filename`, with a plain ASCII
+/// fallback for clients that don't read `filename*`.
+fn attachment(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        utf8_percent_encode(filename, FILENAME)
+    )
+}
+
+/// A body reading `path` in chunks, opened once the response is sent.
+fn stream_file(path: PathBuf) -> Body {
+    let (chunks, body) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(4);
+    tokio::spawn(async move {
+        let mut file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) => {
+                let _ = chunks.send(Err(e)).await;
+                return;
+            }
+        };
+        loop {
+            let mut chunk = vec![0; 64 * 1024];
+            match file.read(&mut chunk).await {
+                Ok(0) => break,
+                Ok(len) => {
+                    chunk.truncate(len);
+                    // The client went away
+                    if chunks.send(Ok(chunk)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = chunks.send(Err(e)).await;
+                    break;
+                }
+            }
+        }
+    });
+    Body::from_stream(ReceiverStream::new(body))
+}
@@ -0,0 +1,169 @@
+//! HTML rendering for [`AppError`] responses.
+
+use crate::{escape_html, AppError, Fragment};
+use http::StatusCode;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, LazyLock, RwLock};
+
+type Renderer = dyn Fn(&AppError) -> Fragment + Send + Sync;
+
+#[derive(Default)]
+struct ErrorPages {
+    default: Option<Arc<Renderer>>,
+    by_status: HashMap<StatusCode, Arc<Renderer>>,
+    target: Option<String>,
+}
+
+static ERROR_PAGES: LazyLock<RwLock<ErrorPages>> = LazyLock::new(RwLock::default);
+
+/// Render every error with `renderer` unless a status has its own.
+///
+/// # Example
+/// ```ignore
+/// set_error_renderer(|err| html! {
+///     <p class="alert">{err.message()}</p>
+/// });
+/// ```
+pub fn set_error_renderer(renderer: impl Fn(&AppError) -> Fragment + Send + Sync + 'static) {
+    ERROR_PAGES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .default = Some(Arc::new(renderer));
+}
+
+/// Render errors with the given status with `renderer`.
+///
+/// # Example
+/// ```ignore
+/// set_status_renderer(StatusCode::NOT_FOUND, |_| NotFoundPage());
+/// ```
+pub fn set_status_renderer(
+    status: StatusCode,
+    renderer: impl Fn(&AppError) -> Fragment + Send + Sync + 'static,
+) {
+    ERROR_PAGES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .by_status
+        .insert(status, Arc::new(renderer));
+}
+
+/// Show errors from htmx requests in the element matching `selector`.
+///
+/// Without a target, failed htmx requests leave the page as it was.
+///
+/// # Example
+/// ```ignore
+/// set_error_target("#errors");
+/// ```
+pub fn set_error_target(selector: &str) {
+    ERROR_PAGES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .target = Some(selector.to_string());
+}
+
+/// The element htmx errors are swapped into, if any.
+pub(crate) fn error_target() -> Option<String> {
+    ERROR_PAGES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .target
+        .clone()
+}
+
+/// The details of a server error, attached to its response for the
+/// development error page and `Acacia::on_error`.
+#[derive(Clone, Debug)]
+pub struct ErrorReport {
+    pub status: StatusCode,
+    pub message: String,
+    /// The underlying causes, outermost first.
+    pub causes: Vec<String>,
+    /// Where the error was raised, for errors converted from `anyhow::Error`
+    /// outside the `Prod` profile.
+    pub backtrace: Option<String>,
+}
+
+impl ErrorReport {
+    pub(crate) fn new(err: &AppError) -> Self {
+        let mut causes = Vec::new();
+        let mut backtrace = None;
+        let mut source = std::error::Error::source(err);
+        if let Some(traced) = source.and_then(|source| source.downcast_ref::<Traced>()) {
+            backtrace = traced.backtrace.clone();
+        }
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+        Self {
+            status: err.status_code(),
+            message: err.message(),
+            causes,
+            backtrace,
+        }
+    }
+}
+
+/// An `anyhow::Error` kept as the source of an [`AppError`], with its
+/// backtrace when error details are shown.
+pub(crate) struct Traced {
+    error: anyhow::Error,
+    backtrace: Option<String>,
+}
+
+impl Traced {
+    pub(crate) fn new(error: anyhow::Error) -> Self {
+        let backtrace = crate::profile().shows_error_details().then(|| {
+            // Captured where the error was created when RUST_BACKTRACE is set,
+            // or else here, where `?` converts it
+            match error.backtrace().status() {
+                BacktraceStatus::Captured => error.backtrace().to_string(),
+                _ => Backtrace::force_capture().to_string(),
+            }
+        });
+        Self { error, backtrace }
+    }
+}
+
+impl fmt::Debug for Traced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+
+impl fmt::Display for Traced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for Traced {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Render an error with the registered renderer, or the built-in one.
+pub(crate) fn render(err: &AppError) -> Fragment {
+    let renderer = {
+        let pages = ERROR_PAGES.read().unwrap_or_else(|e| e.into_inner());
+        pages
+            .by_status
+            .get(&err.status_code())
+            .or(pages.default.as_ref())
+            .cloned()
+    };
+    match renderer {
+        Some(renderer) => renderer(err),
+        None => Fragment(format!(
+            r#"<div class="acacia-error" role="alert" style="padding: 20px; color: #721c24; background: #f8d7da; border: 1px solid #f5c6cb; border-radius: 4px;">
+                <strong>Error:</strong> {}
+            </div>"#,
+            escape_html(&err.message())
+        )),
+    }
+}
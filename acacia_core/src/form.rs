@@ -0,0 +1,947 @@
+//! Form extraction, validation and error re-rendering.
+
+use crate::{escape_html, submits, AppError, Endpoint, Fragment, Swap, Target};
+use axum::response::IntoResponse;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::{Mutex, OnceLock};
+
+/// A validation failure for a single form field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// Name of the offending field, or an empty string for form-level errors.
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+/// Server-side validation run by [`Valid`] after deserialization.
+///
+/// `#[form]` and `#[derive(Form)]` implement this automatically. Standalone
+/// form types can implement it with custom rules and register the impl with
+/// [`register_validate!`](crate::register_validate) so `Valid` runs it; other
+/// types are accepted as deserialized.
+///
+/// # Example
+/// ```ignore
+/// impl Validate for Signup {
+///     fn validate(&self) -> std::result::Result<(), Vec<FieldError>> {
+///         if self.password.len() < 8 {
+///             return Err(vec![FieldError::new("password", "must be at least 8 characters")]);
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// register_validate!(Signup);
+/// ```
+pub trait Validate {
+    fn validate(&self) -> std::result::Result<(), Vec<FieldError>> {
+        Ok(())
+    }
+}
+
+/// A type's [`Validate`] impl, found by [`Valid`] through its `TypeId`.
+#[doc(hidden)]
+pub struct ValidateRegistration {
+    pub type_id: fn() -> std::any::TypeId,
+    pub validate: fn(&dyn std::any::Any) -> std::result::Result<(), Vec<FieldError>>,
+}
+
+inventory::collect!(ValidateRegistration);
+
+/// Have [`Valid`] run a type's [`Validate`] impl. `#[form]` and
+/// `#[derive(Form)]` register theirs already.
+#[macro_export]
+macro_rules! register_validate {
+    ($ty:ty) => {
+        ::inventory::submit! {
+            $crate::ValidateRegistration {
+                type_id: ::std::any::TypeId::of::<$ty>,
+                validate: $crate::validate_any::<$ty>,
+            }
+        }
+    };
+}
+
+/// Validate a registered value. Called through [`ValidateRegistration`].
+#[doc(hidden)]
+pub fn validate_any<T: Validate + 'static>(
+    value: &dyn std::any::Any,
+) -> std::result::Result<(), Vec<FieldError>> {
+    value.downcast_ref::<T>().map_or(Ok(()), T::validate)
+}
+
+/// Run the registered `Validate` impl for `T`, if there is one.
+fn registered_validate<T: 'static>(value: &T) -> std::result::Result<(), Vec<FieldError>> {
+    let type_id = std::any::TypeId::of::<T>();
+    inventory::iter::<ValidateRegistration>
+        .into_iter()
+        .find(|registration| (registration.type_id)() == type_id)
+        .map_or(Ok(()), |registration| (registration.validate)(value))
+}
+
+/// Convert `validator` crate errors into field errors.
+///
+/// `#[form]` structs that `#[derive(Validate)]` from the `validator` crate call
+/// this automatically. Nested errors are named with dotted paths (`address.city`),
+/// and struct-level (`__all__`) errors become form-level errors.
+///
+/// # Example
+/// ```ignore
+/// impl acacia::prelude::Validate for Signup {
+///     fn validate(&self) -> std::result::Result<(), Vec<FieldError>> {
+///         validator::Validate::validate(self).map_err(acacia_core::validator_errors)
+///     }
+/// }
+/// ```
+#[cfg(feature = "validator")]
+pub fn validator_errors(errors: validator::ValidationErrors) -> Vec<FieldError> {
+    fn collect(prefix: &str, errors: validator::ValidationErrors, out: &mut Vec<FieldError>) {
+        for (name, kind) in errors.into_errors() {
+            let field = match (prefix.is_empty(), name.as_ref()) {
+                (_, "__all__") => prefix.to_string(),
+                (true, name) => name.to_string(),
+                (false, name) => format!("{}.{}", prefix, name),
+            };
+            match kind {
+                validator::ValidationErrorsKind::Field(errors) => {
+                    out.extend(errors.into_iter().map(|error| {
+                        let message = match error.message {
+                            Some(message) => message.into_owned(),
+                            None => format!("failed validation: {}", error.code),
+                        };
+                        FieldError::new(field.clone(), message)
+                    }));
+                }
+                validator::ValidationErrorsKind::Struct(errors) => collect(&field, *errors, out),
+                validator::ValidationErrorsKind::List(items) => {
+                    for (index, errors) in items {
+                        collect(&format!("{}[{}]", field, index), *errors, out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    collect("", errors, &mut out);
+    // HashMap order is random; keep messages stable between requests
+    out.sort_by(|a, b| a.field.cmp(&b.field));
+    out
+}
+
+/// Turn a field name into a label: `due_date` → `Due date`.
+pub(crate) fn humanize(name: &str) -> String {
+    let text = name.replace('_', " ");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Metadata for one field of a form, generated by `#[form]`.
+///
+/// Carries the validation rules declared with `#[required]`, `#[max_len(n)]`
+/// and `#[pattern("...")]` so inputs can render matching HTML attributes.
+#[derive(Clone, Copy, Debug)]
+pub struct FormField {
+    pub name: &'static str,
+    /// HTML input type inferred from the Rust type (`text`, `number`, `checkbox`, `file`).
+    pub input_type: &'static str,
+    pub required: bool,
+    pub max_len: Option<usize>,
+    pub pattern: Option<&'static str>,
+}
+
+impl FormField {
+    /// Render `name` plus the validation attributes, for spreading onto an input.
+    pub fn attrs(&self) -> String {
+        let mut attrs = format!("name=\"{}\"", self.name);
+        if self.required {
+            attrs.push_str(" required");
+        }
+        if let Some(max_len) = self.max_len {
+            attrs.push_str(&format!(" maxlength=\"{}\"", max_len));
+        }
+        if let Some(pattern) = self.pattern {
+            attrs.push_str(&format!(
+                " pattern=\"{}\"",
+                html_escape::encode_double_quoted_attribute(pattern)
+            ));
+        }
+        attrs
+    }
+
+    /// A human-readable label derived from the field name, e.g. `due_date` → `Due date`.
+    pub fn label(&self) -> String {
+        humanize(self.name)
+    }
+
+    /// Render a labeled input, refilled with the submitted value and any error.
+    fn render<T>(&self, errors: &FormErrors<T>) -> String {
+        let value = errors.value(self.name);
+        let mut attrs = format!(
+            "id=\"{}\" type=\"{}\" {}",
+            self.name,
+            self.input_type,
+            self.attrs()
+        );
+        match self.input_type {
+            "checkbox" => {
+                attrs.push_str(" value=\"true\"");
+                if value == "true" || value == "on" {
+                    attrs.push_str(" checked");
+                }
+            }
+            // Browsers never prefill file inputs
+            "file" => {}
+            // Decimals and floats would otherwise be limited to whole numbers
+            "number" => attrs.push_str(&format!(
+                " step=\"any\" value=\"{}\"",
+                html_escape::encode_double_quoted_attribute(value)
+            )),
+            _ => attrs.push_str(&format!(
+                " value=\"{}\"",
+                html_escape::encode_double_quoted_attribute(value)
+            )),
+        }
+        if errors.has_error(self.name) {
+            attrs.push_str(&format!(
+                " aria-invalid=\"true\" aria-describedby=\"{}-error\"",
+                self.name
+            ));
+        }
+        format!(
+            r#"<div class="form-field"><label for="{}">{}</label><input {} />{}</div>"#,
+            self.name,
+            escape_html(&self.label()),
+            attrs,
+            errors.error_html(self.name).0
+        )
+    }
+}
+
+impl fmt::Display for FormField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.attrs())
+    }
+}
+
+/// Field metadata for a form type, generated by `#[form]`.
+///
+/// # Example
+/// ```ignore
+/// #[form(Task)]
+/// pub struct NewTask {
+///     #[required]
+///     #[max_len(80)]
+///     pub title: String,
+/// }
+///
+/// html! { <input {NewTask::input_attrs("title")} /> }
+/// // <input name="title" required maxlength="80" />
+/// ```
+pub trait FormSchema {
+    const FIELDS: &'static [FormField];
+
+    /// Look up a field by name.
+    fn field(name: &str) -> Option<&'static FormField> {
+        Self::FIELDS.iter().find(|field| field.name == name)
+    }
+
+    /// Whether the form has file fields and must be submitted as `multipart/form-data`.
+    fn is_multipart() -> bool {
+        Self::FIELDS.iter().any(|field| field.input_type == "file")
+    }
+
+    /// The attributes for a field's input, or just its `name` if unknown.
+    fn input_attrs(name: &str) -> String {
+        match Self::field(name) {
+            Some(field) => field.attrs(),
+            None => format!("name=\"{}\"", name),
+        }
+    }
+
+    /// Render a complete form submitting to `endpoint`, with a labeled input per field.
+    ///
+    /// The response replaces the form, so an action can answer with
+    /// [`FormSchema::form_with_errors`] to show validation errors in place.
+    ///
+    /// # Example
+    /// ```ignore
+    /// html! { <section>{NewTask::form(CREATE_TASK)}</section> }
+    /// ```
+    fn form(endpoint: Endpoint) -> Fragment
+    where
+        Self: Sized,
+    {
+        Self::form_with_errors(endpoint, &FormErrors::empty())
+    }
+
+    /// Render the form refilled with submitted values and inline errors.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[action("/tasks", method = "POST")]
+    /// async fn create_task(db: Db, form: std::result::Result<Valid<NewTask>, FormErrors<NewTask>>) -> Result<Fragment> {
+    ///     match form {
+    ///         Ok(form) => Ok(TaskItem(&db.insert::<Task, _>(form.into_inner()).await?)),
+    ///         Err(errors) => Ok(NewTask::form_with_errors(CREATE_TASK, &errors)),
+    ///     }
+    /// }
+    /// ```
+    fn form_with_errors(endpoint: Endpoint, errors: &FormErrors<Self>) -> Fragment
+    where
+        Self: Sized,
+    {
+        let action = submits(endpoint)
+            .target(Target::This)
+            .swap(Swap::OuterHtml)
+            .no_js();
+        let mut html = format!("<form {}", action);
+        if Self::is_multipart() {
+            html.push_str(r#" hx-encoding="multipart/form-data" enctype="multipart/form-data""#);
+        }
+        html.push('>');
+        html.push_str(&action.method_field().0);
+        if let Some(message) = errors.form_error() {
+            html.push_str(&format!(
+                r#"<p class="form-error" role="alert">{}</p>"#,
+                escape_html(message)
+            ));
+        }
+        for field in Self::FIELDS {
+            html.push_str(&field.render(errors));
+        }
+        html.push_str(r#"<button type="submit">Submit</button></form>"#);
+        Fragment(html)
+    }
+}
+
+/// Access to a field's submitted text, used by generated validation rules.
+pub trait FieldValue {
+    /// The field's value as text, or `None` if it was not provided.
+    fn field_text(&self) -> Option<Cow<'_, str>>;
+}
+
+impl FieldValue for String {
+    fn field_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl FieldValue for &str {
+    fn field_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn field_text(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(FieldValue::field_text)
+    }
+}
+
+macro_rules! impl_field_value_display {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn field_text(&self) -> Option<Cow<'_, str>> {
+                    Some(Cow::Owned(self.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+impl_field_value_display!(
+    bool, char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+#[cfg(feature = "decimal")]
+impl_field_value_display!(rust_decimal::Decimal);
+
+#[cfg(feature = "uuid")]
+impl_field_value_display!(uuid::Uuid);
+
+#[cfg(feature = "chrono")]
+impl_field_value_display!(chrono::NaiveDate, chrono::NaiveDateTime, chrono::NaiveTime);
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> FieldValue for chrono::DateTime<Tz>
+where
+    Tz::Offset: fmt::Display,
+{
+    fn field_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(self.to_string()))
+    }
+}
+
+/// Check a `#[required]` rule: the value is present and not blank.
+pub fn check_required(value: &impl FieldValue) -> bool {
+    value
+        .field_text()
+        .is_some_and(|text| !text.trim().is_empty())
+}
+
+/// Check a `#[max_len(n)]` rule, counting characters. Absent values pass.
+pub fn check_max_len(value: &impl FieldValue, max_len: usize) -> bool {
+    value
+        .field_text()
+        .is_none_or(|text| text.chars().count() <= max_len)
+}
+
+/// Check a `#[pattern("...")]` rule. Like the HTML attribute, the pattern must
+/// match the whole value, and absent or empty values pass.
+///
+/// # Panics
+///
+/// Panics if the pattern is not a valid regular expression.
+pub fn check_pattern(value: &impl FieldValue, pattern: &'static str) -> bool {
+    static CACHE: OnceLock<Mutex<HashMap<&'static str, regex::Regex>>> = OnceLock::new();
+
+    let Some(text) = value.field_text() else {
+        return true;
+    };
+    if text.is_empty() {
+        return true;
+    }
+
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let regex = cache.entry(pattern).or_insert_with(|| {
+        regex::Regex::new(&format!("^(?:{})$", pattern))
+            .unwrap_or_else(|e| panic!("invalid form pattern {:?}: {}", pattern, e))
+    });
+    regex.is_match(&text)
+}
+
+/// Deserialize a checkbox: `on`, `true`, `1` and `yes` are checked, anything
+/// else unchecked. Used by `#[form]` for `bool` fields, which also default
+/// to `false` since browsers leave unchecked boxes out entirely.
+///
+/// With a hidden input before the checkbox submitting under the same name,
+/// the box counts as checked if any value is.
+pub fn checkbox<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<bool, D::Error> {
+    struct CheckboxVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for CheckboxVisitor {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a checkbox value")
+        }
+
+        fn visit_bool<E>(self, checked: bool) -> std::result::Result<bool, E> {
+            Ok(checked)
+        }
+
+        fn visit_str<E>(self, value: &str) -> std::result::Result<bool, E> {
+            Ok(["on", "true", "1", "yes"]
+                .iter()
+                .any(|checked| value.eq_ignore_ascii_case(checked)))
+        }
+
+        fn visit_i64<E>(self, value: i64) -> std::result::Result<bool, E> {
+            Ok(value != 0)
+        }
+
+        fn visit_u64<E>(self, value: u64) -> std::result::Result<bool, E> {
+            Ok(value != 0)
+        }
+
+        fn visit_unit<E>(self) -> std::result::Result<bool, E> {
+            Ok(false)
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(
+            self,
+            mut seq: A,
+        ) -> std::result::Result<bool, A::Error> {
+            let mut checked = false;
+            while let Some(value) = seq.next_element_seed(CheckboxSeed)? {
+                checked |= value;
+            }
+            Ok(checked)
+        }
+    }
+
+    struct CheckboxSeed;
+
+    impl<'de> serde::de::DeserializeSeed<'de> for CheckboxSeed {
+        type Value = bool;
+
+        fn deserialize<D: serde::Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> std::result::Result<bool, D::Error> {
+            checkbox(deserializer)
+        }
+    }
+
+    deserializer.deserialize_any(CheckboxVisitor)
+}
+
+/// Deserialize an `Option<bool>` checkbox like [`checkbox`]. Used by `#[form]`
+/// for `Option<bool>` fields, which are `None` when the box isn't submitted.
+pub fn optional_checkbox<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<bool>, D::Error> {
+    checkbox(deserializer).map(Some)
+}
+
+/// Errors from a rejected form submission, along with the submitted values.
+///
+/// This is the rejection of [`Valid<T>`]. Extract `Result<Valid<T>, FormErrors<T>>`
+/// to re-render the form with inline errors instead of returning a bare 400.
+///
+/// # Example
+/// ```ignore
+/// #[component]
+/// fn TaskForm(errors: &FormErrors<NewTask>) -> Fragment {
+///     html! {
+///         <form {submits(CREATE_TASK).target(Target::This).swap(Swap::OuterHtml)}>
+///             <input name="title" value={errors.value("title")} />
+///             {errors.error_html("title")}
+///         </form>
+///     }
+/// }
+///
+/// #[action("/tasks", method = "POST")]
+/// async fn create_task(db: Db, form: std::result::Result<Valid<NewTask>, FormErrors<NewTask>>) -> Result<Fragment> {
+///     match form {
+///         Ok(form) => Ok(TaskItem(&db.insert::<Task, _>(form.into_inner()).await?)),
+///         Err(errors) => Ok(TaskForm(&errors)),
+///     }
+/// }
+/// ```
+pub struct FormErrors<T> {
+    values: Vec<(String, String)>,
+    errors: Vec<FieldError>,
+    _form: PhantomData<fn() -> T>,
+}
+
+impl<T> FormErrors<T> {
+    /// Create an error set for the given submitted values.
+    pub fn new(values: Vec<(String, String)>) -> Self {
+        Self {
+            values,
+            errors: Vec::new(),
+            _form: PhantomData,
+        }
+    }
+
+    /// An empty error set, for the initial render of a form.
+    pub fn empty() -> Self {
+        Self::new(Vec::new())
+    }
+
+    /// Record an error for a field.
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError::new(field, message));
+    }
+
+    /// Record an error for a field, builder-style.
+    pub fn with_error(mut self, field: impl Into<String>, message: impl Into<String>) -> Self {
+        self.add(field, message);
+        self
+    }
+
+    /// Record several errors at once.
+    pub fn extend(&mut self, errors: impl IntoIterator<Item = FieldError>) {
+        self.errors.extend(errors);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// The first error message for a field, if any.
+    pub fn error(&self, field: &str) -> Option<&str> {
+        self.errors
+            .iter()
+            .find(|e| e.field == field)
+            .map(|e| e.message.as_str())
+    }
+
+    /// Whether a field has any error.
+    pub fn has_error(&self, field: &str) -> bool {
+        self.error(field).is_some()
+    }
+
+    /// The first form-level error (not tied to a field), if any.
+    pub fn form_error(&self) -> Option<&str> {
+        self.error("")
+    }
+
+    /// The submitted value for a field, or an empty string.
+    pub fn value(&self, field: &str) -> &str {
+        self.values
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value.as_str())
+            .unwrap_or("")
+    }
+
+    /// All submitted values, in submission order.
+    pub fn values(&self) -> &[(String, String)] {
+        &self.values
+    }
+
+    /// The error for a field rendered as `<p class="field-error">`, or nothing.
+    pub fn error_html(&self, field: &str) -> Fragment {
+        match self.error(field) {
+            Some(message) => Fragment::new(format!(
+                r#"<p class="field-error" id="{}-error">{}</p>"#,
+                html_escape::encode_double_quoted_attribute(field),
+                crate::escape_html(message)
+            )),
+            None => Fragment::empty(),
+        }
+    }
+
+    /// All errors joined into one human-readable message.
+    pub fn message(&self) -> String {
+        self.errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+impl<T> Default for FormErrors<T> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T> Clone for FormErrors<T> {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            errors: self.errors.clone(),
+            _form: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for FormErrors<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormErrors")
+            .field("values", &self.values)
+            .field("errors", &self.errors)
+            .finish()
+    }
+}
+
+impl<T> IntoResponse for FormErrors<T> {
+    fn into_response(self) -> axum::response::Response {
+        AppError::BadRequest(self.message()).into_response()
+    }
+}
+
+/// An uploaded file from a multipart form.
+///
+/// Use it as a `#[form]` field type (or `Option<Upload>` for optional files);
+/// [`Valid<T>`] then parses `multipart/form-data` submissions.
+///
+/// # Example
+/// ```ignore
+/// #[form]
+/// pub struct NewAvatar {
+///     pub caption: String,
+///     #[required]
+///     pub image: Upload,
+/// }
+///
+/// #[action("/avatars", method = "POST")]
+/// async fn upload_avatar(form: Valid<NewAvatar>) -> Result<Fragment> {
+///     let image = &form.image;
+///     println!("{:?} ({:?}): {} bytes", image.filename, image.content_type, image.len());
+///     Ok(Fragment::empty())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Upload {
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub bytes: axum::body::Bytes,
+}
+
+impl Upload {
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl FieldValue for Upload {
+    fn field_text(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.filename.as_deref().unwrap_or("upload")))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Upload {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct UploadVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for UploadVisitor {
+            type Value = Upload;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a file upload (is the form multipart/form-data?)")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Upload, A::Error> {
+                let mut upload = Upload::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "filename" => upload.filename = map.next_value()?,
+                        "content_type" => upload.content_type = map.next_value()?,
+                        "bytes" => upload.bytes = map.next_value::<UploadBytes>()?.0,
+                        _ => {
+                            map.next_value::<serde::de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(upload)
+            }
+        }
+
+        deserializer.deserialize_any(UploadVisitor)
+    }
+}
+
+/// File contents, deserialized from a byte buffer.
+struct UploadBytes(axum::body::Bytes);
+
+impl<'de> serde::Deserialize<'de> for UploadBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl serde::de::Visitor<'_> for BytesVisitor {
+            type Value = UploadBytes;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "file contents")
+            }
+
+            fn visit_bytes<E>(self, bytes: &[u8]) -> std::result::Result<UploadBytes, E> {
+                Ok(UploadBytes(axum::body::Bytes::copy_from_slice(bytes)))
+            }
+
+            fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> std::result::Result<UploadBytes, E> {
+                Ok(UploadBytes(bytes.into()))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+/// Validated form wrapper and extractor.
+/// Use this instead of `axum::extract::Form` for cleaner handler signatures.
+///
+/// Any deserializable type works. Types from `#[form]` and `#[derive(Form)]`,
+/// or registered with [`register_validate!`](crate::register_validate), also
+/// have their [`Validate`] rules checked, and failures come back as [`FormErrors`].
+///
+/// # Example
+/// ```ignore
+/// #[action("/tasks", method = "POST")]
+/// async fn create_task(form: Valid<NewTask>, db: Db) -> Result<Fragment> {
+///     let task = db.insert::<Task, _>(form.into_inner()).await?;
+///     Ok(TaskItem(&task))
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Valid<T>(pub T);
+
+impl<T> Valid<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Valid<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// Implement FromRequest to make Valid<T> work as an axum extractor
+#[axum::async_trait]
+impl<T, S> axum::extract::FromRequest<S> for Valid<T>
+where
+    T: serde::de::DeserializeOwned + Send + 'static,
+    S: Send + Sync,
+{
+    type Rejection = FormErrors<T>;
+
+    async fn from_request(
+        req: axum::extract::Request,
+        state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        if has_content_type(req.headers(), "multipart/form-data") {
+            return from_multipart(req, state).await;
+        }
+
+        // GET forms submit through the query string, everything else through the body
+        let bytes = if req.method() == http::Method::GET {
+            req.uri().query().unwrap_or_default().as_bytes().to_vec()
+        } else {
+            if !has_content_type(req.headers(), "application/x-www-form-urlencoded") {
+                return Err(FormErrors::empty().with_error(
+                    "",
+                    "Expected request with `Content-Type: application/x-www-form-urlencoded`",
+                ));
+            }
+            axum::body::Bytes::from_request(req, state)
+                .await
+                .map_err(|e| FormErrors::empty().with_error("", e.body_text()))?
+                .to_vec()
+        };
+
+        let values: Vec<(String, String)> = serde_urlencoded::from_bytes(&bytes)
+            .map_err(|e| FormErrors::empty().with_error("", e.to_string()))?;
+
+        // Parsed like multipart forms, so a bad value is reported on its field
+        let entries = values
+            .iter()
+            .map(|(name, text)| (name.clone(), crate::de::FormValue::Text(text.clone())))
+            .collect();
+        let value: T = crate::de::from_values(entries).map_err(|e| {
+            FormErrors::new(values.clone()).with_error(e.field.unwrap_or_default(), e.message)
+        })?;
+
+        validated(value, values)
+    }
+}
+
+/// Parse a `multipart/form-data` submission, collecting text fields and files.
+async fn from_multipart<T, S>(
+    req: axum::extract::Request,
+    state: &S,
+) -> std::result::Result<Valid<T>, FormErrors<T>>
+where
+    T: serde::de::DeserializeOwned + Send + 'static,
+    S: Send + Sync,
+{
+    use axum::extract::FromRequest;
+
+    let mut multipart = axum::extract::Multipart::from_request(req, state)
+        .await
+        .map_err(|e| FormErrors::empty().with_error("", e.body_text()))?;
+
+    let mut values = Vec::new();
+    let mut entries = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Err(FormErrors::new(values).with_error("", e.body_text())),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+
+        if let Some(filename) = field.file_name().map(str::to_string) {
+            let content_type = field.content_type().map(str::to_string);
+            let bytes = match field.bytes().await {
+                Ok(bytes) => bytes,
+                Err(e) => return Err(FormErrors::new(values).with_error(name, e.body_text())),
+            };
+            // Browsers submit an empty, unnamed part for file inputs left blank
+            if filename.is_empty() && bytes.is_empty() {
+                continue;
+            }
+            entries.push((
+                name,
+                crate::de::FormValue::File(Upload {
+                    filename: Some(filename),
+                    content_type,
+                    bytes,
+                }),
+            ));
+        } else {
+            let text = match field.text().await {
+                Ok(text) => text,
+                Err(e) => return Err(FormErrors::new(values).with_error(name, e.body_text())),
+            };
+            values.push((name.clone(), text.clone()));
+            entries.push((name, crate::de::FormValue::Text(text)));
+        }
+    }
+
+    let value: T = crate::de::from_values(entries).map_err(|e| {
+        FormErrors::new(values.clone()).with_error(e.field.unwrap_or_default(), e.message)
+    })?;
+
+    validated(value, values)
+}
+
+/// Run a form's validation rules, keeping the submitted values on failure.
+fn validated<T: 'static>(
+    value: T,
+    values: Vec<(String, String)>,
+) -> std::result::Result<Valid<T>, FormErrors<T>> {
+    if let Err(errors) = registered_validate(&value) {
+        let mut form_errors = FormErrors::new(values);
+        form_errors.extend(errors);
+        return Err(form_errors);
+    }
+
+    Ok(Valid(value))
+}
+
+fn has_content_type(headers: &http::HeaderMap, expected: &str) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(expected))
+}
@@ -30,6 +30,8 @@ impl fmt::Display for Method {
 pub struct Endpoint {
     pub path: Cow<'static, str>,
     pub method: Method,
+    /// The `module_path!()` of the route, so [`Endpoint::href`] can apply its mount prefix.
+    pub module: &'static str,
 }
 
 impl Endpoint {
@@ -37,9 +39,53 @@ impl Endpoint {
         Self {
             path: Cow::Owned(path.into()),
             method,
+            module: "",
         }
     }
 
+    /// Record the module the route was defined in, set by route macros.
+    pub const fn in_module(mut self, module: &'static str) -> Self {
+        self.module = module;
+        self
+    }
+
+    /// The URL to link to: the path under the prefix its module is mounted at.
+    ///
+    /// # Example
+    /// ```ignore
+    /// html! { <a href={POSTS.href()}>Blog</a> }
+    /// ```
+    pub fn href(&self) -> Cow<'_, str> {
+        crate::route::mounted(self.module, &self.path)
+    }
+
+    /// Add query parameters to the path, percent-encoded and appended after
+    /// any the path already has.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let next = SEARCH.with_query([("q", term.to_string()), ("page", (page + 1).to_string())]);
+    /// html! { <button {loads(next).into("#results")}>"More"</button> }
+    /// ```
+    pub fn with_query<K, V>(mut self, params: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<str>,
+        V: fmt::Display,
+    {
+        let params: Vec<(String, String)> = params
+            .into_iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value.to_string()))
+            .collect();
+        if params.is_empty() {
+            return self;
+        }
+        // Encoding a list of string pairs can't fail
+        let query = serde_urlencoded::to_string(params).unwrap_or_default();
+        let separator = if self.path.contains('?') { '&' } else { '?' };
+        self.path = Cow::Owned(format!("{}{}{}", self.path, separator, query));
+        self
+    }
+
     pub fn get(path: impl Into<String>) -> Self {
         Self::new(path, Method::Get)
     }
@@ -48,6 +94,14 @@ impl Endpoint {
         Self::new(path, Method::Post)
     }
 
+    pub fn put(path: impl Into<String>) -> Self {
+        Self::new(path, Method::Put)
+    }
+
+    pub fn patch(path: impl Into<String>) -> Self {
+        Self::new(path, Method::Patch)
+    }
+
     pub fn delete(path: impl Into<String>) -> Self {
         Self::new(path, Method::Delete)
     }
@@ -57,6 +111,7 @@ impl Endpoint {
         Self {
             path: Cow::Borrowed(path),
             method: Method::Get,
+            module: "",
         }
     }
 
@@ -64,6 +119,23 @@ impl Endpoint {
         Self {
             path: Cow::Borrowed(path),
             method: Method::Post,
+            module: "",
+        }
+    }
+
+    pub const fn put_const(path: &'static str) -> Self {
+        Self {
+            path: Cow::Borrowed(path),
+            method: Method::Put,
+            module: "",
+        }
+    }
+
+    pub const fn patch_const(path: &'static str) -> Self {
+        Self {
+            path: Cow::Borrowed(path),
+            method: Method::Patch,
+            module: "",
         }
     }
 
@@ -71,6 +143,7 @@ impl Endpoint {
         Self {
             path: Cow::Borrowed(path),
             method: Method::Delete,
+            module: "",
         }
     }
 }
@@ -87,6 +160,10 @@ pub enum Swap {
     AfterEnd,
     Delete,
     None,
+    /// Morph the target's children into the response, keeping focus and input state.
+    Morph,
+    /// Morph the target itself into the response.
+    MorphOuter,
 }
 
 impl fmt::Display for Swap {
@@ -100,16 +177,50 @@ impl fmt::Display for Swap {
             Swap::AfterEnd => write!(f, "afterend"),
             Swap::Delete => write!(f, "delete"),
             Swap::None => write!(f, "none"),
+            Swap::Morph => write!(f, "morph:innerHTML"),
+            Swap::MorphOuter => write!(f, "morph:outerHTML"),
+        }
+    }
+}
+
+/// Which edge of an element to scroll to, for [`HtmxAction::scroll`] and [`HtmxAction::show`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scroll {
+    Top,
+    Bottom,
+}
+
+impl fmt::Display for Scroll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scroll::Top => write!(f, "top"),
+            Scroll::Bottom => write!(f, "bottom"),
         }
     }
 }
 
 /// HTMX target specification.
+///
+/// `Closest`, `Find`, `Next` and `Previous` use htmx's extended selectors,
+/// which search relative to the element carrying the attribute.
 #[derive(Clone, Debug)]
 pub enum Target {
     This,
+    /// The closest `<li>`, whatever the element's actual parent is.
+    #[deprecated(
+        note = "use `Target::Closest(\"li\".into())`, or `Closest` with the element you mean"
+    )]
     Parent,
+    /// The nearest ancestor, or the element itself, matching the selector.
     Closest(String),
+    /// The first descendant matching the selector.
+    Find(String),
+    /// The next element in the document matching the selector.
+    Next(String),
+    /// The previous element in the document matching the selector.
+    Previous(String),
+    /// The document body.
+    Body,
     Selector(String),
 }
 
@@ -117,19 +228,195 @@ impl fmt::Display for Target {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Target::This => write!(f, "this"),
-            Target::Parent => write!(f, "closest li"),  // Common case: list items
+            #[allow(deprecated)]
+            Target::Parent => write!(f, "closest li"),
             Target::Closest(selector) => write!(f, "closest {}", selector),
+            Target::Find(selector) => write!(f, "find {}", selector),
+            Target::Next(selector) => write!(f, "next {}", selector),
+            Target::Previous(selector) => write!(f, "previous {}", selector),
+            Target::Body => write!(f, "body"),
             Target::Selector(s) => write!(f, "{}", s),
         }
     }
 }
 
+/// A client-side event raised by actions and listened for by templates.
+///
+/// Declare each event once so the name can't drift between the action emitting
+/// it with `Response::with_trigger` and the elements refreshing with [`HtmxAction::on`].
+///
+/// # Example
+/// ```ignore
+/// pub const TASKS_CHANGED: Event = Event::new("tasks-changed");
+///
+/// #[action("/tasks", method = "POST")]
+/// async fn create_task(form: Valid<NewTask>, db: Db) -> Result<Response> {
+///     let task = db.insert::<Task, _>(form.into_inner()).await?;
+///     Ok(Response::html(TaskItem(&task).0).with_trigger(TASKS_CHANGED))
+/// }
+///
+/// html! { <span {loads(TASK_COUNT).on(TASKS_CHANGED)}></span> }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Event(&'static str);
+
+impl Event {
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+
+    pub const fn name(self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Modifiers for the event that sends a request, set with [`HtmxAction::trigger_with`].
+///
+/// # Example
+/// ```ignore
+/// loads(SEARCH).trigger_with("keyup", |t| t.changed().delay(300))
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Trigger {
+    modifiers: Vec<String>,
+}
+
+impl Trigger {
+    /// Wait until the event has stopped firing for `ms` milliseconds before sending.
+    pub fn delay(mut self, ms: u64) -> Self {
+        self.modifiers.push(format!("delay:{}ms", ms));
+        self
+    }
+
+    /// Send at most one request every `ms` milliseconds while the event keeps firing.
+    pub fn throttle(mut self, ms: u64) -> Self {
+        self.modifiers.push(format!("throttle:{}ms", ms));
+        self
+    }
+
+    /// Only send when the element's value has changed since the last request.
+    pub fn changed(mut self) -> Self {
+        self.modifiers.push("changed".to_string());
+        self
+    }
+
+    /// Only send on the first time the event fires.
+    pub fn once(mut self) -> Self {
+        self.modifiers.push("once".to_string());
+        self
+    }
+
+    /// Listen for the event on another element, e.g. `"body"` for events sent
+    /// with an `HX-Trigger` response header.
+    pub fn from(mut self, selector: &str) -> Self {
+        self.modifiers.push(format!("from:{}", selector));
+        self
+    }
+}
+
+/// Where an `HX-Location` response sends the client, set with `Response::location`.
+///
+/// htmx loads `path` as if a link with these attributes had been clicked,
+/// without a full page reload.
+///
+/// # Example
+/// ```ignore
+/// Response::location(Location::new("/tasks").target("#main").swap(Swap::OuterHtml))
+/// ```
+#[derive(Clone, Debug)]
+pub struct Location {
+    path: String,
+    target: Option<Target>,
+    swap: Option<Swap>,
+    select: Option<String>,
+}
+
+impl Location {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            target: None,
+            swap: None,
+            select: None,
+        }
+    }
+
+    /// Swap the response into `target` instead of the body.
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn swap(mut self, swap: Swap) -> Self {
+        self.swap = Some(swap);
+        self
+    }
+
+    /// Only swap in the part of the response matching a CSS selector.
+    pub fn select(mut self, selector: &str) -> Self {
+        self.select = Some(selector.to_string());
+        self
+    }
+
+    /// The `HX-Location` header value: the bare path, or JSON when options are set.
+    pub fn header_value(&self) -> String {
+        if self.target.is_none() && self.swap.is_none() && self.select.is_none() {
+            return self.path.clone();
+        }
+        let mut location = serde_json::Map::new();
+        location.insert("path".to_string(), self.path.clone().into());
+        if let Some(ref target) = self.target {
+            location.insert("target".to_string(), target.to_string().into());
+        }
+        if let Some(ref swap) = self.swap {
+            location.insert("swap".to_string(), swap.to_string().into());
+        }
+        if let Some(ref select) = self.select {
+            location.insert("select".to_string(), select.clone().into());
+        }
+        serde_json::Value::Object(location).to_string()
+    }
+}
+
+impl From<&str> for Location {
+    fn from(path: &str) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<String> for Location {
+    fn from(path: String) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<Endpoint> for Location {
+    fn from(endpoint: Endpoint) -> Self {
+        Self::new(endpoint.href())
+    }
+}
+
 /// Builder for HTMX attributes.
 #[derive(Clone, Debug)]
 pub struct HtmxAction {
     endpoint: Endpoint,
     target: Option<Target>,
     swap: Option<Swap>,
+    select: Option<String>,
+    select_oob: Option<String>,
+    headers: Vec<(String, String)>,
+    disabled_elt: Option<Target>,
+    no_js: bool,
+    trigger: Option<String>,
+    trigger_modifiers: Vec<String>,
+    swap_modifiers: Vec<String>,
+    preload: Option<&'static str>,
 }
 
 impl HtmxAction {
@@ -138,6 +425,15 @@ impl HtmxAction {
             endpoint,
             target: None,
             swap: None,
+            select: None,
+            select_oob: None,
+            headers: Vec::new(),
+            disabled_elt: None,
+            no_js: false,
+            trigger: None,
+            trigger_modifiers: Vec::new(),
+            swap_modifiers: Vec::new(),
+            preload: None,
         }
     }
 
@@ -171,6 +467,182 @@ impl HtmxAction {
         self
     }
 
+    /// Wait `ms` milliseconds after the response arrives before swapping it in.
+    pub fn swap_delay(mut self, ms: u64) -> Self {
+        self.swap_modifiers.push(format!("swap:{}ms", ms));
+        self
+    }
+
+    /// Wait `ms` milliseconds between swapping and settling, giving CSS
+    /// transitions on the new content time to run.
+    pub fn settle(mut self, ms: u64) -> Self {
+        self.swap_modifiers.push(format!("settle:{}ms", ms));
+        self
+    }
+
+    /// Scroll the target to its top or bottom after the swap.
+    ///
+    /// # Example
+    /// ```ignore
+    /// submits(SEND_MESSAGE).into("#messages").append().scroll(Scroll::Bottom)
+    /// ```
+    pub fn scroll(mut self, position: Scroll) -> Self {
+        self.swap_modifiers.push(format!("scroll:{}", position));
+        self
+    }
+
+    /// Scroll the element matching `selector` into view after the swap,
+    /// aligned at its top or bottom.
+    pub fn show(mut self, selector: &str, position: Scroll) -> Self {
+        self.swap_modifiers
+            .push(format!("show:{}:{}", selector, position));
+        self
+    }
+
+    /// Whether the swap runs in a View Transition, where the browser supports them.
+    pub fn transition(mut self, transition: bool) -> Self {
+        self.swap_modifiers
+            .push(format!("transition:{}", transition));
+        self
+    }
+
+    /// Only swap in the part of the response matching a CSS selector (hx-select).
+    ///
+    /// Lets a full-page handler be reused for fragment updates.
+    ///
+    /// # Example
+    /// ```ignore
+    /// loads(SEARCH).into("#results").select("#results")
+    /// ```
+    pub fn select(mut self, selector: &str) -> Self {
+        self.select = Some(selector.to_string());
+        self
+    }
+
+    /// Swap parts of the response out of band into matching elements (hx-select-oob).
+    ///
+    /// Accepts htmx's syntax: a comma-separated list of selectors, each
+    /// optionally suffixed with a swap strategy, e.g. `"#alert, #count:afterbegin"`.
+    pub fn select_oob(mut self, selectors: &str) -> Self {
+        self.select_oob = Some(selectors.to_string());
+        self
+    }
+
+    /// Add a request header sent with the HTMX request (hx-headers).
+    ///
+    /// Can be called multiple times; useful for CSRF tokens and API keys.
+    ///
+    /// # Example
+    /// ```ignore
+    /// submits(CREATE_TASK).header("X-CSRF-Token", &token)
+    /// ```
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Disable the given element while the request is in flight (hx-disabled-elt).
+    ///
+    /// Prevents double submissions from impatient clicks.
+    ///
+    /// # Example
+    /// ```ignore
+    /// <form {submits(CREATE_TASK).disable_elt(Target::Selector("button".into()))}>
+    /// ```
+    pub fn disable_elt(mut self, target: Target) -> Self {
+        self.disabled_elt = Some(target);
+        self
+    }
+
+    /// Set the event that sends the request (hx-trigger), e.g. `"keyup"` or `"load"`.
+    ///
+    /// Without it htmx uses the element's natural event: `change` for inputs,
+    /// `submit` for forms and `click` for everything else.
+    ///
+    /// # Example
+    /// ```ignore
+    /// <div {loads(NOTIFICATIONS).trigger("load")}></div>
+    /// ```
+    pub fn trigger(self, event: &str) -> Self {
+        self.trigger_with(event, |trigger| trigger)
+    }
+
+    /// Like [`HtmxAction::trigger`], with [`Trigger`] modifiers such as a delay.
+    ///
+    /// # Example
+    /// ```ignore
+    /// <input name="q" {loads(SEARCH).into("#results").trigger_with("keyup", |t| t.changed().delay(300))}>
+    /// <div {loads(TASK_COUNT).trigger_with("tasks-changed", |t| t.from("body"))}></div>
+    /// ```
+    pub fn trigger_with(mut self, event: &str, modifiers: impl FnOnce(Trigger) -> Trigger) -> Self {
+        self.trigger = Some(event.to_string());
+        self.trigger_modifiers = modifiers(Trigger::default()).modifiers;
+        self
+    }
+
+    /// Send the request whenever an action responds with `event`.
+    ///
+    /// Listens on the body, where events from `HX-Trigger` bubble up to, so any
+    /// element on the page can refresh itself.
+    pub fn on(self, event: Event) -> Self {
+        self.trigger_with(event.name(), |trigger| trigger.from("body"))
+    }
+
+    /// Fetch the response once the pointer rests on the element, so the
+    /// click swaps it in without waiting on the network.
+    ///
+    /// Uses htmx's preload extension: only GET requests are preloaded, and
+    /// each element is preloaded once per page load.
+    ///
+    /// # Example
+    /// ```ignore
+    /// <a href="/tasks" {loads(TASKS).into("#main").preload()}>Tasks</a>
+    /// ```
+    pub fn preload(mut self) -> Self {
+        self.preload = Some("mouseover");
+        self
+    }
+
+    /// Like [`HtmxAction::preload`], but start fetching on mousedown or touch,
+    /// which still saves most of a click's delay without preloading links the
+    /// pointer merely passes over.
+    pub fn preload_on_mousedown(mut self) -> Self {
+        self.preload = Some("mousedown");
+        self
+    }
+
+    /// Also emit `action` and `method` so a `<form>` still submits without JavaScript.
+    ///
+    /// PUT, PATCH and DELETE are sent as a POST carrying [`HtmxAction::method_field`],
+    /// which the server turns back into the intended method.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let delete = removes(DELETE_TASK(task.id)).no_js();
+    /// html! {
+    ///     <form {delete.clone()}>
+    ///         {delete.method_field()}
+    ///         <button type="submit">Delete</button>
+    ///     </form>
+    /// }
+    /// ```
+    pub fn no_js(mut self) -> Self {
+        self.no_js = true;
+        self
+    }
+
+    /// A hidden `_method` input for PUT, PATCH and DELETE endpoints, empty otherwise.
+    pub fn method_field(&self) -> crate::Fragment {
+        match self.endpoint.method {
+            Method::Get | Method::Post => crate::Fragment::default(),
+            method => crate::Fragment(format!(
+                r#"<input type="hidden" name="{}" value="{}">"#,
+                crate::METHOD_FIELD,
+                method
+            )),
+        }
+    }
+
     /// Build the HTMX attributes as a string.
     pub fn build(&self) -> String {
         let mut attrs = Vec::new();
@@ -183,16 +655,82 @@ impl HtmxAction {
             Method::Patch => "hx-patch",
             Method::Delete => "hx-delete",
         };
-        attrs.push(format!("{}=\"{}\"", method_attr, self.endpoint.path));
+        attrs.push(format!("{}=\"{}\"", method_attr, self.endpoint.href()));
+
+        if self.no_js {
+            let form_method = match self.endpoint.method {
+                Method::Get => "get",
+                _ => "post",
+            };
+            attrs.push(format!("action=\"{}\"", self.endpoint.href()));
+            attrs.push(format!("method=\"{}\"", form_method));
+        }
 
         // Add target if specified
         if let Some(ref target) = self.target {
             attrs.push(format!("hx-target=\"{}\"", target));
         }
 
-        // Add swap if specified
-        if let Some(ref swap) = self.swap {
-            attrs.push(format!("hx-swap=\"{}\"", swap));
+        // Add swap and its modifiers if specified
+        if self.swap.is_some() || !self.swap_modifiers.is_empty() {
+            let swap = self.swap.unwrap_or_default().to_string();
+            let swap = std::iter::once(swap.as_str())
+                .chain(self.swap_modifiers.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ");
+            attrs.push(format!(
+                "hx-swap=\"{}\"",
+                html_escape::encode_double_quoted_attribute(&swap)
+            ));
+        }
+
+        // Add trigger and its modifiers if specified
+        if let Some(ref event) = self.trigger {
+            let trigger = std::iter::once(event.as_str())
+                .chain(self.trigger_modifiers.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ");
+            attrs.push(format!(
+                "hx-trigger=\"{}\"",
+                html_escape::encode_double_quoted_attribute(&trigger)
+            ));
+        }
+
+        // Add response selection if specified
+        if let Some(ref select) = self.select {
+            attrs.push(format!(
+                "hx-select=\"{}\"",
+                html_escape::encode_double_quoted_attribute(select)
+            ));
+        }
+
+        if let Some(ref select_oob) = self.select_oob {
+            attrs.push(format!(
+                "hx-select-oob=\"{}\"",
+                html_escape::encode_double_quoted_attribute(select_oob)
+            ));
+        }
+
+        if let Some(preload) = self.preload {
+            attrs.push(format!("preload=\"{}\"", preload));
+        }
+
+        if let Some(ref disabled_elt) = self.disabled_elt {
+            attrs.push(format!("hx-disabled-elt=\"{}\"", disabled_elt));
+        }
+
+        // Add request headers as escaped JSON
+        if !self.headers.is_empty() {
+            let headers: serde_json::Map<String, serde_json::Value> = self
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                .collect();
+            let json = serde_json::Value::Object(headers).to_string();
+            attrs.push(format!(
+                "hx-headers=\"{}\"",
+                html_escape::encode_double_quoted_attribute(&json)
+            ));
         }
 
         attrs.join(" ")
@@ -207,7 +745,66 @@ impl fmt::Display for HtmxAction {
 
 /// Create a GET request action (for loading content).
 pub fn loads(endpoint: Endpoint) -> HtmxAction {
-    HtmxAction::new(Endpoint::new(endpoint.path, Method::Get))
+    HtmxAction::new(Endpoint {
+        method: Method::Get,
+        ..endpoint
+    })
+}
+
+/// A plain link to a page, for `<a {links(...)}>`: renders its `href`, so it
+/// works without JavaScript and can be opened in a new tab.
+///
+/// Links can only point at GET endpoints; any other endpoint panics, in
+/// release builds too, rather than render an `href` the browser can't follow.
+///
+/// # Example
+/// ```ignore
+/// html! {
+///     <a {links(HOME)}>"Home"</a>
+///     <a {links(SHOW_TASK(task.id)).boost()}>{&task.title}</a>
+/// }
+/// ```
+pub fn links(endpoint: Endpoint) -> Link {
+    assert!(
+        endpoint.method == Method::Get,
+        "links() needs a GET endpoint, not {} {}",
+        endpoint.method,
+        endpoint.path
+    );
+    Link {
+        endpoint,
+        boost: false,
+    }
+}
+
+/// An `href` to a GET endpoint, built with [`links`].
+#[derive(Clone, Debug)]
+pub struct Link {
+    endpoint: Endpoint,
+    boost: bool,
+}
+
+impl Link {
+    /// Follow the link with htmx (`hx-boost`), swapping the body instead of
+    /// reloading the page.
+    pub fn boost(mut self) -> Self {
+        self.boost = true;
+        self
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "href=\"{}\"",
+            html_escape::encode_double_quoted_attribute(&self.endpoint.href())
+        )?;
+        if self.boost {
+            write!(f, " hx-boost=\"true\"")?;
+        }
+        Ok(())
+    }
 }
 
 /// Create a POST request action (for form submissions).
@@ -215,7 +812,11 @@ pub fn submits(endpoint: Endpoint) -> HtmxAction {
     HtmxAction::new(endpoint)
 }
 
-/// Create a DELETE request action with delete swap.
+/// Create a DELETE request action with delete swap, replacing the closest `<li>`.
+///
+/// Outside of lists, set the element to replace with [`HtmxAction::target`].
 pub fn removes(endpoint: Endpoint) -> HtmxAction {
-    HtmxAction::new(endpoint).swap(Swap::OuterHtml).target(Target::Parent)
+    HtmxAction::new(endpoint)
+        .swap(Swap::OuterHtml)
+        .target(Target::Closest("li".to_string()))
 }
@@ -0,0 +1,53 @@
+//! Page shells chosen per route with `#[page(..., layout = ...)]`.
+
+use crate::Fragment;
+
+/// The markup around a page's content, like a navigation bar and sidebar.
+///
+/// Implemented for functions and components taking the content as a
+/// `Fragment`, so a layout is usually just a component.
+///
+/// # Example
+/// ```ignore
+/// #[component]
+/// fn AdminLayout(content: Fragment) -> Fragment {
+///     html! {
+///         <nav>{AdminNav()}</nav>
+///         <main>{content}</main>
+///     }
+/// }
+///
+/// #[page("/admin", layout = AdminLayout)]
+/// async fn admin(db: Db) -> Page {
+///     html! { <h1>Dashboard</h1> }.into_page()
+/// }
+/// ```
+pub trait Layout: Send + Sync {
+    fn render(&self, content: Fragment) -> Fragment;
+}
+
+impl<F: Fn(Fragment) -> Fragment + Send + Sync> Layout for F {
+    fn render(&self, content: Fragment) -> Fragment {
+        self(content)
+    }
+}
+
+tokio::task_local! {
+    static LAYOUT: &'static dyn Layout;
+}
+
+/// Wrap `content` in the current route's layout, if it has one.
+pub(crate) fn apply(content: &str) -> Option<Fragment> {
+    LAYOUT
+        .try_with(|layout| layout.render(Fragment(content.to_string())))
+        .ok()
+}
+
+/// Run a page handler with the `layout` of its `#[page]` attribute. Called by `#[page]`.
+pub async fn with_layout(
+    layout: &'static dyn Layout,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    LAYOUT.scope(layout, next.run(req)).await
+}
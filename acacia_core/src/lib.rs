@@ -3,15 +3,55 @@
 use axum::response::{Html, IntoResponse};
 use http::StatusCode;
 use std::fmt;
-
+use std::sync::RwLock;
+
+pub mod broadcast;
+pub mod context;
+pub mod cookie;
+#[cfg(feature = "chrono")]
+pub mod datetime;
+mod de;
+pub mod download;
+pub mod error;
+pub mod form;
 pub mod hateoas;
+pub mod layout;
+pub mod mail;
+pub mod method_override;
+pub mod negotiate;
+pub mod permission;
+pub mod profile;
+pub mod request;
 pub mod route;
+pub mod stream;
+pub mod table;
+pub mod updates;
 
 #[cfg(feature = "tailwind")]
 pub mod tw;
 
+pub use broadcast::{broadcast, live, subscribe, Live};
+pub use context::{ctx, update_ctx, with_context, RequestContext};
+pub use cookie::{Cookie, Cookies, SameSite};
+#[cfg(feature = "chrono")]
+pub use datetime::{
+    format_date, set_date_format, set_datetime_format, set_time_format, FormatDate,
+};
+pub use download::Download;
+pub use error::{set_error_renderer, set_error_target, set_status_renderer, ErrorReport};
+pub use form::*;
 pub use hateoas::*;
+pub use layout::Layout;
+pub use mail::*;
+pub use method_override::*;
+pub use negotiate::*;
+pub use permission::{require, Permission, Roles};
+pub use profile::{profile, set_profile, set_stylesheet, Profile};
+pub use request::*;
 pub use route::*;
+pub use stream::FragmentStream;
+pub use table::*;
+pub use updates::Updates;
 
 /// A raw HTML fragment that can be returned from actions and components.
 #[derive(Clone, Debug, Default)]
@@ -34,6 +74,16 @@ impl Fragment {
     pub fn into_page(self) -> Page {
         Page::new(self.0)
     }
+
+    /// Respond with the fragment and `status`, e.g. `422` for a form with errors.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Err(errors) => Ok(TaskForm(&errors).with_status(StatusCode::UNPROCESSABLE_ENTITY)),
+    /// ```
+    pub fn with_status(self, status: StatusCode) -> Response {
+        Response::html(self.0).with_status(status)
+    }
 }
 
 impl fmt::Display for Fragment {
@@ -69,71 +119,163 @@ impl std::iter::FromIterator<Fragment> for Fragment {
     }
 }
 
+/// Where pages load htmx from, set with `Acacia::htmx`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Htmx {
+    /// The copy bundled with Acacia, served at `/__acacia__/htmx.min.js`.
+    #[default]
+    Bundled,
+    /// A specific version from the jsDelivr CDN, e.g. `Htmx::Version("2.0.3".into())`.
+    Version(String),
+    /// A script URL, e.g. a self-hosted bundle with extensions.
+    Url(String),
+    /// A local file served in place of the bundled copy.
+    File(String),
+    /// No htmx script at all.
+    None,
+}
+
+static HTMX: RwLock<Htmx> = RwLock::new(Htmx::Bundled);
+
+/// Set how pages load htmx. Called by `Acacia::serve`.
+pub fn set_htmx(htmx: Htmx) {
+    *HTMX.write().unwrap_or_else(|e| e.into_inner()) = htmx;
+}
+
+tokio::task_local! {
+    static CSP_NONCE: String;
+    static PAGE_TITLE: String;
+}
+
+/// The Content-Security-Policy nonce for the current request, if the policy uses one.
+///
+/// Page scripts carry it already; add it to your own inline scripts.
+///
+/// # Example
+/// ```ignore
+/// html! { <script nonce={csp_nonce().unwrap_or_default()}>"initChart()"</script> }
+/// ```
+pub fn csp_nonce() -> Option<String> {
+    CSP_NONCE.try_with(Clone::clone).ok()
+}
+
+/// Run `future` with `nonce` as the request's CSP nonce. Called by `Acacia::security`.
+pub async fn with_csp_nonce<F: std::future::Future>(nonce: String, future: F) -> F::Output {
+    CSP_NONCE.scope(nonce, future).await
+}
+
+/// The title set with `#[page(..., title = "...")]` for the current request.
+pub fn page_title() -> Option<String> {
+    PAGE_TITLE.try_with(Clone::clone).ok()
+}
+
+/// Run a page handler with the `title` of its `#[page]` attribute, replacing
+/// each `{param}` with the escaped path parameter. Called by `#[page]`.
+pub async fn with_page_title(
+    title: &'static str,
+    params: axum::extract::RawPathParams,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let title = params
+        .iter()
+        .fold(title.to_string(), |title, (name, value)| {
+            title.replace(&format!("{{{}}}", name), &escape_html(value))
+        });
+    PAGE_TITLE.scope(title, next.run(req)).await
+}
+
+/// A ` nonce="..."` attribute for script tags, or nothing without a nonce.
+fn nonce_attr() -> String {
+    csp_nonce()
+        .map(|nonce| {
+            format!(
+                " nonce=\"{}\"",
+                html_escape::encode_double_quoted_attribute(&nonce)
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// idiomorph's htmx extension, behind `Swap::Morph` and `Swap::MorphOuter`.
+const IDIOMORPH_URL: &str =
+    "https://cdn.jsdelivr.net/npm/idiomorph@0.7.3/dist/idiomorph-ext.min.js";
+
+/// htmx's preload extension for htmx 2, behind `HtmxAction::preload`.
+const PRELOAD_URL: &str = "https://cdn.jsdelivr.net/npm/htmx-ext-preload@2.1.0/preload.js";
+
+/// The `<script>` tags loading htmx, the live element client and the morph and preload extensions.
+fn htmx_scripts() -> String {
+    let src = match &*HTMX.read().unwrap_or_else(|e| e.into_inner()) {
+        Htmx::Bundled | Htmx::File(_) => "/__acacia__/htmx.min.js".to_string(),
+        Htmx::Version(version) => format!(
+            "https://cdn.jsdelivr.net/npm/htmx.org@{}/dist/htmx.min.js",
+            version
+        ),
+        Htmx::Url(url) => url.clone(),
+        Htmx::None => return String::new(),
+    };
+    let nonce = nonce_attr();
+    // htmx adds its own inline indicator styles, which need the nonce too
+    let config = match csp_nonce() {
+        Some(nonce) => format!(
+            "    <meta name=\"htmx-config\" content=\"{}\">\n",
+            html_escape::encode_double_quoted_attribute(
+                &serde_json::json!({ "inlineScriptNonce": nonce, "inlineStyleNonce": nonce })
+                    .to_string()
+            )
+        ),
+        None => String::new(),
+    };
+    format!(
+        "{config}    <script{nonce} src=\"{}\"></script>\n    <script{nonce} src=\"/__acacia__/errors.js\"></script>\n    <script{nonce} src=\"/__acacia__/live.js\"></script>\n    <script{nonce} src=\"{IDIOMORPH_URL}\"></script>\n    <script{nonce} src=\"{PRELOAD_URL}\"></script>\n",
+        html_escape::encode_double_quoted_attribute(&src)
+    )
+}
+
 /// A full HTML page with layout.
 #[derive(Clone, Debug)]
 pub struct Page(pub String);
 
 impl Page {
-    #[cfg(not(feature = "tailwind"))]
+    /// A page titled by its `#[page]` attribute, or "Acacia App" without one.
     pub fn new(content: String) -> Self {
-        let html = format!(
-            r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Acacia App</title>
-    <script src="/__acacia__/htmx.min.js"></script>
-</head>
-<body>
-{content}
-</body>
-</html>"#
-        );
-        Self(html)
+        let title = page_title();
+        Self::with_title(content, title.as_deref().unwrap_or("Acacia App"))
     }
 
-    #[cfg(feature = "tailwind")]
-    pub fn new(content: String) -> Self {
-        let html = format!(
-            r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Acacia App</title>
-    <script src="https://cdn.jsdelivr.net/npm/@tailwindcss/browser@4"></script>
-    <script src="/__acacia__/htmx.min.js"></script>
-</head>
-<body>
-{content}
-</body>
-</html>"#
-        );
-        Self(html)
-    }
-
-    #[cfg(not(feature = "tailwind"))]
     pub fn with_title(content: String, title: &str) -> Self {
-        let html = format!(
-            r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>{title}</title>
-    <script src="/__acacia__/htmx.min.js"></script>
-</head>
-<body>
-{content}
-</body>
-</html>"#
-        );
-        Self(html)
-    }
+        Self::document(&content, title, &htmx_scripts())
+    }
+
+    /// A page without the htmx scripts, for static content that doesn't need them.
+    pub fn plain(content: String, title: &str) -> Self {
+        Self::document(&content, title, "")
+    }
+
+    fn document(content: &str, title: &str, scripts: &str) -> Self {
+        let content = layout::apply(content).map_or_else(|| content.to_string(), |f| f.0);
+        let prod = profile::profile().is_prod();
+        let styles = match profile::stylesheet() {
+            // The Tailwind CDN compiles classes in the browser during development
+            _ if cfg!(feature = "tailwind") && !prod => format!(
+                "    <script{} src=\"https://cdn.jsdelivr.net/npm/@tailwindcss/browser@4\"></script>\n",
+                nonce_attr()
+            ),
+            Some(href) => format!(
+                "    <link rel=\"stylesheet\" href=\"{}\">\n",
+                html_escape::encode_double_quoted_attribute(&href)
+            ),
+            None => String::new(),
+        };
+
+        // Extensions only apply below an element enabling them
+        let body = if scripts.is_empty() {
+            "<body>"
+        } else {
+            "<body hx-ext=\"morph, preload\">"
+        };
 
-    #[cfg(feature = "tailwind")]
-    pub fn with_title(content: String, title: &str) -> Self {
         let html = format!(
             r#"<!DOCTYPE html>
 <html lang="en">
@@ -141,15 +283,13 @@ impl Page {
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>{title}</title>
-    <script src="https://cdn.jsdelivr.net/npm/@tailwindcss/browser@4"></script>
-    <script src="/__acacia__/htmx.min.js"></script>
-</head>
-<body>
+{styles}{scripts}</head>
+{body}
 {content}
 </body>
 </html>"#
         );
-        Self(html)
+        Self(if prod { profile::minify(&html) } else { html })
     }
 }
 
@@ -169,11 +309,7 @@ pub struct Response {
 
 impl Response {
     pub fn empty() -> Self {
-        Self {
-            status: StatusCode::OK,
-            body: String::new(),
-            headers: vec![],
-        }
+        Self::html(String::new())
     }
 
     pub fn html(body: impl Into<String>) -> Self {
@@ -184,6 +320,65 @@ impl Response {
         }
     }
 
+    /// A `201 Created` response showing the new resource.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[action("/tasks", method = "POST")]
+    /// async fn create_task(form: Valid<NewTask>, db: Db) -> Result<Response> {
+    ///     let task = db.insert::<Task, _>(form.into_inner()).await?;
+    ///     Ok(Response::created(TaskItem(&task)))
+    /// }
+    /// ```
+    pub fn created(fragment: Fragment) -> Self {
+        fragment.with_status(StatusCode::CREATED)
+    }
+
+    /// A `204 No Content` response, which htmx leaves the page alone for.
+    pub fn no_content() -> Self {
+        Self::empty().with_status(StatusCode::NO_CONTENT)
+    }
+
+    /// A `303 See Other` redirect to `path`, followed with a GET, as after a form post.
+    pub fn see_other(path: impl Into<String>) -> Self {
+        Self::empty()
+            .with_status(StatusCode::SEE_OTHER)
+            .with_header(http::header::LOCATION.as_str(), path)
+    }
+
+    /// Navigate htmx clients to another page without a full reload (`HX-Location`).
+    ///
+    /// Unlike a redirect, the page keeps its scroll position and state outside
+    /// the swapped element. Takes a path, an [`Endpoint`] or a [`Location`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[action("/tasks", method = "POST")]
+    /// async fn create_task(form: Valid<NewTask>, db: Db) -> Result<Response> {
+    ///     let task = db.insert::<Task, _>(form.into_inner()).await?;
+    ///     let location = Location::new(format!("/tasks/{}", task.id));
+    ///     Ok(Response::location(location.target(Target::Selector("#main".into()))))
+    /// }
+    /// ```
+    pub fn location(location: impl Into<Location>) -> Self {
+        Self::empty().with_header("HX-Location", location.into().header_value())
+    }
+
+    /// A JSON body, for the odd endpoint that isn't HTML, like a webhook or health check.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[page("/health")]
+    /// async fn health(db: Db) -> Result<Response> {
+    ///     let tasks = db.all::<Task>().await?.len();
+    ///     Response::json(&serde_json::json!({ "status": "ok", "tasks": tasks }))
+    /// }
+    /// ```
+    pub fn json<T: serde::Serialize + ?Sized>(value: &T) -> Result<Self> {
+        Ok(Self::html(serde_json::to_string(value)?)
+            .with_header(http::header::CONTENT_TYPE.as_str(), "application/json"))
+    }
+
     pub fn with_status(mut self, status: StatusCode) -> Self {
         self.status = status;
         self
@@ -193,73 +388,86 @@ impl Response {
         self.headers.push((name.into(), value.into()));
         self
     }
-}
 
-impl IntoResponse for Response {
-    fn into_response(self) -> axum::response::Response {
-        let mut response = (self.status, Html(self.body)).into_response();
-        for (name, value) in self.headers {
-            if let (Ok(name), Ok(value)) = (
-                http::header::HeaderName::try_from(name),
-                http::header::HeaderValue::try_from(value),
-            ) {
-                response.headers_mut().insert(name, value);
+    /// Swap the response with `swap` instead of the strategy the client asked for (`HX-Reswap`).
+    pub fn with_reswap(self, swap: Swap) -> Self {
+        self.with_header("HX-Reswap", swap.to_string())
+    }
+
+    /// Swap the response into `target` instead of the client's target (`HX-Retarget`).
+    ///
+    /// # Example
+    /// ```ignore
+    /// Err(e) => Ok(Response::html(html! { <p class="error">{e.to_string()}</p> }.0)
+    ///     .with_retarget(Target::Selector("#errors".into()))
+    ///     .with_reswap(Swap::BeforeEnd)),
+    /// ```
+    pub fn with_retarget(self, target: Target) -> Self {
+        self.with_header("HX-Retarget", target.to_string())
+    }
+
+    /// Have htmx reload the whole page once the response arrives (`HX-Refresh`).
+    pub fn with_refresh(self) -> Self {
+        self.with_header("HX-Refresh", "true")
+    }
+
+    /// Raise `event` on the client once the response is swapped in (`HX-Trigger`).
+    ///
+    /// Can be called multiple times to raise several events.
+    pub fn with_trigger(mut self, event: Event) -> Self {
+        let existing = self
+            .headers
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case("HX-Trigger"));
+        match existing {
+            Some((_, events)) => {
+                events.push_str(", ");
+                events.push_str(event.name());
             }
+            None => self
+                .headers
+                .push(("HX-Trigger".to_string(), event.to_string())),
         }
-        response
-    }
-}
-
-/// Validated form wrapper and extractor.
-/// Use this instead of `axum::extract::Form` for cleaner handler signatures.
-///
-/// # Example
-/// ```ignore
-/// #[action("/tasks", method = "POST")]
-/// async fn create_task(form: Valid<NewTask>, db: Db) -> Result<Fragment> {
-///     let task = db.insert::<Task, _>(form.into_inner()).await?;
-///     Ok(TaskItem(&task))
-/// }
-/// ```
-#[derive(Clone, Debug)]
-pub struct Valid<T>(pub T);
-
-impl<T> Valid<T> {
-    pub fn new(value: T) -> Self {
-        Self(value)
+        self
     }
 
-    pub fn into_inner(self) -> T {
-        self.0
+    /// Add a `Set-Cookie` header; call once per cookie.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Response::empty().with_cookie(Cookie::build("theme", "dark").http_only(true))
+    /// ```
+    pub fn with_cookie(self, cookie: Cookie) -> Self {
+        self.with_header(http::header::SET_COOKIE.as_str(), cookie.to_string())
     }
 }
 
-impl<T> std::ops::Deref for Valid<T> {
-    type Target = T;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-// Implement FromRequest to make Valid<T> work as an axum extractor
-#[axum::async_trait]
-impl<T, S> axum::extract::FromRequest<S> for Valid<T>
-where
-    T: serde::de::DeserializeOwned + Send,
-    S: Send + Sync,
-{
-    type Rejection = AppError;
-
-    async fn from_request(
-        req: axum::extract::Request,
-        state: &S,
-    ) -> std::result::Result<Self, Self::Rejection> {
-        let axum::extract::Form(value) = axum::extract::Form::<T>::from_request(req, state)
-            .await
-            .map_err(|e| AppError::BadRequest(e.to_string()))?;
-        Ok(Valid(value))
+impl IntoResponse for Response {
+    fn into_response(self) -> axum::response::Response {
+        let response = (self.status, Html(self.body)).into_response();
+        with_headers(response, self.headers)
+    }
+}
+
+/// Add `headers` to `response`, skipping invalid ones.
+fn with_headers(
+    mut response: axum::response::Response,
+    headers: Vec<(String, String)>,
+) -> axum::response::Response {
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            http::header::HeaderName::try_from(name),
+            http::header::HeaderValue::try_from(value),
+        ) {
+            // Several cookies need several headers
+            if name == http::header::SET_COOKIE {
+                response.headers_mut().append(name, value);
+            } else {
+                response.headers_mut().insert(name, value);
+            }
+        }
     }
+    response
 }
 
 /// Children passed to a component.
@@ -283,19 +491,25 @@ pub fn escape_html(s: &str) -> String {
 
 /// Trait for rendering values as HTML.
 /// Fragment renders as raw HTML, while other types are escaped.
+///
+/// `html!` renders through [`RenderHtml::render_into`], so a whole tree is written
+/// into one buffer; override it (with `html_into!`) to skip the intermediate `String`.
 pub trait RenderHtml {
     fn render_html(&self) -> String;
+
+    /// Append the HTML to `out`.
+    fn render_into(&self, out: &mut String) {
+        out.push_str(&self.render_html());
+    }
 }
 
 impl RenderHtml for Fragment {
     fn render_html(&self) -> String {
         self.0.clone() // Don't escape - already HTML
     }
-}
 
-impl RenderHtml for &Fragment {
-    fn render_html(&self) -> String {
-        self.0.clone()
+    fn render_into(&self, out: &mut String) {
+        out.push_str(&self.0);
     }
 }
 
@@ -303,17 +517,67 @@ impl RenderHtml for String {
     fn render_html(&self) -> String {
         escape_html(self)
     }
+
+    fn render_into(&self, out: &mut String) {
+        self.as_str().render_into(out);
+    }
 }
 
-impl RenderHtml for &String {
+impl RenderHtml for str {
     fn render_html(&self) -> String {
         escape_html(self)
     }
+
+    fn render_into(&self, out: &mut String) {
+        html_escape::encode_text_to_string(self, out);
+    }
 }
 
-impl RenderHtml for &str {
+impl<T: RenderHtml + ?Sized> RenderHtml for &T {
     fn render_html(&self) -> String {
-        escape_html(self)
+        (**self).render_html()
+    }
+
+    fn render_into(&self, out: &mut String) {
+        (**self).render_into(out);
+    }
+}
+
+/// `None` renders nothing.
+impl<T: RenderHtml> RenderHtml for Option<T> {
+    fn render_html(&self) -> String {
+        self.as_ref().map(T::render_html).unwrap_or_default()
+    }
+
+    fn render_into(&self, out: &mut String) {
+        if let Some(value) = self {
+            value.render_into(out);
+        }
+    }
+}
+
+/// Items render back to back, e.g. `{tasks.iter().map(TaskItem).collect::<Vec<_>>()}`.
+impl<T: RenderHtml> RenderHtml for [T] {
+    fn render_html(&self) -> String {
+        let mut html = String::new();
+        self.render_into(&mut html);
+        html
+    }
+
+    fn render_into(&self, out: &mut String) {
+        for item in self {
+            item.render_into(out);
+        }
+    }
+}
+
+impl<T: RenderHtml> RenderHtml for Vec<T> {
+    fn render_html(&self) -> String {
+        self.as_slice().render_html()
+    }
+
+    fn render_into(&self, out: &mut String) {
+        self.as_slice().render_into(out);
     }
 }
 
@@ -359,6 +623,20 @@ impl RenderHtml for bool {
     }
 }
 
+#[cfg(feature = "uuid")]
+impl RenderHtml for uuid::Uuid {
+    fn render_html(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl RenderHtml for rust_decimal::Decimal {
+    fn render_html(&self) -> String {
+        self.to_string()
+    }
+}
+
 /// Application error type for handlers.
 /// Handlers return `Result<T, AppError>` and use `?` for error propagation.
 #[derive(Debug)]
@@ -373,6 +651,13 @@ pub enum AppError {
     // 5xx Server Errors
     Internal(String),
     Database(String),
+
+    /// Any status with a public message, keeping the underlying cause for logs.
+    Custom {
+        status: StatusCode,
+        message: String,
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
 }
 
 impl AppError {
@@ -384,6 +669,21 @@ impl AppError {
             AppError::Forbidden => StatusCode::FORBIDDEN,
             AppError::Conflict(_) => StatusCode::CONFLICT,
             AppError::Internal(_) | AppError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Custom { status, .. } => *status,
+        }
+    }
+
+    /// An error with the given status and message and no underlying cause.
+    ///
+    /// # Example
+    /// ```ignore
+    /// return Err(AppError::custom(StatusCode::PAYMENT_REQUIRED, "Upgrade to export"));
+    /// ```
+    pub fn custom(status: StatusCode, message: impl Into<String>) -> Self {
+        AppError::Custom {
+            status,
+            message: message.into(),
+            source: None,
         }
     }
 
@@ -396,6 +696,7 @@ impl AppError {
             AppError::Conflict(msg) => msg.clone(),
             AppError::Internal(msg) => msg.clone(),
             AppError::Database(msg) => msg.clone(),
+            AppError::Custom { message, .. } => message.clone(),
         }
     }
 }
@@ -406,19 +707,108 @@ impl std::fmt::Display for AppError {
     }
 }
 
-impl std::error::Error for AppError {}
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Custom {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Unhandled errors become a generic 500; the cause is only logged.
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        AppError::Custom {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: "Internal server error".to_string(),
+            source: Some(Box::new(error::Traced::new(err))),
+        }
+    }
+}
+
+/// A value that can't be serialized, as in `Response::json`, is a bug, not bad input.
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Internal(err.to_string())
+    }
+}
+
+/// Extension trait for attaching a message to any error.
+///
+/// # Example
+/// ```ignore
+/// let report = std::fs::read_to_string(&path).context("Could not load the report")?;
+/// ```
+pub trait ResultExt<T> {
+    /// Turn the error into a 500 with `message`, keeping it as the source.
+    fn context(self, message: impl Into<String>) -> std::result::Result<T, AppError>;
 
+    /// Like [`ResultExt::context`], with a status other than 500.
+    fn context_status(
+        self,
+        status: StatusCode,
+        message: impl Into<String>,
+    ) -> std::result::Result<T, AppError>;
+}
+
+impl<T, E: std::error::Error + Send + Sync + 'static> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, message: impl Into<String>) -> std::result::Result<T, AppError> {
+        self.context_status(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
+    fn context_status(
+        self,
+        status: StatusCode,
+        message: impl Into<String>,
+    ) -> std::result::Result<T, AppError> {
+        self.map_err(|err| AppError::Custom {
+            status,
+            message: message.into(),
+            source: Some(Box::new(err)),
+        })
+    }
+}
+
+/// Renders with the registered error renderer. htmx keeps the page as it is,
+/// or swaps the error into the target set with [`set_error_target`].
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
         let status = self.status_code();
-        let body = format!(
-            r#"<div style="padding: 20px; color: #721c24; background: #f8d7da; border: 1px solid #f5c6cb; border-radius: 4px;">
-                <strong>Error:</strong> {}
-            </div>"#,
-            self.message()
-        );
-        (status, Html(body)).into_response()
+        let body = error::render(&self).0;
+        let mut response = (status, Html(body)).into_response();
+        if status.is_server_error() {
+            log_error(&self);
+            response
+                .extensions_mut()
+                .insert(error::ErrorReport::new(&self));
+        }
+        let headers = response.headers_mut();
+        match error::error_target().and_then(|target| http::HeaderValue::from_str(&target).ok()) {
+            Some(target) => {
+                headers.insert("HX-Retarget", target);
+                headers.insert("HX-Reswap", http::HeaderValue::from_static("innerHTML"));
+            }
+            None => {
+                headers.insert("HX-Reswap", http::HeaderValue::from_static("none"));
+            }
+        }
+        response
+    }
+}
+
+/// Print a server error with its chain of causes.
+fn log_error(err: &AppError) {
+    let mut line = format!("❌ {}: {}", err.status_code(), err);
+    let mut source = std::error::Error::source(err);
+    while let Some(cause) = source {
+        line.push_str(&format!("\n   caused by: {}", cause));
+        source = cause.source();
     }
+    eprintln!("{}", line);
 }
 
 /// Result type alias for handler operations.
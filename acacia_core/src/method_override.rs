@@ -0,0 +1,97 @@
+//! Method override for plain HTML forms, which can only GET or POST.
+
+use crate::{AppError, KB};
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Name of the hidden field carrying the intended method.
+pub const METHOD_FIELD: &str = "_method";
+
+/// Largest form body read before routing, for `_method` and error reports.
+///
+/// Bigger bodies, and ones without a `Content-Length`, are passed on unread,
+/// so the route's [`body_limit`](crate::body_limit) still decides their size.
+pub const MAX_INSPECTED_FORM: usize = 64 * KB;
+
+/// Middleware that turns a form POST with `_method=PUT|PATCH|DELETE` into that method.
+///
+/// Lets DELETE and PUT actions work without JavaScript; see
+/// [`HtmxAction::no_js`](crate::HtmxAction::no_js). The field is read from
+/// the query string, or else from urlencoded bodies up to
+/// [`MAX_INSPECTED_FORM`]. `Acacia::serve` runs it before routing.
+pub async fn method_override(req: Request, next: Next) -> Response {
+    if req.method() != http::Method::POST {
+        return next.run(req).await;
+    }
+
+    let (req, method) = match req.uri().query().and_then(|query| field(query.as_bytes())) {
+        Some(method) => (req, Some(method)),
+        None => match read_small_form(req).await {
+            Ok((req, Some(bytes))) => (req, field(&bytes)),
+            Ok((req, None)) => (req, None),
+            Err(err) => return err.into_response(),
+        },
+    };
+
+    let (mut parts, body) = req.into_parts();
+    match method.map(|method| method.to_uppercase()).as_deref() {
+        Some("PUT") => parts.method = http::Method::PUT,
+        Some("PATCH") => parts.method = http::Method::PATCH,
+        Some("DELETE") => parts.method = http::Method::DELETE,
+        Some("POST") | None => {}
+        Some(other) => {
+            return AppError::BadRequest(format!("Unsupported {}: {}", METHOD_FIELD, other))
+                .into_response()
+        }
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
+
+/// Read an urlencoded body declaring a `Content-Length` up to
+/// [`MAX_INSPECTED_FORM`], handing back the request with the same body.
+///
+/// Other requests are returned untouched with `None`. Used by the middleware
+/// that look at forms before the route's extractors do.
+#[doc(hidden)]
+pub async fn read_small_form(
+    req: Request,
+) -> std::result::Result<(Request, Option<Bytes>), AppError> {
+    let is_form = req
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    let small = req
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok())
+        .is_some_and(|len| len <= MAX_INSPECTED_FORM);
+    if !is_form || !small {
+        return Ok((req, None));
+    }
+
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_INSPECTED_FORM)
+        .await
+        .map_err(|err| AppError::Custom {
+            status: http::StatusCode::BAD_REQUEST,
+            message: "The form couldn't be read".to_string(),
+            source: Some(err.into()),
+        })?;
+    let req = Request::from_parts(parts, Body::from(bytes.clone()));
+    Ok((req, Some(bytes)))
+}
+
+/// The `_method` value of an urlencoded query or body, if any.
+fn field(form: &[u8]) -> Option<String> {
+    serde_urlencoded::from_bytes::<Vec<(String, String)>>(form)
+        .ok()?
+        .into_iter()
+        .find(|(name, _)| name == METHOD_FIELD)
+        .map(|(_, value)| value)
+}
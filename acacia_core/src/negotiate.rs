@@ -0,0 +1,226 @@
+//! Response negotiation based on request headers.
+
+use crate::{AppError, Fragment, HtmxRequest, Page};
+use axum::{
+    body::Body,
+    extract::Request,
+    middleware::Next,
+    response::{Html, IntoResponse},
+};
+
+/// Alternative bodies a response can be swapped for by [`negotiate`].
+///
+/// Responders that depend on the request attach this as a response
+/// extension, since `IntoResponse` has no access to the request.
+#[derive(Clone, Debug, Default)]
+pub struct Alternates {
+    /// Full HTML document, used for non-HTMX requests.
+    pub page: Option<String>,
+    /// JSON body, used for requests that prefer `application/json`.
+    pub json: Option<String>,
+}
+
+/// Responds with a bare fragment to HTMX requests and a full page otherwise.
+///
+/// Makes every route deep-linkable without writing two handlers: htmx swaps
+/// in the fragment, while direct browser navigation gets it wrapped in the layout.
+///
+/// # Example
+/// ```ignore
+/// #[page("/tasks")]
+/// async fn tasks(db: Db) -> Result<PageOrFragment> {
+///     let tasks = db.all::<Task>().await?;
+///     Ok(TaskList(&tasks).into())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PageOrFragment {
+    fragment: Fragment,
+    title: Option<String>,
+}
+
+impl PageOrFragment {
+    pub fn new(fragment: Fragment) -> Self {
+        Self {
+            fragment,
+            title: None,
+        }
+    }
+
+    /// Set the title used when the fragment is wrapped in a full page.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+impl From<Fragment> for PageOrFragment {
+    fn from(fragment: Fragment) -> Self {
+        Self::new(fragment)
+    }
+}
+
+impl IntoResponse for PageOrFragment {
+    fn into_response(self) -> axum::response::Response {
+        let page = match &self.title {
+            Some(title) => Page::with_title(self.fragment.0.clone(), title),
+            None => Page::new(self.fragment.0.clone()),
+        };
+        let mut response = Html(self.fragment.0).into_response();
+        response.extensions_mut().insert(Alternates {
+            page: Some(page.0),
+            json: None,
+        });
+        response
+    }
+}
+
+/// Responds with a fragment to HTMX and browsers, and JSON to API clients.
+///
+/// Clients sending `Accept: application/json` get the serialized value, so
+/// mobile apps and scripts can reuse the same action endpoints.
+///
+/// # Example
+/// ```ignore
+/// #[action("/tasks", method = "POST")]
+/// async fn create_task(db: Db, form: Valid<NewTask>) -> Result<Respond<Task>> {
+///     let task = db.insert::<Task, _>(form.into_inner()).await?;
+///     Ok(Respond::render(task, |task| TaskItem(task)))
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Respond<T> {
+    value: T,
+    fragment: Fragment,
+}
+
+impl<T: serde::Serialize> Respond<T> {
+    pub fn new(value: T, fragment: Fragment) -> Self {
+        Self { value, fragment }
+    }
+
+    /// Render the fragment from the value.
+    pub fn render(value: T, render: impl FnOnce(&T) -> Fragment) -> Self {
+        let fragment = render(&value);
+        Self { value, fragment }
+    }
+}
+
+impl<T: serde::Serialize> IntoResponse for Respond<T> {
+    fn into_response(self) -> axum::response::Response {
+        let json = match serde_json::to_string(&self.value) {
+            Ok(json) => json,
+            Err(e) => return AppError::Internal(e.to_string()).into_response(),
+        };
+        let mut response = Html(self.fragment.0).into_response();
+        response.extensions_mut().insert(Alternates {
+            page: None,
+            json: Some(json),
+        });
+        response
+    }
+}
+
+/// Middleware that picks between the [`Alternates`] attached to a response.
+///
+/// JSON wins for clients preferring it, then the full page for non-HTMX requests.
+/// Installed by `Acacia::serve`; responses without alternates pass through untouched.
+pub async fn negotiate(req: Request, next: Next) -> axum::response::Response {
+    let htmx = HtmxRequest::from_headers(req.headers());
+    let wants_json = prefers_json(req.headers());
+    let mut response = next.run(req).await;
+
+    let Some(alternates) = response.extensions_mut().remove::<Alternates>() else {
+        return response;
+    };
+
+    // The body depends on these headers, so caches must key on them
+    vary(response.headers_mut(), &["HX-Request", "Accept"]);
+
+    let body = match (alternates.json, alternates.page) {
+        (Some(json), _) if wants_json => {
+            response.headers_mut().insert(
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_static("application/json"),
+            );
+            json
+        }
+        (_, Some(page)) if !htmx.is_partial() => page,
+        _ => return response,
+    };
+
+    response.headers_mut().remove(http::header::CONTENT_LENGTH);
+    *response.body_mut() = Body::from(body);
+    response
+}
+
+/// Add `names` to the response's `Vary` header, keeping those already listed.
+fn vary(headers: &mut http::HeaderMap, names: &[&str]) {
+    let mut listed: Vec<String> = headers
+        .get_all(http::header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    // `*` already varies on everything
+    if listed.iter().any(|name| name == "*") {
+        return;
+    }
+    for name in names {
+        if !listed
+            .iter()
+            .any(|listed| listed.eq_ignore_ascii_case(name))
+        {
+            listed.push(name.to_string());
+        }
+    }
+    if let Ok(value) = http::HeaderValue::from_str(&listed.join(", ")) {
+        headers.insert(http::header::VARY, value);
+    }
+}
+
+/// Whether the `Accept` header ranks JSON above HTML.
+///
+/// Each type takes the quality of its most specific matching range; on a tie,
+/// the one listed first by name wins.
+fn prefers_json(headers: &http::HeaderMap) -> bool {
+    let Some(accept) = headers
+        .get(http::header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let ranges: Vec<(&str, f32)> = accept
+        .split(',')
+        .map(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let range = parts.next().unwrap_or_default();
+            let q = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            (range, q)
+        })
+        .collect();
+    // The quality for `media`, and the position of its range when named exactly
+    let rank = |media: &str| {
+        let kind = format!("{}/*", media.split('/').next().unwrap_or_default());
+        [media, kind.as_str(), "*/*"].iter().find_map(|candidate| {
+            ranges
+                .iter()
+                .position(|(range, _)| range.eq_ignore_ascii_case(candidate))
+                .map(|i| (ranges[i].1, (*candidate == media).then_some(i)))
+        })
+    };
+    match (rank("application/json"), rank("text/html")) {
+        (Some((json, _)), _) if json <= 0.0 => false,
+        (Some(_), None) => true,
+        (Some((json, json_at)), Some((html, html_at))) => {
+            json > html
+                || (json == html && matches!((json_at, html_at), (Some(j), Some(h)) if j < h))
+        }
+        (None, _) => false,
+    }
+}
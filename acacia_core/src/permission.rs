@@ -0,0 +1,129 @@
+//! Guarding routes behind roles and permissions.
+
+use crate::{AppError, AppState, Result};
+use axum::{
+    async_trait,
+    extract::Request,
+    handler::Handler,
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Something a route can require with `#[page("/admin", require = ...)]`.
+///
+/// Denied requests get a 403 through the standard error renderer.
+///
+/// # Example
+/// ```ignore
+/// struct Owns(i32);
+///
+/// #[async_trait]
+/// impl Permission for Owns {
+///     async fn check(&self, parts: &mut Parts, state: &AppState) -> Result<bool> {
+///         let user = CurrentUser::from_request_parts(parts, state).await?;
+///         Ok(user.project_ids.contains(&self.0))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Permission: Send + Sync + 'static {
+    /// Whether the request may continue; an error is returned as the response.
+    async fn check(&self, parts: &mut Parts, state: &AppState) -> Result<bool>;
+}
+
+/// The current user's roles, so any role can be used as a [`Permission`].
+///
+/// Roles are loaded once per request, from a session, a model or anything else
+/// the request can reach.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Clone, PartialEq, Deserialize)]
+/// enum Role { Admin, Editor }
+///
+/// #[async_trait]
+/// impl Roles for Role {
+///     async fn load(parts: &mut Parts, state: &AppState) -> Result<Vec<Self>> {
+///         let session = Session::from_request_parts(parts, state).await?;
+///         Ok(session.get("roles")?.unwrap_or_default())
+///     }
+/// }
+///
+/// #[page("/admin", require = Role::Admin)]
+/// async fn admin() -> Page { ... }
+/// ```
+#[async_trait]
+pub trait Roles: Clone + PartialEq + Send + Sync + Sized + 'static {
+    async fn load(parts: &mut Parts, state: &AppState) -> Result<Vec<Self>>;
+}
+
+/// Roles already loaded for this request.
+#[derive(Clone)]
+struct LoadedRoles<R>(Vec<R>);
+
+#[async_trait]
+impl<R: Roles> Permission for R {
+    async fn check(&self, parts: &mut Parts, state: &AppState) -> Result<bool> {
+        if let Some(LoadedRoles(roles)) = parts.extensions.get::<LoadedRoles<R>>() {
+            return Ok(roles.contains(self));
+        }
+        let roles = R::load(parts, state).await?;
+        let granted = roles.contains(self);
+        parts.extensions.insert(LoadedRoles(roles));
+        Ok(granted)
+    }
+}
+
+/// A handler that only runs once its permission is granted; see [`require`].
+pub struct Require<H> {
+    handler: H,
+    permission: Arc<dyn Permission>,
+}
+
+impl<H: Clone> Clone for Require<H> {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            permission: self.permission.clone(),
+        }
+    }
+}
+
+/// Guard `handler` with `permission`, as `require = ...` does for route macros.
+///
+/// # Example
+/// ```ignore
+/// Router::new().route("/admin", get(require(admin, Role::Admin)))
+/// ```
+pub fn require<H>(handler: H, permission: impl Permission) -> Require<H> {
+    Require {
+        handler,
+        permission: Arc::new(permission),
+    }
+}
+
+impl<H, T> Handler<T, AppState> for Require<H>
+where
+    H: Handler<T, AppState>,
+    T: 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, req: Request, state: AppState) -> Self::Future {
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            match self.permission.check(&mut parts, &state).await {
+                Ok(true) => {
+                    self.handler
+                        .call(Request::from_parts(parts, body), state)
+                        .await
+                }
+                Ok(false) => AppError::Forbidden.into_response(),
+                Err(err) => err.into_response(),
+            }
+        })
+    }
+}
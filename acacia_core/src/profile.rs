@@ -0,0 +1,145 @@
+//! Dev and prod profiles, which change how pages load styles and are rendered.
+
+use std::sync::RwLock;
+
+/// Whether the app runs in development or production, set with `Acacia::profile`.
+///
+/// In `Prod`, pages link the stylesheet set with `Acacia::stylesheet` instead
+/// of the Tailwind CDN and are minified, and a strict Content-Security-Policy
+/// is sent unless `Acacia::security` is set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    Dev,
+    Prod,
+}
+
+impl Profile {
+    /// `Prod` when `ACACIA_ENV` is `prod`, and `Dev` otherwise.
+    ///
+    /// Release builds don't switch to `Prod` by themselves, since its strict
+    /// policy and missing Tailwind CDN break apps that aren't set up for it.
+    pub fn detect() -> Self {
+        match std::env::var("ACACIA_ENV").as_deref() {
+            Ok("prod" | "production") => Profile::Prod,
+            _ => Profile::Dev,
+        }
+    }
+
+    pub fn is_prod(self) -> bool {
+        self == Profile::Prod
+    }
+
+    /// Whether server errors show their causes, backtrace and request: only in
+    /// debug builds outside `Prod`, so a release binary never does.
+    pub fn shows_error_details(self) -> bool {
+        cfg!(debug_assertions) && !self.is_prod()
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+static PROFILE: RwLock<Profile> = RwLock::new(Profile::Dev);
+static STYLESHEET: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set the profile pages are rendered for. Called by `Acacia::serve`.
+pub fn set_profile(profile: Profile) {
+    *PROFILE.write().unwrap_or_else(|e| e.into_inner()) = profile;
+}
+
+/// The profile the app is running with.
+pub fn profile() -> Profile {
+    *PROFILE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Set the stylesheet pages link to. Called by `Acacia::serve`.
+pub fn set_stylesheet(href: Option<String>) {
+    *STYLESHEET.write().unwrap_or_else(|e| e.into_inner()) = href;
+}
+
+pub(crate) fn stylesheet() -> Option<String> {
+    STYLESHEET.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Elements whose text is copied unchanged.
+const RAW: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Drop indentation and newlines between tags, and collapse other runs of
+/// whitespace to one space.
+///
+/// Tags, and the contents of `<pre>`, `<textarea>`, `<script>` and `<style>`,
+/// are copied as they are.
+pub(crate) fn minify(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut i = 0;
+    while let Some(c) = html[i..].chars().next() {
+        if c == '<' {
+            let end = tag_end(html, i);
+            out.push_str(&html[i..end]);
+            let name = html[i + 1..end]
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            i = end;
+            if RAW.contains(&name.as_str()) {
+                let text = find_close(html, i, &name);
+                out.push_str(&html[i..text]);
+                i = text;
+            }
+        } else if c.is_whitespace() {
+            let rest = &html[i..];
+            let len = rest.len() - rest.trim_start().len();
+            let between_tags = out.ends_with('>') && rest[len..].starts_with('<');
+            if !(between_tags && rest[..len].contains('\n')) {
+                out.push(' ');
+            }
+            i += len;
+        } else {
+            out.push(c);
+            i += c.len_utf8();
+        }
+    }
+    out
+}
+
+/// The index of the `</name` closing a raw element opened before `start`,
+/// matched case-insensitively, or the end of the document.
+fn find_close(html: &str, start: usize, name: &str) -> usize {
+    let bytes = html.as_bytes();
+    let name = name.as_bytes();
+    let mut i = start;
+    while let Some(offset) = html[i..].find("</") {
+        let tag = i + offset + 2;
+        if bytes[tag..]
+            .get(..name.len())
+            .is_some_and(|candidate| candidate.eq_ignore_ascii_case(name))
+        {
+            return tag - 2;
+        }
+        i = tag;
+    }
+    html.len()
+}
+
+/// The index just past the `>` closing the tag at `start`, skipping quoted attributes.
+fn tag_end(html: &str, start: usize) -> usize {
+    if html[start..].starts_with("<!--") {
+        return html[start..]
+            .find("-->")
+            .map_or(html.len(), |offset| start + offset + 3);
+    }
+    let mut quote = None;
+    for (offset, c) in html[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return start + offset + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
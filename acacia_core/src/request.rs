@@ -0,0 +1,82 @@
+//! Extractor for HTMX request headers.
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use http::HeaderMap;
+
+/// HTMX request metadata, extracted from the `HX-*` request headers.
+///
+/// # Example
+/// ```ignore
+/// #[page("/tasks")]
+/// async fn tasks(htmx: HtmxRequest, db: Db) -> Result<Response> {
+///     let list = TaskList(&db.all::<Task>().await?);
+///     if htmx.is_htmx() {
+///         Ok(Response::html(list.0))
+///     } else {
+///         Ok(Response::html(list.into_page().0))
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HtmxRequest {
+    /// `HX-Request`: the request was issued by htmx.
+    pub request: bool,
+    /// `HX-Boosted`: the request came from an element using hx-boost.
+    pub boosted: bool,
+    /// `HX-Target`: the id of the target element, if it has one.
+    pub target: Option<String>,
+    /// `HX-Trigger`: the id of the triggering element, if it has one.
+    pub trigger: Option<String>,
+    /// `HX-Trigger-Name`: the name of the triggering element, if it has one.
+    pub trigger_name: Option<String>,
+    /// `HX-Current-URL`: the current URL of the browser.
+    pub current_url: Option<String>,
+}
+
+impl HtmxRequest {
+    /// Read the HTMX headers from a header map.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let text = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let flag = |name: &str| text(name).is_some_and(|value| value == "true");
+
+        Self {
+            request: flag("hx-request"),
+            boosted: flag("hx-boosted"),
+            target: text("hx-target"),
+            trigger: text("hx-trigger"),
+            trigger_name: text("hx-trigger-name"),
+            current_url: text("hx-current-url"),
+        }
+    }
+
+    /// Whether the request was issued by htmx.
+    pub fn is_htmx(&self) -> bool {
+        self.request
+    }
+
+    /// Whether the request was issued by htmx for a fragment swap,
+    /// as opposed to a boosted navigation expecting a full page.
+    pub fn is_partial(&self) -> bool {
+        self.request && !self.boosted
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for HtmxRequest
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        Ok(HtmxRequest::from_headers(&parts.headers))
+    }
+}
@@ -1,13 +1,36 @@
 //! Route registration for compile-time route collection.
 
-use crate::Method;
-use axum::routing::MethodRouter;
+use crate::{AppError, Method};
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts, routing::MethodRouter};
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Whether a route was registered with `#[page]` or `#[action]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteKind {
+    Page,
+    Action,
+}
 
 /// A registered route definition.
 pub struct RouteDefinition {
     pub path: &'static str,
     pub method: Method,
+    /// Methods served besides `method`, for `method = "PUT|PATCH"`.
+    pub also: &'static [Method],
+    pub kind: RouteKind,
     pub handler: fn() -> MethodRouter<crate::AppState>,
+    /// Overrides `Acacia::timeout` for this route.
+    pub timeout: Option<Duration>,
+    /// The `module_path!()` the route was defined in, for `Acacia::mount`.
+    pub module: &'static str,
+    /// The handler function's name, shown on the development error page.
+    pub name: &'static str,
+    /// Whether the page is listed in `/sitemap.xml`.
+    pub listed: bool,
 }
 
 impl RouteDefinition {
@@ -19,26 +42,165 @@ impl RouteDefinition {
         Self {
             path,
             method,
+            also: &[],
+            kind: RouteKind::Action,
+            handler,
+            timeout: None,
+            module: "",
+            name: "",
+            listed: true,
+        }
+    }
+
+    /// A GET route rendering a full page.
+    pub const fn page(path: &'static str, handler: fn() -> MethodRouter<crate::AppState>) -> Self {
+        Self {
+            path,
+            method: Method::Get,
+            also: &[],
+            kind: RouteKind::Page,
             handler,
+            timeout: None,
+            module: "",
+            name: "",
+            listed: true,
         }
     }
+
+    /// Give the route its own timeout, set with `timeout = ...` on route macros.
+    pub const fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Record the module the route was defined in, set by route macros.
+    pub const fn in_module(self, module: &'static str) -> Self {
+        Self { module, ..self }
+    }
+
+    /// Record the handler function's name, set by route macros.
+    pub const fn named(self, name: &'static str) -> Self {
+        Self { name, ..self }
+    }
+
+    /// Leave the page out of `/sitemap.xml`, set by `#[page]` with `require = ...`
+    /// or `sitemap = false`.
+    pub const fn unlisted(self) -> Self {
+        Self {
+            listed: false,
+            ..self
+        }
+    }
+
+    /// Also serve these methods, set by `#[action]` with several methods.
+    pub const fn also(self, also: &'static [Method]) -> Self {
+        Self { also, ..self }
+    }
+
+    /// Every method the route serves.
+    pub fn methods(&self) -> impl Iterator<Item = Method> + '_ {
+        std::iter::once(self.method).chain(self.also.iter().copied())
+    }
+
+    /// Whether the path has `{param}` segments.
+    pub fn has_params(&self) -> bool {
+        self.path.contains('{')
+    }
+
+    /// The path the route is served at, under its module's mount prefix.
+    pub fn url(&self) -> String {
+        mounted(self.module, self.path).into_owned()
+    }
 }
 
 inventory::collect!(RouteDefinition);
 
+static MOUNTS: RwLock<Vec<(&'static str, String)>> = RwLock::new(Vec::new());
+
+/// Set the path prefixes modules are mounted at. Called by `Acacia::serve`.
+pub fn set_mounts(mounts: Vec<(&'static str, String)>) {
+    *MOUNTS.write().unwrap_or_else(|e| e.into_inner()) = mounts;
+}
+
+/// `path` under the prefix `module` or its closest parent is mounted at.
+pub(crate) fn mounted<'a>(module: &str, path: &'a str) -> Cow<'a, str> {
+    let mounts = MOUNTS.read().unwrap_or_else(|e| e.into_inner());
+    let prefix = mounts
+        .iter()
+        .filter(|(mounted, _)| {
+            module
+                .strip_prefix(mounted)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        })
+        .max_by_key(|(mounted, _)| mounted.len())
+        .map(|(_, prefix)| prefix.as_str());
+    match prefix {
+        None => Cow::Borrowed(path),
+        Some(prefix) if path == "/" => Cow::Owned(prefix.to_string()),
+        Some(prefix) => Cow::Owned(format!("{}{}", prefix, path)),
+    }
+}
+
+/// One kilobyte, for sizes like `body_limit(512 * KB)`.
+pub const KB: usize = 1024;
+/// One megabyte.
+pub const MB: usize = 1024 * KB;
+/// One gigabyte.
+pub const GB: usize = 1024 * MB;
+
+/// Limit the request body size of a single route.
+///
+/// Route macros accept size literals, so `10_MB` reads as `10 * MB`.
+///
+/// # Example
+/// ```ignore
+/// #[action("/upload", method = "POST", layer = body_limit(10_MB))]
+/// async fn upload(form: Valid<NewAttachment>) -> Result<Fragment> {
+///     // ...
+/// }
+/// ```
+pub fn body_limit(bytes: usize) -> axum::extract::DefaultBodyLimit {
+    axum::extract::DefaultBodyLimit::max(bytes)
+}
+
 /// Application state shared across all routes.
 #[derive(Clone)]
 pub struct AppState {
     pub db: Option<sea_orm::DatabaseConnection>,
+    /// Read replicas of `db`.
+    pub replicas: Vec<sea_orm::DatabaseConnection>,
+    services: Arc<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
-        Self { db: None }
+        Self {
+            db: None,
+            replicas: Vec::new(),
+            services: Arc::default(),
+        }
     }
 
     pub fn with_db(db: sea_orm::DatabaseConnection) -> Self {
-        Self { db: Some(db) }
+        Self {
+            db: Some(db),
+            ..Self::new()
+        }
+    }
+
+    /// Store a user-defined service, replacing any previous value of the same type.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, service: T) {
+        Arc::make_mut(&mut self.services).insert(TypeId::of::<T>(), Arc::new(service));
+    }
+
+    /// Look up a service stored with [`AppState::insert`].
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.services
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|service| service.downcast().ok())
     }
 }
 
@@ -47,3 +209,53 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+/// Extractor for a service registered with `Acacia::state`.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Clone)]
+/// struct Services {
+///     mailer: Mailer,
+/// }
+///
+/// #[action("/invite", method = "POST")]
+/// async fn invite(services: Inject<Services>, form: Valid<Invite>) -> Result<Fragment> {
+///     services.mailer.send(&form.email).await?;
+///     Ok(html! { <p>Invitation sent</p> })
+/// }
+///
+/// Acacia::new().state(Services { mailer }).serve_or_exit("0.0.0.0:3000").await;
+/// ```
+pub struct Inject<T>(pub Arc<T>);
+
+impl<T> std::ops::Deref for Inject<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Clone for Inject<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync + 'static> FromRequestParts<AppState> for Inject<T> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        _parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        state.get::<T>().map(Inject).ok_or_else(|| {
+            AppError::Internal(format!(
+                "{} was not registered with Acacia::state",
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+}
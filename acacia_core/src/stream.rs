@@ -0,0 +1,96 @@
+//! Streaming large fragments to the client as they are rendered.
+
+use crate::Fragment;
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use std::convert::Infallible;
+use tokio_stream::{Stream, StreamExt};
+
+/// A fragment sent to the client piece by piece, created with [`Fragment::stream`].
+///
+/// Nothing is buffered beyond the current piece, so long lists and exports
+/// start arriving immediately and never sit in memory as one `String`.
+pub struct FragmentStream {
+    prefix: Option<Fragment>,
+    body: Body,
+    suffix: Option<Fragment>,
+}
+
+impl Fragment {
+    /// Stream fragments to the client as they are produced.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[page("/activity")]
+    /// async fn activity(db: Db) -> Result<FragmentStream> {
+    ///     let events = Event::find().stream(db.connection()).await?;
+    ///     Ok(Fragment::try_stream(events.map(|event| event.map(|e| EventRow(&e))))
+    ///         .prefix(Fragment::new("<ul>".into()))
+    ///         .suffix(Fragment::new("</ul>".into())))
+    /// }
+    /// ```
+    pub fn stream<S>(fragments: S) -> FragmentStream
+    where
+        S: Stream<Item = Fragment> + Send + 'static,
+    {
+        let chunks = fragments.map(|fragment| Ok::<_, Infallible>(fragment.0));
+        FragmentStream::new(Body::from_stream(chunks))
+    }
+
+    /// Like [`Fragment::stream`], for sources that can fail partway, such as database cursors.
+    ///
+    /// An error ends the response early, since the status has already been sent.
+    pub fn try_stream<S, E>(fragments: S) -> FragmentStream
+    where
+        S: Stream<Item = Result<Fragment, E>> + Send + 'static,
+        E: Into<BoxError> + 'static,
+    {
+        let chunks = fragments.map(|fragment| fragment.map(|fragment| fragment.0));
+        FragmentStream::new(Body::from_stream(chunks))
+    }
+}
+
+impl FragmentStream {
+    fn new(body: Body) -> Self {
+        Self {
+            prefix: None,
+            body,
+            suffix: None,
+        }
+    }
+
+    /// Markup sent before the streamed fragments, such as an opening `<table>`.
+    pub fn prefix(mut self, prefix: Fragment) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Markup sent after the streamed fragments, such as a closing `</table>`.
+    pub fn suffix(mut self, suffix: Fragment) -> Self {
+        self.suffix = Some(suffix);
+        self
+    }
+}
+
+impl IntoResponse for FragmentStream {
+    fn into_response(self) -> Response {
+        let body = match (self.prefix, self.suffix) {
+            (None, None) => self.body,
+            (prefix, suffix) => {
+                let edge = |fragment: Option<Fragment>| {
+                    tokio_stream::iter(fragment.map(|fragment| Ok(fragment.0.into())))
+                };
+                Body::from_stream(
+                    edge(prefix)
+                        .chain(self.body.into_data_stream())
+                        .chain(edge(suffix)),
+                )
+            }
+        };
+        ([(header::CONTENT_TYPE, "text/html; charset=utf-8")], body).into_response()
+    }
+}
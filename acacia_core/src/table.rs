@@ -0,0 +1,41 @@
+//! Column metadata for rendering models as tables.
+
+use crate::escape_html;
+
+/// Column access for a model, generated by `#[model]`.
+///
+/// Used by `acacia::components::DataTable` to render rows without writing
+/// the markup for every field.
+pub trait TableRow {
+    /// Field names, in declaration order.
+    const COLUMNS: &'static [&'static str];
+
+    /// The column's value rendered as escaped HTML, or empty for unknown columns.
+    fn cell(&self, column: &str) -> String;
+
+    /// A header label derived from the column name, e.g. `created_at` → `Created at`.
+    fn label(column: &str) -> String {
+        crate::form::humanize(column)
+    }
+}
+
+/// Render any serializable field as a table cell.
+///
+/// Going through serde keeps `#[model]` working for every column type,
+/// including ones without a [`RenderHtml`](crate::RenderHtml) impl.
+#[doc(hidden)]
+pub fn table_cell<T: serde::Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::Null) | Err(_) => String::new(),
+        Ok(serde_json::Value::String(text)) => escape_html(&text),
+        Ok(other) => escape_html(&other.to_string()),
+    }
+}
+
+/// Render a binary column as its size, since the bytes themselves aren't readable.
+#[doc(hidden)]
+pub fn table_bytes(bytes: Option<&[u8]>) -> String {
+    bytes
+        .map(|bytes| format!("{} bytes", bytes.len()))
+        .unwrap_or_default()
+}
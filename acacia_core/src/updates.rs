@@ -0,0 +1,80 @@
+//! Responses updating several regions of the page at once.
+
+use crate::{Fragment, Swap};
+use axum::response::{Html, IntoResponse};
+
+/// A response swapped into the request's target, plus out-of-band updates
+/// htmx swaps into other elements by selector.
+///
+/// # Example
+/// ```ignore
+/// #[action("/tasks", method = "POST")]
+/// async fn create_task(form: Valid<NewTask>, db: Db) -> Result<Updates> {
+///     let task = db.insert::<Task, _>(form.into_inner()).await?;
+///     let count = db.all::<Task>().await?.len();
+///     Ok(Updates::swap(TaskItem(&task))
+///         .oob("#task-count", html! { {count} })
+///         .oob_append("#toasts", html! { <p>"Task created"</p> }))
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Updates {
+    main: Fragment,
+    oob: Vec<(String, Swap, Fragment)>,
+}
+
+impl Updates {
+    /// Swap `main` into the target, as a plain fragment would be.
+    pub fn swap(main: Fragment) -> Self {
+        Self {
+            main,
+            oob: Vec::new(),
+        }
+    }
+
+    /// Also replace the contents of the element matching `selector`.
+    pub fn oob(self, selector: &str, fragment: Fragment) -> Self {
+        self.push(selector, Swap::InnerHtml, fragment)
+    }
+
+    /// Also append to the element matching `selector`, e.g. a toast container.
+    pub fn oob_append(self, selector: &str, fragment: Fragment) -> Self {
+        self.push(selector, Swap::BeforeEnd, fragment)
+    }
+
+    /// Also prepend to the element matching `selector`, e.g. a feed.
+    pub fn oob_prepend(self, selector: &str, fragment: Fragment) -> Self {
+        self.push(selector, Swap::AfterBegin, fragment)
+    }
+
+    fn push(mut self, selector: &str, swap: Swap, fragment: Fragment) -> Self {
+        self.oob.push((selector.to_string(), swap, fragment));
+        self
+    }
+
+    /// The combined body: the main fragment followed by one wrapper per update.
+    pub fn into_fragment(self) -> Fragment {
+        let mut html = self.main.0;
+        for (selector, swap, fragment) in self.oob {
+            // htmx swaps in the wrapper's children, leaving the target element in place
+            html.push_str(&format!(
+                "<div hx-swap-oob=\"{}\">{}</div>",
+                html_escape::encode_double_quoted_attribute(&format!("{}:{}", swap, selector)),
+                fragment.0
+            ));
+        }
+        Fragment(html)
+    }
+}
+
+impl From<Updates> for Fragment {
+    fn from(updates: Updates) -> Self {
+        updates.into_fragment()
+    }
+}
+
+impl IntoResponse for Updates {
+    fn into_response(self) -> axum::response::Response {
+        Html(self.into_fragment().0).into_response()
+    }
+}
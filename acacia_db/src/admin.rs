@@ -0,0 +1,154 @@
+//! Type-erased CRUD operations for the admin scaffold.
+//!
+//! `#[model]` registers an [`AdminRegistration`] per model, so the server can
+//! list and edit every table without knowing the concrete types. Rows travel
+//! as JSON objects keyed by field name.
+
+use crate::{Db, DbError, Result, Versioned};
+use acacia_core::Validate;
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, EntityTrait, FromQueryResult, IntoActiveModel,
+    ModelTrait, PrimaryKeyTrait, TryIntoModel,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+
+/// A boxed future returned by the admin operations.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// One field of a model as shown in the admin.
+#[derive(Clone, Copy, Debug)]
+pub struct AdminColumn {
+    pub name: &'static str,
+    /// HTML input type inferred from the Rust type (`text`, `number`, `checkbox`).
+    pub input_type: &'static str,
+    pub key: bool,
+    pub optional: bool,
+}
+
+/// Admin metadata and operations for one model, generated by `#[model]`.
+pub struct AdminRegistration {
+    /// The model's struct name, e.g. `Task`.
+    pub name: &'static str,
+    pub table: &'static str,
+    pub columns: &'static [AdminColumn],
+    pub list: fn(Db) -> BoxFuture<Result<Vec<serde_json::Value>>>,
+    pub get: fn(Db, String) -> BoxFuture<Result<Option<serde_json::Value>>>,
+    pub create: fn(Db, serde_json::Value) -> BoxFuture<Result<()>>,
+    pub update: fn(Db, String, serde_json::Value) -> BoxFuture<Result<()>>,
+    pub delete: fn(Db, String) -> BoxFuture<Result<()>>,
+}
+
+inventory::collect!(AdminRegistration);
+
+impl AdminRegistration {
+    /// The column holding the primary key.
+    pub fn key(&self) -> Option<&'static AdminColumn> {
+        self.columns.iter().find(|column| column.key)
+    }
+}
+
+type KeyOf<M> =
+    <<<M as ModelTrait>::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType;
+type ActiveModelOf<M> = <<M as ModelTrait>::Entity as EntityTrait>::ActiveModel;
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value> {
+    serde_json::to_value(value).map_err(|e| DbError::Query(e.to_string()))
+}
+
+fn parse_key<M: ModelTrait>(id: &str) -> Result<KeyOf<M>>
+where
+    KeyOf<M>: FromStr,
+{
+    id.parse().map_err(|_| DbError::NotFound)
+}
+
+#[doc(hidden)]
+pub fn list<M>(db: Db) -> BoxFuture<Result<Vec<serde_json::Value>>>
+where
+    M: ModelTrait + FromQueryResult + Serialize + Send + Sync + 'static,
+    M::Entity: EntityTrait<Model = M>,
+{
+    Box::pin(async move { db.all::<M>().await?.iter().map(to_json).collect() })
+}
+
+#[doc(hidden)]
+pub fn get<M>(db: Db, id: String) -> BoxFuture<Result<Option<serde_json::Value>>>
+where
+    M: ModelTrait + FromQueryResult + Serialize + Send + Sync + 'static,
+    M::Entity: EntityTrait<Model = M>,
+    KeyOf<M>: FromStr + Send,
+{
+    Box::pin(async move {
+        let id = parse_key::<M>(&id)?;
+        db.get::<M>(id).await?.as_ref().map(to_json).transpose()
+    })
+}
+
+#[doc(hidden)]
+pub fn create<M>(db: Db, values: serde_json::Value) -> BoxFuture<Result<()>>
+where
+    M: ModelTrait
+        + FromQueryResult
+        + Serialize
+        + DeserializeOwned
+        + Validate
+        + IntoActiveModel<ActiveModelOf<M>>
+        + Send
+        + Sync
+        + 'static,
+    M::Entity: EntityTrait<Model = M>,
+    ActiveModelOf<M>:
+        ActiveModelTrait<Entity = M::Entity> + ActiveModelBehavior + TryIntoModel<M> + Send,
+{
+    Box::pin(async move {
+        let active_model = ActiveModelOf::<M>::from_json(values)?;
+        db.insert::<M, _>(active_model).await?;
+        Ok(())
+    })
+}
+
+#[doc(hidden)]
+pub fn update<M>(db: Db, id: String, values: serde_json::Value) -> BoxFuture<Result<()>>
+where
+    M: ModelTrait
+        + FromQueryResult
+        + Serialize
+        + DeserializeOwned
+        + Validate
+        + Versioned
+        + IntoActiveModel<ActiveModelOf<M>>
+        + Send
+        + Sync
+        + 'static,
+    M::Entity: EntityTrait<Model = M>,
+    ActiveModelOf<M>:
+        ActiveModelTrait<Entity = M::Entity> + ActiveModelBehavior + TryIntoModel<M> + Send,
+    KeyOf<M>: FromStr + Send,
+{
+    Box::pin(async move {
+        let id = parse_key::<M>(&id)?;
+        // Read from the primary, since every column is written back
+        let model = db.primary().get::<M>(id).await?.ok_or(DbError::NotFound)?;
+        let mut active_model = model.clone().into_active_model();
+        active_model.set_from_json(values)?;
+        db.save(&model, active_model.try_into_model()?).await?;
+        Ok(())
+    })
+}
+
+#[doc(hidden)]
+pub fn delete<M>(db: Db, id: String) -> BoxFuture<Result<()>>
+where
+    M: ModelTrait + FromQueryResult + IntoActiveModel<ActiveModelOf<M>> + Send + Sync + 'static,
+    M::Entity: EntityTrait<Model = M>,
+    ActiveModelOf<M>: ActiveModelTrait<Entity = M::Entity> + ActiveModelBehavior + Send,
+    KeyOf<M>: FromStr + Send,
+{
+    Box::pin(async move {
+        let id = parse_key::<M>(&id)?;
+        db.delete::<M>(id).await
+    })
+}
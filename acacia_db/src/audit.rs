@@ -0,0 +1,282 @@
+//! The activity log kept for `#[audited]` models.
+//!
+//! Inserts, updates and deletes made through `Db` add a row to `_acacia_audit`
+//! in the same transaction, with the acting user, the time and the columns
+//! that changed.
+
+use crate::{Db, DbError, EntityRegistration, Result};
+use sea_orm::sea_query::{
+    sea_value_to_json_value, Alias, ColumnDef, Expr, ExprTrait, Order, Query, Table,
+    TableCreateStatement,
+};
+use sea_orm::{
+    ConnectionTrait, EntityName, EntityTrait, IdenStatic, Iterable, ModelTrait, PrimaryKeyToColumn,
+    PrimaryKeyTrait, QueryResult, Value,
+};
+use serde::Serialize;
+use std::fmt;
+
+const AUDIT: &str = "_acacia_audit";
+
+/// What happened to a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Insert => "insert",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+        }
+    }
+
+    fn parse(action: &str) -> Result<Self> {
+        match action {
+            "insert" => Ok(AuditAction::Insert),
+            "update" => Ok(AuditAction::Update),
+            "delete" => Ok(AuditAction::Delete),
+            other => Err(DbError::Query(format!("unknown audit action `{}`", other))),
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One column's value before and after a change; `null` for a row that didn't exist.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AuditChange {
+    pub column: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+/// One recorded change, from [`Db::activity`] or [`Db::activity_for`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AuditEntry {
+    pub id: i32,
+    pub table: String,
+    /// The row's primary key, with composite keys joined by commas.
+    pub row: String,
+    pub action: AuditAction,
+    /// Who made the change, as set with [`Db::acting_as`].
+    pub actor: Option<String>,
+    pub at: sea_orm::prelude::DateTime,
+    pub changes: Vec<AuditChange>,
+}
+
+impl Db {
+    /// A handle whose changes to `#[audited]` models are recorded as made by `actor`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[action("/tasks", method = "POST")]
+    /// async fn create_task(form: Valid<NewTask>, db: Db, user: VerifiedEmail) -> Result<Fragment> {
+    ///     let task = db.acting_as(&user.0).insert::<Task, _>(form.into_inner()).await?;
+    ///     Ok(TaskItem(&task))
+    /// }
+    /// ```
+    pub fn acting_as(&self, actor: impl Into<String>) -> Db {
+        Self {
+            actor: Some(actor.into().into()),
+            ..self.clone()
+        }
+    }
+
+    /// The most recent changes to audited models, newest first.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[page("/activity")]
+    /// async fn activity(db: Db) -> Result<Fragment> {
+    ///     Ok(ActivityFeed::new(&db.activity(50).await?).into())
+    /// }
+    /// ```
+    pub async fn activity(&self, limit: u64) -> Result<Vec<AuditEntry>> {
+        self.audit_entries(None, Some(limit)).await
+    }
+
+    /// The changes to one row of `M`, newest first.
+    pub async fn activity_for<M>(
+        &self,
+        id: <<M::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType,
+    ) -> Result<Vec<AuditEntry>>
+    where
+        M: ModelTrait,
+    {
+        use sea_orm::sea_query::IntoValueTuple;
+        let table = M::Entity::default().table_name().to_string();
+        let row = key(id.into_value_tuple());
+        self.audit_entries(Some((table, row)), None).await
+    }
+
+    async fn audit_entries(
+        &self,
+        row: Option<(String, String)>,
+        limit: Option<u64>,
+    ) -> Result<Vec<AuditEntry>> {
+        let mut select = Query::select();
+        select
+            .columns(
+                [
+                    "id",
+                    "table_name",
+                    "row_id",
+                    "action",
+                    "actor",
+                    "at",
+                    "changes",
+                ]
+                .map(Alias::new),
+            )
+            .from(Alias::new(AUDIT))
+            .order_by(Alias::new("id"), Order::Desc);
+        if let Some(limit) = limit {
+            select.limit(limit);
+        }
+        if let Some((table, row)) = row {
+            select
+                .and_where(Expr::col(Alias::new("table_name")).eq(table))
+                .and_where(Expr::col(Alias::new("row_id")).eq(row));
+        }
+        let rows = self.reader().query_all(&select).await?;
+        rows.iter().map(entry).collect()
+    }
+}
+
+fn entry(row: &QueryResult) -> Result<AuditEntry> {
+    let changes: String = row.try_get("", "changes")?;
+    let changes: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&changes).map_err(|e| DbError::Query(e.to_string()))?;
+    Ok(AuditEntry {
+        id: row.try_get("", "id")?,
+        table: row.try_get("", "table_name")?,
+        row: row.try_get("", "row_id")?,
+        action: AuditAction::parse(&row.try_get::<String>("", "action")?)?,
+        actor: row.try_get("", "actor")?,
+        at: row.try_get("", "at")?,
+        changes: changes
+            .into_iter()
+            .map(|(column, values)| {
+                let mut values = match values {
+                    serde_json::Value::Array(values) => values.into_iter(),
+                    _ => Vec::new().into_iter(),
+                };
+                AuditChange {
+                    column,
+                    before: values.next().unwrap_or_default(),
+                    after: values.next().unwrap_or_default(),
+                }
+            })
+            .collect(),
+    })
+}
+
+/// Whether `#[audited]` was set on the model for `E`.
+pub(crate) fn audited<E: EntityTrait>() -> bool {
+    let table = E::default().table_name();
+    inventory::iter::<EntityRegistration>
+        .into_iter()
+        .any(|registration| registration.audited == Some(table))
+}
+
+/// Whether any registered model is audited, so the table is needed.
+pub(crate) fn any_audited() -> bool {
+    inventory::iter::<EntityRegistration>
+        .into_iter()
+        .any(|registration| registration.audited.is_some())
+}
+
+/// Record a change to a row of an audited model; `before` or `after` is `None`
+/// for inserts and deletes.
+pub(crate) async fn record<M: ModelTrait>(
+    conn: &impl ConnectionTrait,
+    actor: Option<&str>,
+    action: AuditAction,
+    before: Option<&M>,
+    after: Option<&M>,
+) -> Result<()> {
+    let Some(model) = after.or(before) else {
+        return Ok(());
+    };
+    let table = M::Entity::default().table_name().to_string();
+    let row = key(<M::Entity as EntityTrait>::PrimaryKey::iter()
+        .map(|key| model.get(key.into_column()))
+        .collect::<Vec<_>>());
+
+    let mut changes = serde_json::Map::new();
+    for column in <M::Entity as EntityTrait>::Column::iter() {
+        let value = |model: Option<&M>| {
+            model
+                .map(|model| sea_value_to_json_value(&model.get(column)))
+                .unwrap_or_default()
+        };
+        let (before, after) = (value(before), value(after));
+        if before != after {
+            changes.insert(
+                column.as_str().to_string(),
+                serde_json::Value::Array(vec![before, after]),
+            );
+        }
+    }
+
+    let insert = Query::insert()
+        .into_table(Alias::new(AUDIT))
+        .columns(["table_name", "row_id", "action", "actor", "changes"].map(Alias::new))
+        .values_panic([
+            table.into(),
+            row.into(),
+            action.as_str().into(),
+            actor.map(str::to_string).into(),
+            serde_json::Value::Object(changes).to_string().into(),
+        ])
+        .to_owned();
+    conn.execute(&insert).await?;
+    Ok(())
+}
+
+/// A primary key as text, with composite keys joined by commas.
+fn key(values: impl IntoIterator<Item = Value>) -> String {
+    values
+        .into_iter()
+        .map(|value| match sea_value_to_json_value(&value) {
+            serde_json::Value::String(value) => value,
+            value => value.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub(crate) fn audit_table() -> TableCreateStatement {
+    Table::create()
+        .table(Alias::new(AUDIT))
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Alias::new("id"))
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Alias::new("table_name")).string().not_null())
+        .col(ColumnDef::new(Alias::new("row_id")).string().not_null())
+        .col(ColumnDef::new(Alias::new("action")).string().not_null())
+        .col(ColumnDef::new(Alias::new("actor")).string())
+        .col(
+            ColumnDef::new(Alias::new("at"))
+                .timestamp()
+                .not_null()
+                .default(Expr::current_timestamp()),
+        )
+        .col(ColumnDef::new(Alias::new("changes")).text().not_null())
+        .to_owned()
+}
@@ -0,0 +1,93 @@
+//! Cached SQL text for the `Db` CRUD helpers.
+//!
+//! A helper's SQL only depends on the entity, the operation and the backend,
+//! so it is rendered once and reused with each call's parameter values.
+//!
+//! This only saves rendering the SQL. The cache is shared by every
+//! connection, and preparing statements on each connection is left to the
+//! driver's own statement cache.
+
+use sea_orm::{DbBackend, EntityTrait, Statement, Value, Values};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::mem::Discriminant;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, RwLock};
+
+/// The CRUD helpers whose SQL is cached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Operation {
+    All,
+    Get,
+}
+
+type Key = (TypeId, Operation, Discriminant<DbBackend>);
+
+/// Rendered SQL, plus the values the query adds after the caller's, like `LIMIT 1`.
+struct Cached {
+    sql: String,
+    tail: Vec<Value>,
+}
+
+static SQL: LazyLock<RwLock<HashMap<Key, Cached>>> = LazyLock::new(Default::default);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// How well the SQL cache is doing, from [`Db::sql_cache_stats`](crate::Db::sql_cache_stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SqlCacheStats {
+    /// Calls that reused cached SQL.
+    pub hits: u64,
+    /// Calls that had to render SQL.
+    pub misses: u64,
+    /// Distinct SQL strings cached.
+    pub entries: usize,
+}
+
+pub(crate) fn stats() -> SqlCacheStats {
+    SqlCacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        entries: SQL.read().unwrap_or_else(|e| e.into_inner()).len(),
+    }
+}
+
+/// The statement for `operation` on `E` with `params`, rendering it with `build` on a miss.
+///
+/// `build` must put `params` first, in order, among the statement's values.
+pub(crate) fn statement<E: EntityTrait>(
+    backend: DbBackend,
+    operation: Operation,
+    params: Vec<Value>,
+    build: impl FnOnce(Vec<Value>) -> Statement,
+) -> Statement {
+    let key = (
+        TypeId::of::<E>(),
+        operation,
+        std::mem::discriminant(&backend),
+    );
+
+    if let Some(cached) = SQL.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        let mut values = params;
+        values.extend(cached.tail.iter().cloned());
+        return Statement::from_sql_and_values(backend, cached.sql.clone(), values);
+    }
+
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let count = params.len();
+    let statement = build(params);
+    let tail = statement
+        .values
+        .as_ref()
+        .map(|Values(values)| values.iter().skip(count).cloned().collect())
+        .unwrap_or_default();
+    SQL.write().unwrap_or_else(|e| e.into_inner()).insert(
+        key,
+        Cached {
+            sql: statement.sql.clone(),
+            tail,
+        },
+    );
+    statement
+}
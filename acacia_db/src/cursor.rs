@@ -0,0 +1,119 @@
+//! Keyset pagination for `Db::page_after`.
+
+use sea_orm::{ColumnType, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// Where a page ended, passed back to [`Db::page_after`](crate::Db::page_after) for the next one.
+///
+/// Its text form is opaque and URL-safe, for use as a query parameter.
+/// Cursors hold integer and string keys only.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct Feed {
+///     after: Option<Cursor>,
+/// }
+///
+/// #[page("/feed")]
+/// async fn feed(db: Db, axum::extract::Query(feed): axum::extract::Query<Feed>) -> Result<Fragment> {
+///     let page = db.page_after::<Post>(feed.after, 20).await?;
+///     // ...
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor(serde_json::Value);
+
+impl Cursor {
+    pub(crate) fn of(key: &Value) -> Self {
+        Self(sea_orm::sea_query::value::sea_value_to_json_value(key))
+    }
+
+    /// The last key seen, as a value to compare against.
+    pub(crate) fn key(&self) -> Value {
+        match &self.0 {
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(int) => Value::from(int),
+                None => Value::from(number.as_u64()),
+            },
+            serde_json::Value::String(text) => Value::from(text.clone()),
+            _ => Value::from(None::<String>),
+        }
+    }
+
+    /// Whether keys of `column_type` round-trip through a cursor unchanged.
+    pub(crate) fn supports(column_type: &ColumnType) -> bool {
+        matches!(
+            column_type,
+            ColumnType::TinyInteger
+                | ColumnType::SmallInteger
+                | ColumnType::Integer
+                | ColumnType::BigInteger
+                | ColumnType::TinyUnsigned
+                | ColumnType::SmallUnsigned
+                | ColumnType::Unsigned
+                | ColumnType::BigUnsigned
+                | ColumnType::Char(_)
+                | ColumnType::String(_)
+                | ColumnType::Text
+        )
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.to_string().bytes() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// The error for text that isn't a cursor produced by [`Cursor`]'s `Display`.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid cursor")]
+pub struct InvalidCursor;
+
+impl FromStr for Cursor {
+    type Err = InvalidCursor;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = (0..text.len())
+            .step_by(2)
+            .map(|i| {
+                text.get(i..i + 2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(InvalidCursor)?;
+        match serde_json::from_slice(&bytes) {
+            Ok(serde_json::Value::Number(number)) if number.is_i64() || number.is_u64() => {
+                Ok(Self(serde_json::Value::Number(number)))
+            }
+            Ok(value @ serde_json::Value::String(_)) => Ok(Self(value)),
+            _ => Err(InvalidCursor),
+        }
+    }
+}
+
+impl serde::Serialize for Cursor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Cursor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// One page of rows from [`Db::page_after`](crate::Db::page_after).
+#[derive(Clone, Debug)]
+pub struct Paged<M> {
+    pub items: Vec<M>,
+    /// Where the next page starts, or `None` on the last page.
+    pub next: Option<Cursor>,
+}
@@ -0,0 +1,287 @@
+//! Generating `#[model]` structs from an existing database.
+//!
+//! The tables are read with SQLite's `PRAGMA` statements and mapped back to
+//! the field types and attributes `#[model]` turns into those tables.
+
+use crate::{DbError, Result};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, QueryResult, Statement};
+use std::fmt;
+use std::path::Path;
+
+/// `#[model]` source for the tables of an existing database, from [`introspect`].
+///
+/// Displays as a Rust module, ready to write to a file.
+#[derive(Clone, Debug)]
+pub struct Introspection {
+    pub models: Vec<IntrospectedModel>,
+}
+
+/// One table, as the struct `#[model]` would create it from.
+#[derive(Clone, Debug)]
+pub struct IntrospectedModel {
+    pub table: String,
+    /// The struct name, singular and in CamelCase, e.g. `Task` for `tasks`.
+    pub name: String,
+    pub fields: Vec<IntrospectedField>,
+}
+
+#[derive(Clone, Debug)]
+pub struct IntrospectedField {
+    pub name: String,
+    /// The Rust type, e.g. `Option<String>`.
+    pub ty: String,
+    /// Field attributes without the `#[]`, e.g. `key` or `unique`.
+    pub attrs: Vec<String>,
+}
+
+/// Read the schema of the SQLite database at `url` and generate a `#[model]`
+/// struct for each table.
+///
+/// Keys, unique and indexed columns, and foreign keys become `#[key]`,
+/// `#[unique]`, `#[index]` and `#[belongs_to]` or `#[fk]`. Types are inferred
+/// from each column's declared type, so review the output before using it.
+///
+/// # Example
+/// ```ignore
+/// acacia_db::introspect("sqlite://legacy.db")
+///     .await?
+///     .write("src/models.rs")?;
+/// ```
+pub async fn introspect(url: &str) -> Result<Introspection> {
+    let conn = Database::connect(url)
+        .await
+        .map_err(|e| DbError::Connection(e.to_string()))?;
+    if conn.get_database_backend() != DbBackend::Sqlite {
+        return Err(DbError::Query(
+            "introspection only supports SQLite".to_string(),
+        ));
+    }
+
+    let tables = query(
+        &conn,
+        "SELECT name FROM sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '\\_acacia\\_%' ESCAPE '\\' \
+         ORDER BY name",
+    )
+    .await?
+    .iter()
+    .map(|row| row.try_get::<String>("", "name"))
+    .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut models = Vec::new();
+    for table in &tables {
+        models.push(model(&conn, table, &tables).await?);
+    }
+    Ok(Introspection { models })
+}
+
+impl Introspection {
+    /// Write the generated module to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Introspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "//! Models generated by `acacia_db::introspect`.")?;
+        writeln!(f)?;
+        writeln!(f, "use acacia::prelude::*;")?;
+        for model in &self.models {
+            writeln!(f)?;
+            write!(f, "{}", model)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for IntrospectedModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "#[model({:?})]", self.table)?;
+        writeln!(f, "pub struct {} {{", self.name)?;
+        for field in &self.fields {
+            for attr in &field.attrs {
+                writeln!(f, "    #[{}]", attr)?;
+            }
+            writeln!(f, "    pub {}: {},", field.name, field.ty)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+async fn model(
+    conn: &DatabaseConnection,
+    table: &str,
+    tables: &[String],
+) -> Result<IntrospectedModel> {
+    let quoted = table.replace('"', "\"\"");
+    let columns = query(conn, &format!("PRAGMA table_info(\"{}\")", quoted)).await?;
+    let foreign_keys = query(conn, &format!("PRAGMA foreign_key_list(\"{}\")", quoted)).await?;
+
+    // Single-column indexes, unique or not; a key's own index is skipped
+    let mut indexes = Vec::new();
+    for index in query(conn, &format!("PRAGMA index_list(\"{}\")", quoted)).await? {
+        if index.try_get::<String>("", "origin")? == "pk" {
+            continue;
+        }
+        let name: String = index.try_get("", "name")?;
+        let info = query(
+            conn,
+            &format!("PRAGMA index_info(\"{}\")", name.replace('"', "\"\"")),
+        )
+        .await?;
+        if let [column] = info.as_slice() {
+            let unique = index.try_get::<i32>("", "unique")? != 0;
+            indexes.push((column.try_get::<String>("", "name")?, unique));
+        }
+    }
+
+    let mut fields = Vec::new();
+    for column in &columns {
+        let name: String = column.try_get("", "name")?;
+        let declared: String = column.try_get("", "type")?;
+        let key = column.try_get::<i32>("", "pk")? != 0;
+        let not_null = column.try_get::<i32>("", "notnull")? != 0;
+
+        let field = field_name(&name);
+        let mut attrs = Vec::new();
+        if field.trim_start_matches("r#") != name {
+            attrs.push(format!("column({:?})", name));
+        }
+        if key {
+            attrs.push("key".to_string());
+        }
+        match indexes.iter().find(|(indexed, _)| *indexed == name) {
+            Some((_, true)) => attrs.push("unique".to_string()),
+            Some((_, false)) => attrs.push("index".to_string()),
+            None => {}
+        }
+        for fk in &foreign_keys {
+            if fk.try_get::<String>("", "from")? != name {
+                continue;
+            }
+            let target: String = fk.try_get("", "table")?;
+            let to: Option<String> = fk.try_get("", "to")?;
+            let to = to.unwrap_or_else(|| "id".to_string());
+            let mut actions = String::new();
+            for (option, column) in [("on_delete", "on_delete"), ("on_update", "on_update")] {
+                let action = fk.try_get::<String>("", column)?.to_lowercase();
+                if action != "no action" {
+                    actions.push_str(&format!(", {} = {:?}", option, action));
+                }
+            }
+            // Relations are only generated for keys to another model's `id`
+            if to == "id" && tables.contains(&target) {
+                attrs.push(format!("belongs_to({}{})", struct_name(&target), actions));
+            } else {
+                attrs.push(format!("fk({}.{}{})", target, to, actions));
+            }
+        }
+
+        let ty = rust_type(&declared);
+        let ty = if not_null || key {
+            ty.to_string()
+        } else {
+            format!("Option<{}>", ty)
+        };
+        fields.push(IntrospectedField {
+            name: field,
+            ty,
+            attrs,
+        });
+    }
+
+    Ok(IntrospectedModel {
+        table: table.to_string(),
+        name: struct_name(table),
+        fields,
+    })
+}
+
+async fn query(conn: &DatabaseConnection, sql: &str) -> Result<Vec<QueryResult>> {
+    Ok(conn
+        .query_all_raw(Statement::from_string(DbBackend::Sqlite, sql))
+        .await?)
+}
+
+/// The Rust type for a declared column type, following SQLite's affinity rules.
+fn rust_type(declared: &str) -> &'static str {
+    let declared = declared.to_uppercase();
+    let has = |part: &str| declared.contains(part);
+    if has("BIGINT") {
+        "i64"
+    } else if has("BOOL") {
+        "bool"
+    } else if has("INT") {
+        "i32"
+    } else if has("DATETIME") || has("TIMESTAMP") {
+        "DateTime"
+    } else if has("DATE") {
+        "Date"
+    } else if has("TIME") {
+        "Time"
+    } else if has("CHAR") || has("CLOB") || has("TEXT") || has("JSON") || has("UUID") {
+        "String"
+    } else if has("BLOB") || declared.is_empty() {
+        "Vec<u8>"
+    } else if has("REAL") || has("FLOA") || has("DOUB") {
+        "f64"
+    } else if has("DEC") || has("NUMERIC") {
+        "Decimal"
+    } else {
+        "String"
+    }
+}
+
+/// `tasks` -> `Task`, `categories` -> `Category`.
+fn struct_name(table: &str) -> String {
+    let singular = if let Some(stem) = table.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if table.ends_with("ss") {
+        table.to_string()
+    } else {
+        table.strip_suffix('s').unwrap_or(table).to_string()
+    };
+    singular
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// A column name as a field, in snake_case and escaped if it's a keyword.
+fn field_name(column: &str) -> String {
+    let mut name = String::new();
+    for (i, c) in column.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !name.ends_with('_') {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            name.push(c);
+        } else if !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+        "where", "while", "yield",
+    ];
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
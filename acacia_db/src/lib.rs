@@ -1,38 +1,93 @@
 //! Database layer for Acacia, wrapping SeaORM 2.0 with entity-first workflow.
 
+use acacia_core::{FieldError, Validate};
 use axum::{
     async_trait,
     extract::{FromRef, FromRequestParts},
     http::request::Parts,
 };
+use sea_orm::sea_query::IntoValueTuple;
 use sea_orm::{
-    ActiveModelBehavior, ActiveModelTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
-    IntoActiveModel, ModelTrait, PrimaryKeyTrait, Schema,
+    ActiveModelBehavior, ActiveModelTrait, ConnectOptions, DatabaseConnection, EntityTrait,
+    FromQueryResult, IntoActiveModel, Iterable, ModelTrait, PrimaryKeyToColumn, PrimaryKeyTrait,
+    QueryOrder, QuerySelect, QueryTrait, Schema, TransactionTrait,
 };
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+
+pub mod admin;
+mod audit;
+mod cache;
+mod cursor;
+mod introspect;
+mod migrate;
+mod query;
+
+pub use admin::{AdminColumn, AdminRegistration};
+use audit::{audited, record};
+pub use audit::{AuditAction, AuditChange, AuditEntry};
+use cache::Operation;
+pub use cache::SqlCacheStats;
+pub use cursor::{Cursor, InvalidCursor, Paged};
+pub use introspect::{introspect, IntrospectedField, IntrospectedModel, Introspection};
+pub use query::Query;
 
 // Re-export SeaORM types that users need
 pub use sea_orm::{
     ActiveValue, ColumnTrait, DeriveEntityModel, DeriveRelation, EntityName, EnumIter,
-    IntoActiveModel as _, QueryFilter, Set,
+    IntoActiveModel as _, Order, QueryFilter, Set,
 };
 
 /// Registration for entity schema creation.
 /// Each entity registers itself so migrations can create the table.
 pub struct EntityRegistration {
     pub create_table: fn(&Schema) -> sea_orm::sea_query::TableCreateStatement,
+    pub create_indexes: fn(&Schema) -> Vec<sea_orm::sea_query::IndexCreateStatement>,
+    /// The table name, for `#[audited]` models.
+    pub audited: Option<&'static str>,
 }
 
 impl EntityRegistration {
     pub const fn new(
         create_table: fn(&Schema) -> sea_orm::sea_query::TableCreateStatement,
     ) -> Self {
-        Self { create_table }
+        Self {
+            create_table,
+            create_indexes: |_| Vec::new(),
+            audited: None,
+        }
+    }
+
+    /// Also create these indexes, for `#[index]` fields.
+    pub const fn with_indexes(
+        mut self,
+        create_indexes: fn(&Schema) -> Vec<sea_orm::sea_query::IndexCreateStatement>,
+    ) -> Self {
+        self.create_indexes = create_indexes;
+        self
+    }
+
+    /// Record changes to `table` in the activity log, for `#[audited]` models.
+    pub const fn audited(mut self, table: &'static str) -> Self {
+        self.audited = Some(table);
+        self
     }
 }
 
 inventory::collect!(EntityRegistration);
 
+/// Registration for a SQL view, for `#[view]` models with a `query`.
+/// Migrations create the view after the tables it reads from.
+pub struct ViewRegistration {
+    pub name: &'static str,
+    /// The `SELECT` the view is defined by.
+    pub query: &'static str,
+}
+
+inventory::collect!(ViewRegistration);
+
 /// Database error type.
 #[derive(Debug, thiserror::Error)]
 pub enum DbError {
@@ -44,6 +99,30 @@ pub enum DbError {
 
     #[error("Not found")]
     NotFound,
+
+    #[error("Migration error: {0}")]
+    Migration(String),
+
+    /// A `#[version]` model was changed by someone else since it was read.
+    #[error("Conflict: the record was changed since it was read")]
+    Conflict,
+
+    /// The model's `Validate` hook rejected it; nothing was saved.
+    #[error("Validation failed: {}", invalid_message(.0))]
+    Invalid(Vec<FieldError>),
+}
+
+fn invalid_message(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Run the model's `Validate` hook, as `insert` and `update` do before saving.
+pub(crate) fn validate<M: Validate>(model: &M) -> Result<()> {
+    model.validate().map_err(DbError::Invalid)
 }
 
 pub type Result<T> = std::result::Result<T, DbError>;
@@ -64,15 +143,115 @@ impl From<DbError> for acacia_core::AppError {
             DbError::NotFound => acacia_core::AppError::NotFound,
             DbError::Connection(msg) => acacia_core::AppError::Database(msg),
             DbError::Query(msg) => acacia_core::AppError::Database(msg),
+            DbError::Migration(msg) => acacia_core::AppError::Database(msg),
+            DbError::Conflict => acacia_core::AppError::Conflict(
+                "This record was changed by someone else; reload and try again".to_string(),
+            ),
+            DbError::Invalid(errors) => acacia_core::AppError::BadRequest(invalid_message(&errors)),
         }
     }
 }
 
+/// Database URLs for `Acacia::database`: a primary, plus optional read replicas.
+///
+/// A plain URL converts into a config without replicas.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new().database(
+///     DbConfig::new("postgres://primary/app")
+///         .replica("postgres://replica-1/app")
+///         .replica("postgres://replica-2/app"),
+/// )
+/// ```
+#[derive(Clone, Debug)]
+pub struct DbConfig {
+    pub primary: String,
+    pub replicas: Vec<String>,
+    log_queries: bool,
+    slow_query: Option<Duration>,
+}
+
+impl DbConfig {
+    pub fn new(primary: &str) -> Self {
+        Self {
+            primary: primary.to_string(),
+            replicas: Vec::new(),
+            log_queries: false,
+            slow_query: None,
+        }
+    }
+
+    /// Add a replica that reads are spread across.
+    pub fn replica(mut self, url: &str) -> Self {
+        self.replicas.push(url.to_string());
+        self
+    }
+
+    /// Log every SQL statement and its duration at debug level.
+    ///
+    /// Statements are `tracing` events with the target `sqlx::query`, so they
+    /// show up inside the span of the request that ran them.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().database(
+    ///     DbConfig::new("sqlite://app.db?mode=rwc")
+    ///         .log_queries()
+    ///         .slow_query_threshold(Duration::from_millis(100)),
+    /// )
+    /// ```
+    pub fn log_queries(mut self) -> Self {
+        self.log_queries = true;
+        self
+    }
+
+    /// Warn about statements that take longer than `threshold`.
+    pub fn slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query = Some(threshold);
+        self
+    }
+
+    /// Connection options for `url` with the logging settings applied.
+    pub fn options(&self, url: &str) -> ConnectOptions {
+        let mut options = ConnectOptions::new(url);
+        if self.log_queries || self.slow_query.is_some() {
+            options
+                .sqlx_logging(true)
+                .sqlx_logging_level(if self.log_queries {
+                    log::LevelFilter::Debug
+                } else {
+                    log::LevelFilter::Off
+                });
+        }
+        if let Some(threshold) = self.slow_query {
+            options.sqlx_slow_statements_logging_settings(log::LevelFilter::Warn, threshold);
+        }
+        options
+    }
+}
+
+impl From<&str> for DbConfig {
+    fn from(url: &str) -> Self {
+        Self::new(url)
+    }
+}
+
+impl From<String> for DbConfig {
+    fn from(url: String) -> Self {
+        Self::new(&url)
+    }
+}
+
 /// Migration policy.
 #[derive(Clone, Copy, Debug, Default)]
 pub enum MigratePolicy {
     #[default]
     Auto,
+    /// Drop and recreate every table at startup, for development.
+    Fresh,
+    /// Fail at startup if the tables don't match the models, without changing them.
+    Check,
     None,
 }
 
@@ -80,23 +259,100 @@ pub enum MigratePolicy {
 ///
 /// This wraps a SeaORM DatabaseConnection and provides convenient methods
 /// for common database operations.
+///
+/// With read replicas configured, `all` and `get` read from a replica while
+/// writes go to the primary.
 #[derive(Clone)]
 pub struct Db {
     conn: Arc<DatabaseConnection>,
+    replicas: Arc<[DatabaseConnection]>,
+    actor: Option<Arc<str>>,
 }
 
+/// Replica used for the next read, shared so reads rotate across requests.
+static NEXT_REPLICA: AtomicUsize = AtomicUsize::new(0);
+
 impl Db {
     pub fn new(conn: DatabaseConnection) -> Self {
+        Self::with_replicas(conn, Vec::new())
+    }
+
+    pub fn with_replicas(conn: DatabaseConnection, replicas: Vec<DatabaseConnection>) -> Self {
         Self {
             conn: Arc::new(conn),
+            replicas: replicas.into(),
+            actor: None,
         }
     }
 
+    /// A fresh in-memory SQLite database with every registered table, for tests.
+    ///
+    /// Each call gets its own database, so tests don't see each other's rows.
+    /// Handlers can be tested with `AppState::with_db(db.connection().clone())`.
+    ///
+    /// # Panics
+    /// If the database can't be created or migrated.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[tokio::test]
+    /// async fn creates_tasks() {
+    ///     let db = Db::test().await;
+    ///     db.insert::<Task, _>(NewTask { title: "Write tests".into() }).await.unwrap();
+    ///     assert_eq!(db.all::<Task>().await.unwrap().len(), 1);
+    /// }
+    /// ```
+    pub async fn test() -> Db {
+        let conn = sea_orm::Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        let db = Db::new(conn);
+        db.migrate()
+            .await
+            .expect("Failed to migrate in-memory database");
+        db
+    }
+
     /// Get the underlying SeaORM connection for advanced operations.
+    ///
+    /// This is always the primary.
     pub fn connection(&self) -> &DatabaseConnection {
         &self.conn
     }
 
+    /// A connection for read-only queries: a replica, or the primary without replicas.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let open = Task::find()
+    ///     .filter(task::Column::Done.eq(false))
+    ///     .all(db.reader())
+    ///     .await?;
+    /// ```
+    pub fn reader(&self) -> &DatabaseConnection {
+        if self.replicas.is_empty() {
+            return &self.conn;
+        }
+        let next = NEXT_REPLICA.fetch_add(1, Ordering::Relaxed);
+        &self.replicas[next % self.replicas.len()]
+    }
+
+    /// A handle that reads from the primary too, for reads that must see a write
+    /// the replicas may not have caught up with.
+    ///
+    /// # Example
+    /// ```ignore
+    /// db.insert::<Task, _>(form).await?;
+    /// let tasks = db.primary().all::<Task>().await?;
+    /// ```
+    pub fn primary(&self) -> Db {
+        Self {
+            conn: self.conn.clone(),
+            replicas: Arc::new([]),
+            actor: self.actor.clone(),
+        }
+    }
+
     /// Get all records of a model type.
     ///
     /// # Example
@@ -104,11 +360,92 @@ impl Db {
     /// let tasks = db.all::<Task>().await?;
     /// ```
     pub async fn all<M>(&self) -> Result<Vec<M>>
+    where
+        M: ModelTrait + FromQueryResult,
+        M::Entity: EntityTrait<Model = M>,
+    {
+        let conn = self.reader();
+        let backend = conn.get_database_backend();
+        let stmt = cache::statement::<M::Entity>(backend, Operation::All, Vec::new(), |_| {
+            M::Entity::find().build(backend)
+        });
+        M::Entity::find()
+            .from_raw_sql(stmt)
+            .all(conn)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Get records sorted and paged by `query`, so listings needn't load the whole table.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let page = db
+    ///     .all_with::<Task>(Query::default().order_by("created_at", Order::Desc).limit(50).offset(100))
+    ///     .await?;
+    /// ```
+    pub async fn all_with<M>(&self, query: Query) -> Result<Vec<M>>
+    where
+        M: ModelTrait,
+        M::Entity: EntityTrait<Model = M>,
+    {
+        let mut select = M::Entity::find();
+        for (name, order) in query.order {
+            let column = <M::Entity as EntityTrait>::Column::from_str(&name)
+                .map_err(|_| DbError::Query(format!("unknown column `{}`", name)))?;
+            select = select.order_by(column, order);
+        }
+        select
+            .limit(query.limit)
+            .offset(query.offset)
+            .all(self.reader())
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Get up to `limit` records in key order, starting after `cursor`.
+    ///
+    /// Unlike an offset, the cursor stays fast deep into a table and doesn't
+    /// skip or repeat rows when rows are added in between pages. The model needs
+    /// a single integer or string primary key.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let page = db.page_after::<Post>(None, 20).await?;
+    /// let next = db.page_after::<Post>(page.next, 20).await?;
+    /// ```
+    pub async fn page_after<M>(&self, cursor: Option<Cursor>, limit: u64) -> Result<Paged<M>>
     where
         M: ModelTrait,
         M::Entity: EntityTrait<Model = M>,
     {
-        M::Entity::find().all(&*self.conn).await.map_err(Into::into)
+        let mut keys = <M::Entity as EntityTrait>::PrimaryKey::iter();
+        let (Some(key), None) = (keys.next(), keys.next()) else {
+            return Err(DbError::Query(
+                "page_after needs a single-column primary key".to_string(),
+            ));
+        };
+        let column = key.into_column();
+        if !Cursor::supports(column.def().get_column_type()) {
+            return Err(DbError::Query(
+                "page_after needs an integer or string primary key".to_string(),
+            ));
+        }
+
+        let mut select = M::Entity::find().order_by_asc(column);
+        if let Some(cursor) = cursor {
+            select = select.filter(column.gt(cursor.key()));
+        }
+        // One extra row tells whether there is a next page; SQL limits are signed
+        let fetch = limit.saturating_add(1).min(i64::MAX as u64);
+        let mut items = select.limit(fetch).all(self.reader()).await?;
+        let next = if items.len() as u64 > limit {
+            items.truncate(limit as usize);
+            items.last().map(|last| Cursor::of(&last.get(column)))
+        } else {
+            None
+        };
+        Ok(Paged { items, next })
     }
 
     /// Get a single record by primary key.
@@ -122,37 +459,79 @@ impl Db {
         id: <<M::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType,
     ) -> Result<Option<M>>
     where
-        M: ModelTrait,
+        M: ModelTrait + FromQueryResult,
         M::Entity: EntityTrait<Model = M>,
     {
-        M::Entity::find_by_id(id)
-            .one(&*self.conn)
+        let conn = self.reader();
+        let backend = conn.get_database_backend();
+        let params = id.into_value_tuple().into_iter().collect();
+        let stmt = cache::statement::<M::Entity>(backend, Operation::Get, params, |params| {
+            let mut select = M::Entity::find();
+            for (key, value) in <M::Entity as EntityTrait>::PrimaryKey::iter().zip(params) {
+                select = select.filter(key.into_column().eq(value));
+            }
+            select.limit(1).build(backend)
+        });
+        M::Entity::find()
+            .from_raw_sql(stmt)
+            .one(conn)
             .await
             .map_err(Into::into)
     }
 
+    /// Hit and miss counts for the SQL text cached by `all` and `get`.
+    ///
+    /// Only the rendered SQL is cached; statements are still prepared by the
+    /// driver on each connection.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let stats = Db::sql_cache_stats();
+    /// println!("{} of {} queries reused SQL", stats.hits, stats.hits + stats.misses);
+    /// ```
+    pub fn sql_cache_stats() -> SqlCacheStats {
+        cache::stats()
+    }
+
     /// Insert a new record from a form/DTO.
     ///
+    /// The row is inserted in a transaction and only committed if the model's
+    /// `Validate` hook accepts it, so database defaults and keys are filled in
+    /// when it runs.
+    ///
     /// # Example
     /// ```ignore
     /// let task = db.insert::<Task, _>(NewTask { title: "...".into() }).await?;
     /// ```
     pub async fn insert<M, F>(&self, form: F) -> Result<M>
     where
-        M: ModelTrait + IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
+        M: ModelTrait + Validate + IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
         M::Entity: EntityTrait<Model = M>,
         F: IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
         <M::Entity as EntityTrait>::ActiveModel: ActiveModelTrait<Entity = M::Entity> + Send,
     {
         let active_model = form.into_active_model();
-        let result = active_model.insert(&*self.conn).await?;
+        let txn = self.conn.begin().await?;
+        let result = active_model.insert(&txn).await?;
+        if let Err(err) = validate(&result) {
+            txn.rollback().await?;
+            return Err(err);
+        }
+        if audited::<M::Entity>() {
+            let actor = self.actor.as_deref();
+            record(&txn, actor, AuditAction::Insert, None, Some(&result)).await?;
+        }
+        txn.commit().await?;
         Ok(result)
     }
 
     /// Update a record by applying a mutation function to the model.
     ///
     /// The closure receives a mutable reference to the model data,
-    /// which you can modify directly. The changes are then saved to the database.
+    /// which you can modify directly. The changes are then checked with the
+    /// model's `Validate` hook and saved to the database. For `#[version]`
+    /// models, the save fails with `DbError::Conflict` if the row's version no
+    /// longer matches the model's.
     ///
     /// # Example
     /// ```ignore
@@ -166,19 +545,71 @@ impl Db {
         mutate: F,
     ) -> Result<M>
     where
-        M: ModelTrait + IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
+        M: ModelTrait
+            + Validate
+            + Versioned
+            + IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
         M::Entity: EntityTrait<Model = M>,
-        <M::Entity as EntityTrait>::ActiveModel: ActiveModelTrait<Entity = M::Entity> + Send,
+        <M::Entity as EntityTrait>::ActiveModel:
+            ActiveModelTrait<Entity = M::Entity> + ActiveModelBehavior + Send,
         F: FnOnce(&mut M),
     {
-        let mut model = self.get::<M>(id).await?.ok_or(DbError::NotFound)?;
+        let mut model = M::Entity::find_by_id(id)
+            .one(self.conn.as_ref())
+            .await?
+            .ok_or(DbError::NotFound)?;
 
         // Apply the user's mutation to the model
+        let before = model.clone();
         mutate(&mut model);
+        self.save(&before, model).await
+    }
 
-        // Convert to ActiveModel and save
-        let active_model = model.into_active_model();
-        let updated = active_model.update(&*self.conn).await?;
+    /// Validate a changed model and write it back, checking its `#[version]`.
+    pub(crate) async fn save<M>(&self, before: &M, mut model: M) -> Result<M>
+    where
+        M: ModelTrait
+            + Validate
+            + Versioned
+            + IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
+        M::Entity: EntityTrait<Model = M>,
+        <M::Entity as EntityTrait>::ActiveModel:
+            ActiveModelTrait<Entity = M::Entity> + ActiveModelBehavior + Send,
+    {
+        validate(&model)?;
+        let version = model.next_version();
+
+        // Every column is written, since the mutation may have touched any of them
+        let active_model = model.into_active_model().reset_all();
+        let txn = self.conn.begin().await?;
+        let active_model = ActiveModelBehavior::before_save(active_model, &txn, false).await?;
+        let mut update = M::Entity::update(active_model).validate()?;
+        if let Some((column, expected)) = &version {
+            update = update.filter(column.eq(expected.clone()));
+        }
+        let updated = match update.exec(&txn).await {
+            Ok(updated) => updated,
+            Err(sea_orm::DbErr::RecordNotUpdated) if version.is_some() => {
+                return Err(DbError::Conflict)
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let updated = <<M::Entity as EntityTrait>::ActiveModel as ActiveModelBehavior>::after_save(
+            updated, &txn, false,
+        )
+        .await?;
+        if audited::<M::Entity>() {
+            let actor = self.actor.as_deref();
+            record(
+                &txn,
+                actor,
+                AuditAction::Update,
+                Some(before),
+                Some(&updated),
+            )
+            .await?;
+        }
+        txn.commit().await?;
         Ok(updated)
     }
 
@@ -194,9 +625,13 @@ impl Db {
         field: F,
     ) -> Result<M>
     where
-        M: ModelTrait + IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
+        M: ModelTrait
+            + Validate
+            + Versioned
+            + IntoActiveModel<<M::Entity as EntityTrait>::ActiveModel>,
         M::Entity: EntityTrait<Model = M>,
-        <M::Entity as EntityTrait>::ActiveModel: ActiveModelTrait<Entity = M::Entity> + Send,
+        <M::Entity as EntityTrait>::ActiveModel:
+            ActiveModelTrait<Entity = M::Entity> + ActiveModelBehavior + Send,
         F: FnOnce(&mut M) -> &mut bool,
     {
         self.update::<M, _>(id, |model| {
@@ -222,29 +657,29 @@ impl Db {
         <M::Entity as EntityTrait>::ActiveModel:
             ActiveModelTrait<Entity = M::Entity> + ActiveModelBehavior + Send,
     {
-        let model = self.get::<M>(id).await?.ok_or(DbError::NotFound)?;
+        let model = M::Entity::find_by_id(id)
+            .one(self.conn.as_ref())
+            .await?
+            .ok_or(DbError::NotFound)?;
 
-        model.delete(&*self.conn).await?;
+        let txn = self.conn.begin().await?;
+        model.clone().delete(&txn).await?;
+        if audited::<M::Entity>() {
+            let actor = self.actor.as_deref();
+            record(&txn, actor, AuditAction::Delete, Some(&model), None).await?;
+        }
+        txn.commit().await?;
         Ok(())
     }
+}
 
-    /// Run schema synchronization for all registered entities.
-    ///
-    /// This creates tables for all entities that have been registered
-    /// via the #[model] attribute macro.
-    pub async fn migrate(&self) -> Result<()> {
-        let backend = self.conn.get_database_backend();
-        let schema = Schema::new(backend);
-
-        for registration in inventory::iter::<EntityRegistration> {
-            let stmt = (registration.create_table)(&schema);
-            // Use IF NOT EXISTS for idempotent migrations
-            self.conn
-                .execute(&stmt)
-                .await
-                .map_err(|e| DbError::Query(e.to_string()))?;
-        }
-        Ok(())
+/// Optimistic locking for models with a `#[version]` field, implemented by `#[model]`.
+///
+/// Hand-written entities can use the default, which saves without a version check.
+pub trait Versioned: ModelTrait {
+    /// Bump the version, returning its column and the value it had.
+    fn next_version(&mut self) -> Option<(<Self::Entity as EntityTrait>::Column, sea_orm::Value)> {
+        None
     }
 }
 
@@ -270,6 +705,9 @@ where
 
 impl FromRef<acacia_core::AppState> for Db {
     fn from_ref(state: &acacia_core::AppState) -> Self {
-        Db::new(state.db.clone().expect("Database not configured"))
+        Db::with_replicas(
+            state.db.clone().expect("Database not configured"),
+            state.replicas.clone(),
+        )
     }
 }
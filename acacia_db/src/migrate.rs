@@ -0,0 +1,514 @@
+//! Creating and dropping the tables of registered models.
+//!
+//! Each table created is recorded in `_acacia_migrations` with a checksum of
+//! its SQL, so the most recent ones can be rolled back in order and a model
+//! that changed after its table was created is caught instead of ignored.
+//! New nullable or defaulted columns are added with `ALTER TABLE`.
+//! Views hold no rows, so they are dropped and created again on every run.
+
+use crate::{audit, Db, DbError, EntityRegistration, Result, ViewRegistration};
+use sea_orm::sea_query::{
+    Alias, ColumnDef, Expr, ExprTrait, Order, Query, Table, TableCreateStatement, TableName,
+    TableRef,
+};
+use sea_orm::{ConnectionTrait, Database, DbBackend, Schema, Statement, StatementBuilder};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+const HISTORY: &str = "_acacia_migrations";
+
+impl Db {
+    /// Run schema synchronization for all registered entities.
+    ///
+    /// This creates tables for all entities that have been registered
+    /// via the #[model] attribute macro. Tables created by an earlier run are
+    /// skipped, or get new columns when their model gained nullable or
+    /// defaulted fields. Nothing runs if a table changed in any other way.
+    pub async fn migrate(&self) -> Result<()> {
+        let backend = self.conn.get_database_backend();
+        let schema = Schema::new(backend);
+        self.run(&history_table()).await?;
+        if audit::any_audited() {
+            self.run(&audit::audit_table()).await?;
+        }
+        let applied = self.applied().await?;
+
+        let mut pending = Vec::new();
+        let mut changed = Vec::new();
+        for registration in inventory::iter::<EntityRegistration> {
+            let stmt = (registration.create_table)(&schema);
+            let indexes = (registration.create_indexes)(&schema);
+            let table = table_name(&stmt);
+            let checksum = checksum(backend, &stmt, &indexes);
+            match applied.iter().find(|(name, _)| *name == table) {
+                Some((_, recorded)) if *recorded == checksum => {}
+                Some(_) => {
+                    let problems = self.drift(&stmt).await?;
+                    let mut added = Vec::new();
+                    for problem in &problems {
+                        let column = stmt
+                            .get_columns()
+                            .iter()
+                            .find(|column| column.get_column_name() == problem.column);
+                        match (&problem.kind, column) {
+                            (DriftKind::MissingColumn, Some(column)) if additive(column) => {
+                                added.push(column.clone())
+                            }
+                            _ => {
+                                return Err(DbError::Migration(format!(
+                                    "table `{}` no longer matches its model:{}\n\
+                                     only new nullable or defaulted columns are added automatically; \
+                                     recreate it with `migrate_down` or `MigratePolicy::Fresh`",
+                                    table,
+                                    describe(&problems)
+                                )))
+                            }
+                        }
+                    }
+                    changed.push((table, checksum, added, indexes));
+                }
+                None => pending.push((table, checksum, stmt, indexes)),
+            }
+        }
+
+        for (table, checksum, stmt, indexes) in pending {
+            // IF NOT EXISTS adopts tables created before the history was kept
+            self.run(&stmt).await?;
+            for index in indexes {
+                self.run(&index).await?;
+            }
+            let record = Query::insert()
+                .into_table(Alias::new(HISTORY))
+                .columns([Alias::new("name"), Alias::new("checksum")])
+                .values_panic([table.into(), checksum.into()])
+                .to_owned();
+            self.run(&record).await?;
+        }
+
+        for (table, checksum, added, indexes) in changed {
+            for column in added {
+                let alter = Table::alter()
+                    .table(Alias::new(&table))
+                    .add_column(column)
+                    .to_owned();
+                self.run(&alter).await?;
+            }
+            for index in indexes {
+                self.run(&index).await?;
+            }
+            let record = Query::update()
+                .table(Alias::new(HISTORY))
+                .value(Alias::new("checksum"), checksum)
+                .and_where(Expr::col(Alias::new("name")).eq(table))
+                .to_owned();
+            self.run(&record).await?;
+        }
+        self.create_views().await
+    }
+
+    /// Create the view of every `#[view]` model, replacing older definitions.
+    async fn create_views(&self) -> Result<()> {
+        self.drop_views().await?;
+        let backend = self.conn.get_database_backend();
+        for view in inventory::iter::<ViewRegistration> {
+            let name = quote(backend, view.name);
+            let create = format!("CREATE VIEW {} AS {}", name, view.query);
+            self.run_raw(create).await.map_err(|err| {
+                DbError::Migration(format!(
+                    "view `{}` could not be created: {}",
+                    view.name, err
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Drop every registered view, before the tables they read from.
+    async fn drop_views(&self) -> Result<()> {
+        let backend = self.conn.get_database_backend();
+        for view in inventory::iter::<ViewRegistration> {
+            self.run_raw(format!("DROP VIEW IF EXISTS {}", quote(backend, view.name)))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Drop the `steps` most recently created tables, newest first.
+    ///
+    /// Views are dropped too, since they may read from those tables. The next
+    /// `migrate` creates them all again, the tables empty.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // Undo the table added by the last deploy
+    /// db.migrate_down(1).await?;
+    /// ```
+    pub async fn migrate_down(&self, steps: usize) -> Result<()> {
+        self.run(&history_table()).await?;
+        self.drop_views().await?;
+        for (table, _) in self.applied().await?.into_iter().rev().take(steps) {
+            self.run(
+                &Table::drop()
+                    .table(Alias::new(&table))
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+            let forget = Query::delete()
+                .from_table(Alias::new(HISTORY))
+                .and_where(Expr::col(Alias::new("name")).eq(table))
+                .to_owned();
+            self.run(&forget).await?;
+        }
+        Ok(())
+    }
+
+    /// Drop every registered table and create them again, for development.
+    ///
+    /// All rows are lost; this is what `MigratePolicy::Fresh` runs at startup.
+    pub async fn migrate_fresh(&self) -> Result<()> {
+        self.migrate_down(usize::MAX).await?;
+        // Tables created before they were recorded
+        let schema = Schema::new(self.conn.get_database_backend());
+        for registration in inventory::iter::<EntityRegistration> {
+            let table = table_name(&(registration.create_table)(&schema));
+            self.run(
+                &Table::drop()
+                    .table(Alias::new(table))
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+        }
+        self.migrate().await
+    }
+
+    /// Compare every registered table with the database, without changing anything.
+    ///
+    /// Catches a model that gained a field while its table stayed as it was. The
+    /// error lists each missing table, missing or extra column, and type or
+    /// nullability difference; types are compared on SQLite only. This is what
+    /// `MigratePolicy::Check` runs at startup.
+    ///
+    /// # Example
+    /// ```ignore
+    /// if let Err(err) = db.check_schema().await {
+    ///     eprintln!("{}", err);
+    /// }
+    /// ```
+    pub async fn check_schema(&self) -> Result<()> {
+        let schema = Schema::new(self.conn.get_database_backend());
+        let mut problems = Vec::new();
+        for registration in inventory::iter::<EntityRegistration> {
+            let stmt = (registration.create_table)(&schema);
+            problems.extend(self.drift(&stmt).await?);
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(DbError::Migration(format!(
+                "the database schema doesn't match the models:{}",
+                describe(&problems)
+            )))
+        }
+    }
+
+    /// How the live table differs from `stmt`.
+    ///
+    /// Column types are only compared on SQLite; other backends are checked
+    /// for missing and extra columns and nullability.
+    async fn drift(&self, stmt: &TableCreateStatement) -> Result<Vec<Drift>> {
+        let table = table_name(stmt);
+        let (want, have) = match self.conn.get_database_backend() {
+            DbBackend::Sqlite => {
+                // Create the expected table in a scratch database to read it back the same way
+                let expected = Database::connect("sqlite::memory:").await?;
+                expected.execute(stmt).await?;
+                (
+                    columns(&expected, &table).await?,
+                    columns(&*self.conn, &table).await?,
+                )
+            }
+            backend => (
+                declared_columns(stmt),
+                information_schema_columns(&*self.conn, backend, &table).await?,
+            ),
+        };
+
+        let drift = |column: &str, kind| Drift {
+            table: table.clone(),
+            column: column.to_string(),
+            kind,
+        };
+        if have.is_empty() {
+            return Ok(vec![drift("", DriftKind::MissingTable)]);
+        }
+        let mut problems = Vec::new();
+        for column in &want {
+            let Some(live) = have.iter().find(|live| live.name == column.name) else {
+                problems.push(drift(&column.name, DriftKind::MissingColumn));
+                continue;
+            };
+            if let (Some(live_kind), Some(kind)) = (&live.kind, &column.kind) {
+                if !live_kind.eq_ignore_ascii_case(kind) {
+                    problems.push(drift(
+                        &column.name,
+                        DriftKind::Type {
+                            live: live_kind.clone(),
+                            expected: kind.clone(),
+                        },
+                    ));
+                }
+            }
+            if live.not_null != column.not_null {
+                problems.push(drift(
+                    &column.name,
+                    DriftKind::Nullability {
+                        live: live.not_null,
+                        expected: column.not_null,
+                    },
+                ));
+            }
+        }
+        for live in &have {
+            if !want.iter().any(|column| column.name == live.name) {
+                problems.push(drift(&live.name, DriftKind::ExtraColumn));
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Tables recorded in the history with their checksums, oldest first.
+    async fn applied(&self) -> Result<Vec<(String, String)>> {
+        let select = Query::select()
+            .columns([Alias::new("name"), Alias::new("checksum")])
+            .from(Alias::new(HISTORY))
+            .order_by(Alias::new("id"), Order::Asc)
+            .to_owned();
+        let rows = self
+            .conn
+            .query_all(&select)
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))?;
+        rows.iter()
+            .map(|row| {
+                Ok((
+                    row.try_get::<String>("", "name")?,
+                    row.try_get::<String>("", "checksum")?,
+                ))
+            })
+            .collect()
+    }
+
+    async fn run(&self, stmt: &impl StatementBuilder) -> Result<()> {
+        self.conn
+            .execute(stmt)
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn run_raw(&self, sql: String) -> Result<()> {
+        let backend = self.conn.get_database_backend();
+        self.conn
+            .execute_raw(Statement::from_string(backend, sql))
+            .await
+            .map_err(|e| DbError::Query(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// `name` as a quoted identifier for `backend`.
+fn quote(backend: DbBackend, name: &str) -> String {
+    match backend {
+        DbBackend::MySql => format!("`{}`", name.replace('`', "``")),
+        _ => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+fn history_table() -> TableCreateStatement {
+    Table::create()
+        .table(Alias::new(HISTORY))
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Alias::new("id"))
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Alias::new("name"))
+                .string()
+                .not_null()
+                .unique_key(),
+        )
+        .col(ColumnDef::new(Alias::new("checksum")).string().not_null())
+        .col(
+            ColumnDef::new(Alias::new("applied_at"))
+                .timestamp()
+                .not_null()
+                .default(Expr::current_timestamp()),
+        )
+        .to_owned()
+}
+
+/// One way a live table differs from its model.
+struct Drift {
+    table: String,
+    column: String,
+    kind: DriftKind,
+}
+
+enum DriftKind {
+    MissingTable,
+    MissingColumn,
+    ExtraColumn,
+    Type { live: String, expected: String },
+    Nullability { live: bool, expected: bool },
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (table, column) = (&self.table, &self.column);
+        match &self.kind {
+            DriftKind::MissingTable => write!(f, "table `{}` is missing", table),
+            DriftKind::MissingColumn => write!(f, "{}.{}: column is missing", table, column),
+            DriftKind::ExtraColumn => write!(f, "{}.{}: column is not in the model", table, column),
+            DriftKind::Type { live, expected } => write!(
+                f,
+                "{}.{}: type is `{}`, the model expects `{}`",
+                table, column, live, expected
+            ),
+            DriftKind::Nullability { live, expected } => write!(
+                f,
+                "{}.{}: column is {}, the model expects {}",
+                table,
+                column,
+                nullability(*live),
+                nullability(*expected)
+            ),
+        }
+    }
+}
+
+/// One indented line per problem.
+fn describe(problems: &[Drift]) -> String {
+    problems
+        .iter()
+        .map(|problem| format!("\n  {}", problem))
+        .collect()
+}
+
+/// Whether a column can be added to a table that already has rows.
+fn additive(column: &ColumnDef) -> bool {
+    let spec = column.get_column_spec();
+    !spec.primary_key
+        && !spec.unique
+        && !spec.auto_increment
+        && (spec.nullable != Some(false) || spec.default.is_some())
+}
+
+/// A column as the database reports it, with its type on SQLite.
+struct Column {
+    name: String,
+    kind: Option<String>,
+    not_null: bool,
+}
+
+/// The columns of a SQLite `table`, or none if it doesn't exist.
+async fn columns(conn: &impl ConnectionTrait, table: &str) -> Result<Vec<Column>> {
+    let pragma = format!("PRAGMA table_info(\"{}\")", table.replace('"', "\"\""));
+    let rows = conn
+        .query_all_raw(Statement::from_string(DbBackend::Sqlite, pragma))
+        .await?;
+    rows.iter()
+        .map(|row| {
+            Ok(Column {
+                name: row.try_get("", "name")?,
+                kind: Some(row.try_get("", "type")?),
+                not_null: row.try_get::<i32>("", "notnull")? != 0,
+            })
+        })
+        .collect()
+}
+
+/// The columns of a Postgres or MySQL `table` in the current schema, or none
+/// if it doesn't exist.
+async fn information_schema_columns(
+    conn: &impl ConnectionTrait,
+    backend: DbBackend,
+    table: &str,
+) -> Result<Vec<Column>> {
+    let sql = match backend {
+        DbBackend::MySql => {
+            "SELECT column_name AS name, is_nullable AS nullable FROM information_schema.columns \
+             WHERE table_schema = DATABASE() AND table_name = ? ORDER BY ordinal_position"
+        }
+        _ => {
+            "SELECT column_name AS name, is_nullable AS nullable FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = $1 ORDER BY ordinal_position"
+        }
+    };
+    let rows = conn
+        .query_all_raw(Statement::from_sql_and_values(backend, sql, [table.into()]))
+        .await?;
+    rows.iter()
+        .map(|row| {
+            Ok(Column {
+                name: row.try_get("", "name")?,
+                kind: None,
+                not_null: row.try_get::<String>("", "nullable")? == "NO",
+            })
+        })
+        .collect()
+}
+
+/// The columns `stmt` declares, without types.
+fn declared_columns(stmt: &TableCreateStatement) -> Vec<Column> {
+    stmt.get_columns()
+        .iter()
+        .map(|column| {
+            let spec = column.get_column_spec();
+            Column {
+                name: column.get_column_name(),
+                kind: None,
+                not_null: spec.nullable == Some(false) || spec.primary_key,
+            }
+        })
+        .collect()
+}
+
+fn nullability(not_null: bool) -> &'static str {
+    if not_null {
+        "NOT NULL"
+    } else {
+        "nullable"
+    }
+}
+
+/// SHA-256 of the SQL that creates a table and its indexes.
+fn checksum(
+    backend: DbBackend,
+    stmt: &TableCreateStatement,
+    indexes: &[sea_orm::sea_query::IndexCreateStatement],
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(backend.build(stmt).sql);
+    for index in indexes {
+        hasher.update(backend.build(index).sql);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn table_name(stmt: &TableCreateStatement) -> String {
+    match stmt.get_table_name() {
+        Some(TableRef::Table(TableName(_, table), _)) => table.to_string(),
+        _ => String::new(),
+    }
+}
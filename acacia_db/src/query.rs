@@ -0,0 +1,38 @@
+//! Ordering and paging options for `Db::all_with`.
+
+use sea_orm::Order;
+
+/// How to sort and slice the rows returned by [`Db::all_with`](crate::Db::all_with).
+///
+/// # Example
+/// ```ignore
+/// let recent = db
+///     .all_with::<Task>(Query::default().order_by("created_at", Order::Desc).limit(50))
+///     .await?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    pub(crate) order: Vec<(String, Order)>,
+    pub(crate) limit: Option<u64>,
+    pub(crate) offset: Option<u64>,
+}
+
+impl Query {
+    /// Sort by a column, named as the model field. Later calls break ties.
+    pub fn order_by(mut self, column: &str, order: Order) -> Self {
+        self.order.push((column.to_string(), order));
+        self
+    }
+
+    /// Return at most `limit` rows.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip the first `offset` rows.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
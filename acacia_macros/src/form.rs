@@ -4,15 +4,18 @@
 //! ```ignore
 //! #[form(Task)]
 //! pub struct NewTask {
+//!     #[required]
+//!     #[max_len(80)]
 //!     pub title: String,
 //! }
 //! ```
 //!
-//! Macro generates Deserialize impl and IntoActiveModel<task::ActiveModel>.
+//! Macro generates Deserialize, Validate, FormSchema and IntoActiveModel<task::ActiveModel>.
 
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident};
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, Ident, LitInt, LitStr};
 
 /// Attribute macro: #[form(ModelName)]
 pub fn form_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -23,7 +26,7 @@ pub fn form_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
     };
 
     let input = parse_macro_input!(item as DeriveInput);
-    generate_form(&input, model_name)
+    generate_form(&input, model_name, true)
 }
 
 /// Derive macro: #[derive(Form)] with optional #[for_model(ModelName)]
@@ -39,10 +42,43 @@ pub fn derive_form_impl(input: TokenStream) -> TokenStream {
         }
     });
 
-    generate_form(&input, model_name)
+    // A derive can't replace the struct, so only emit the impls
+    generate_form(&input, model_name, false)
 }
 
-fn generate_form(input: &DeriveInput, model_name: Option<Ident>) -> TokenStream {
+/// Validation rules declared on a form field.
+#[derive(Default)]
+struct FieldRules {
+    required: bool,
+    max_len: Option<LitInt>,
+    pattern: Option<LitStr>,
+}
+
+impl FieldRules {
+    fn from_field(field: &Field) -> syn::Result<Self> {
+        let mut rules = FieldRules::default();
+        for attr in &field.attrs {
+            if attr.path().is_ident("required") {
+                attr.meta.require_path_only()?;
+                rules.required = true;
+            } else if attr.path().is_ident("max_len") {
+                rules.max_len = Some(attr.parse_args()?);
+            } else if attr.path().is_ident("pattern") {
+                let pattern: LitStr = attr.parse_args()?;
+                if let Err(err) = regex_syntax::parse(&pattern.value()) {
+                    return Err(syn::Error::new(
+                        pattern.span(),
+                        format!("invalid pattern: {}", err),
+                    ));
+                }
+                rules.pattern = Some(pattern);
+            }
+        }
+        Ok(rules)
+    }
+}
+
+fn generate_form(input: &DeriveInput, model_name: Option<Ident>, emit_struct: bool) -> TokenStream {
     let name = &input.ident;
     let vis = &input.vis;
 
@@ -58,15 +94,72 @@ fn generate_form(input: &DeriveInput, model_name: Option<Ident>) -> TokenStream
     let field_names: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
     let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
 
+    // Keep user attributes (derives, #[validate(...)]) but drop our own rule markers.
+    // `#[serde(...)]` goes to the generated Deserialize, and stays on the struct
+    // only where one of its own derives understands it.
+    let keeps_serde = derives(&input.attrs, "Serialize") || derives(&input.attrs, "Deserialize");
+    let struct_attrs: Vec<&syn::Attribute> = input
+        .attrs
+        .iter()
+        .filter(|a| keeps_serde || !is_serde_attr(a))
+        .collect();
+    let field_attrs: Vec<Vec<&syn::Attribute>> = fields
+        .iter()
+        .map(|f| {
+            f.attrs
+                .iter()
+                .filter(|a| !is_rule_attr(a) && (keeps_serde || !is_serde_attr(a)))
+                .collect()
+        })
+        .collect();
+    let helper_attrs: Vec<&syn::Attribute> =
+        input.attrs.iter().filter(|a| is_serde_attr(a)).collect();
+    let helper_field_attrs: Vec<TokenStream2> = fields
+        .iter()
+        .map(|f| {
+            let attrs = f.attrs.iter().filter(|a| is_serde_attr(a));
+            let checkbox = checkbox_attr(f);
+            quote! { #(#attrs)* #checkbox }
+        })
+        .collect();
+
+    // Inputs and errors use the names the form is submitted with
+    let serde_names = match SerdeNames::from_attrs(&input.attrs) {
+        Ok(names) => names,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let html_names = match fields
+        .iter()
+        .map(|field| serde_names.field_name(field))
+        .collect::<syn::Result<Vec<_>>>()
+    {
+        Ok(names) => names,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // Types deriving `validator::Validate` get those rules checked too
+    let uses_validator = derives(&input.attrs, "Validate");
+
+    let rules = match fields
+        .iter()
+        .map(FieldRules::from_field)
+        .collect::<syn::Result<Vec<_>>>()
+    {
+        Ok(rules) => rules,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     // Generate IntoActiveModel implementation if model is specified
     let into_active_model_impl = model_name.map(|model_name| {
         // The entity module name is snake_case of the model name
         let mod_name = format_ident!("{}", to_snake_case(&model_name.to_string()));
 
-        // Generate the field assignments for ActiveModel
+        // Generate the field assignments for ActiveModel; uploads aren't columns
         let field_assignments: Vec<_> = field_names
             .iter()
-            .map(|name| {
+            .zip(&field_types)
+            .filter(|(_, ty)| inner_type_name(ty).as_deref() != Some("Upload"))
+            .map(|(name, _)| {
                 quote! {
                     #name: ::sea_orm::ActiveValue::Set(self.#name)
                 }
@@ -85,10 +178,104 @@ fn generate_form(input: &DeriveInput, model_name: Option<Ident>) -> TokenStream
         }
     });
 
-    let expanded = quote! {
-        #vis struct #name {
-            #(#vis #field_names: #field_types,)*
+    // Server-side checks mirroring the HTML validation attributes
+    let checks: Vec<TokenStream2> = field_names
+        .iter()
+        .zip(&rules)
+        .zip(&html_names)
+        .map(|((field, rules), field_str)| {
+            let mut checks = TokenStream2::new();
+            if rules.required {
+                checks.extend(quote! {
+                    if !::acacia_core::check_required(&self.#field) {
+                        errors.push(::acacia_core::FieldError::new(#field_str, "is required"));
+                    }
+                });
+            }
+            if let Some(max_len) = &rules.max_len {
+                checks.extend(quote! {
+                    if !::acacia_core::check_max_len(&self.#field, #max_len) {
+                        errors.push(::acacia_core::FieldError::new(
+                            #field_str,
+                            format!("must be at most {} characters", #max_len),
+                        ));
+                    }
+                });
+            }
+            if let Some(pattern) = &rules.pattern {
+                checks.extend(quote! {
+                    if !::acacia_core::check_pattern(&self.#field, #pattern) {
+                        errors.push(::acacia_core::FieldError::new(
+                            #field_str,
+                            "does not match the required format",
+                        ));
+                    }
+                });
+            }
+            checks
+        })
+        .collect();
+
+    let field_meta: Vec<TokenStream2> = fields
+        .iter()
+        .zip(&rules)
+        .zip(&html_names)
+        .map(|((field, rules), field_str)| {
+            let input_type = input_type(&field.ty);
+            let required = rules.required;
+            let max_len = match &rules.max_len {
+                Some(max_len) => quote! { Some(#max_len) },
+                None => quote! { None },
+            };
+            let pattern = match &rules.pattern {
+                Some(pattern) => quote! { Some(#pattern) },
+                None => quote! { None },
+            };
+            quote! {
+                ::acacia_core::FormField {
+                    name: #field_str,
+                    input_type: #input_type,
+                    required: #required,
+                    max_len: #max_len,
+                    pattern: #pattern,
+                }
+            }
+        })
+        .collect();
+
+    let validator_check = uses_validator.then(|| {
+        quote! {
+            if let Err(validation) = ::validator::Validate::validate(self) {
+                errors.extend(::acacia_core::validator_errors(validation));
+            }
+        }
+    });
+
+    // `#[serde(default)]` on the struct fills missing fields from its `Default`
+    let helper_default = serde_names.default.then(|| {
+        quote! {
+            impl ::std::default::Default for Helper {
+                fn default() -> Self {
+                    let defaults = <#name as ::std::default::Default>::default();
+                    Self {
+                        #(#field_names: defaults.#field_names,)*
+                    }
+                }
+            }
+        }
+    });
+
+    let struct_def = emit_struct.then(|| {
+        quote! {
+            #(#struct_attrs)*
+            #vis struct #name {
+                #(#(#field_attrs)* #vis #field_names: #field_types,)*
+            }
         }
+    });
+
+    let expanded = quote! {
+        #struct_def
 
         // Auto-generate Deserialize using serde (required for form parsing)
         impl<'de> ::serde::Deserialize<'de> for #name {
@@ -97,9 +284,11 @@ fn generate_form(input: &DeriveInput, model_name: Option<Ident>) -> TokenStream
                 D: ::serde::Deserializer<'de>,
             {
                 #[derive(::serde::Deserialize)]
+                #(#helper_attrs)*
                 struct Helper {
-                    #(#field_names: #field_types,)*
+                    #(#helper_field_attrs #field_names: #field_types,)*
                 }
+                #helper_default
 
                 let helper = Helper::deserialize(deserializer)?;
                 Ok(Self {
@@ -111,12 +300,223 @@ fn generate_form(input: &DeriveInput, model_name: Option<Ident>) -> TokenStream
         // Form trait implementation
         impl ::acacia_db::Form for #name {}
 
+        impl ::acacia_core::Validate for #name {
+            fn validate(&self) -> ::std::result::Result<(), ::std::vec::Vec<::acacia_core::FieldError>> {
+                #[allow(unused_mut)]
+                let mut errors = ::std::vec::Vec::new();
+                #(#checks)*
+                #validator_check
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
+            }
+        }
+
+        ::inventory::submit! {
+            ::acacia_core::ValidateRegistration {
+                type_id: ::std::any::TypeId::of::<#name>,
+                validate: ::acacia_core::validate_any::<#name>,
+            }
+        }
+
+        impl ::acacia_core::FormSchema for #name {
+            const FIELDS: &'static [::acacia_core::FormField] = &[#(#field_meta),*];
+        }
+
         #into_active_model_impl
     };
 
     expanded.into()
 }
 
+/// Whether an attribute is one of the validation rules handled by this macro.
+fn is_rule_attr(attr: &syn::Attribute) -> bool {
+    ["required", "max_len", "pattern"]
+        .iter()
+        .any(|name| attr.path().is_ident(name))
+}
+
+/// Parse a `bool` or `Option<bool>` field as a checkbox, unless its own serde
+/// attributes already say how to fill it in.
+fn checkbox_attr(field: &Field) -> Option<TokenStream2> {
+    let syn::Type::Path(type_path) = &field.ty else {
+        return None;
+    };
+    let with = if type_path.path.is_ident("bool") {
+        "::acacia_core::form::checkbox"
+    } else if inner_type_name(&field.ty).as_deref() == Some("bool") {
+        "::acacia_core::form::optional_checkbox"
+    } else {
+        return None;
+    };
+    let mut default = true;
+    let mut deserialize = true;
+    for attr in field.attrs.iter().filter(|a| is_serde_attr(a)) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                default = false;
+            } else if meta.path.is_ident("with") || meta.path.is_ident("deserialize_with") {
+                deserialize = false;
+            }
+            skip_serde_meta(&meta)
+        });
+    }
+    let default = default.then(|| quote! { default, });
+    deserialize.then(|| {
+        quote! { #[serde(#default deserialize_with = #with)] }
+    })
+}
+
+/// Whether an attribute is `#[serde(...)]`.
+fn is_serde_attr(attr: &syn::Attribute) -> bool {
+    attr.path().is_ident("serde")
+}
+
+/// The `#[serde(...)]` options that change what a form's fields are submitted as.
+#[derive(Default)]
+struct SerdeNames {
+    rename_all: Option<LitStr>,
+    default: bool,
+}
+
+impl SerdeNames {
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut names = SerdeNames::default();
+        for attr in attrs.iter().filter(|a| is_serde_attr(a)) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    if rename_rule(&rule.value(), "x").is_none() {
+                        return Err(syn::Error::new(rule.span(), "unknown rename rule"));
+                    }
+                    names.rename_all = Some(rule);
+                } else if meta.path.is_ident("default") {
+                    if meta.input.peek(syn::Token![=]) {
+                        skip_serde_meta(&meta)?;
+                    } else {
+                        names.default = true;
+                    }
+                } else {
+                    skip_serde_meta(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(names)
+    }
+
+    /// The name a field is submitted with: its `rename`, or its Rust name
+    /// changed by the struct's `rename_all`.
+    fn field_name(&self, field: &Field) -> syn::Result<String> {
+        let mut rename = None;
+        for attr in field.attrs.iter().filter(|a| is_serde_attr(a)) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    rename = Some(name.value());
+                } else {
+                    skip_serde_meta(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+        let name = field.ident.as_ref().unwrap().to_string();
+        Ok(match (rename, &self.rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(rule)) => rename_rule(&rule.value(), &name).unwrap_or(name),
+            (None, None) => name,
+        })
+    }
+}
+
+/// Consume the value of a serde option this macro doesn't interpret.
+fn skip_serde_meta(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_serde_meta(&nested))?;
+    }
+    Ok(())
+}
+
+/// Apply a serde `rename_all` rule to a snake_case field name.
+fn rename_rule(rule: &str, name: &str) -> Option<String> {
+    let words = name.split('_').filter(|w| !w.is_empty());
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    };
+    Some(match rule {
+        "lowercase" | "snake_case" => name.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_uppercase(),
+        "PascalCase" => words.map(capitalize).collect(),
+        "camelCase" => {
+            let pascal: String = words.map(capitalize).collect();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|first| first.to_lowercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.replace('_', "-").to_uppercase(),
+        _ => return None,
+    })
+}
+
+/// Whether the struct derives a trait with the given name, such as `Validate`
+/// from the `validator` crate.
+fn derives(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|a| a.path().is_ident("derive"))
+        .any(|attr| {
+            let mut found = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.segments.last().is_some_and(|s| s.ident == name) {
+                    found = true;
+                }
+                Ok(())
+            });
+            found
+        })
+}
+
+/// Infer the HTML input type for a field from its Rust type.
+pub(crate) fn input_type(ty: &syn::Type) -> &'static str {
+    let Some(name) = inner_type_name(ty) else {
+        return "text";
+    };
+    match name.as_str() {
+        "bool" => "checkbox",
+        "Upload" => "file",
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+        | "usize" | "f32" | "f64" | "Decimal" => "number",
+        _ => "text",
+    }
+}
+
+/// The last path segment of a type, looking through `Option<T>`.
+fn inner_type_name(ty: &syn::Type) -> Option<String> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident == "Option" {
+        if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+            if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+                return inner_type_name(inner);
+            }
+        }
+    }
+    Some(segment.ident.to_string())
+}
+
 /// Convert a string to snake_case
 fn to_snake_case(s: &str) -> String {
     let mut result = String::new();
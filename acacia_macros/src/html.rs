@@ -8,89 +8,156 @@ use rstml::{
     Parser, ParserConfig,
 };
 use std::collections::HashSet;
-use syn::Expr;
+use syn::{parse::Parser as _, Expr, Token};
 
 pub fn html_impl(input: TokenStream) -> TokenStream {
-    let self_closed: HashSet<&'static str> = [
-        "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source",
-        "track", "wbr",
-    ]
-    .into_iter()
-    .collect();
+    let output = match render_nodes(input.into()) {
+        Ok(output) => output,
+        Err(err) => return err,
+    };
 
-    let config = ParserConfig::new()
-        .recover_block(true)
-        .always_self_closed_elements(self_closed);
-    let parser = Parser::new(config);
-    let nodes = match parser.parse_simple(input) {
-        Ok(nodes) => nodes,
-        Err(err) => {
-            return err.into_compile_error().into();
+    // Sized for the static markup; dynamic parts grow it as needed
+    let capacity = output.static_len();
+    let output = output.into_tokens();
+    let expanded = quote! {
+        {
+            let mut __buffer = String::with_capacity(#capacity);
+            {
+                let __html = &mut __buffer;
+                #output
+            }
+            ::acacia_core::Fragment::new(__buffer)
         }
     };
 
-    let output = process_nodes(&nodes);
+    expanded.into()
+}
+
+pub fn html_into_impl(input: TokenStream) -> TokenStream {
+    let split = |input: syn::parse::ParseStream| {
+        let buffer: Expr = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let markup: TokenStream2 = input.parse()?;
+        Ok((buffer, markup))
+    };
+    let (buffer, markup) = match split.parse(input) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.into_compile_error().into(),
+    };
+    let output = match render_nodes(markup) {
+        Ok(output) => output,
+        Err(err) => return err,
+    };
 
+    let capacity = output.static_len();
+    let output = output.into_tokens();
     let expanded = quote! {
         {
-            let mut __html = String::new();
+            let __html: &mut String = #buffer;
+            __html.reserve(#capacity);
             #output
-            ::acacia_core::Fragment::new(__html)
         }
     };
 
     expanded.into()
 }
 
-fn process_nodes<C: CustomNode>(nodes: &[Node<C>]) -> TokenStream2 {
-    let mut output = TokenStream2::new();
+/// Generated statements, with adjacent static markup merged into one `push_str`.
+#[derive(Default)]
+struct Output {
+    parts: Vec<Part>,
+}
 
-    for node in nodes {
-        let node_output = process_node(node);
-        output.extend(node_output);
+enum Part {
+    Static(String),
+    Code(TokenStream2),
+}
+
+impl Output {
+    fn push_static(&mut self, html: &str) {
+        match self.parts.last_mut() {
+            Some(Part::Static(last)) => last.push_str(html),
+            _ => self.parts.push(Part::Static(html.to_string())),
+        }
     }
 
-    output
+    fn push_code(&mut self, code: TokenStream2) {
+        self.parts.push(Part::Code(code));
+    }
+
+    /// Bytes of markup that are always written.
+    fn static_len(&self) -> usize {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Static(html) => html.len(),
+                Part::Code(_) => 0,
+            })
+            .sum()
+    }
+
+    fn into_tokens(self) -> TokenStream2 {
+        self.parts
+            .into_iter()
+            .map(|part| match part {
+                Part::Static(html) => quote! { __html.push_str(#html); },
+                Part::Code(code) => code,
+            })
+            .collect()
+    }
 }
 
-fn process_node<C: CustomNode>(node: &Node<C>) -> TokenStream2 {
-    match node {
-        Node::Element(element) => process_element(element),
-        Node::Text(text) => {
-            let value = &text.value;
-            quote! {
-                __html.push_str(#value);
-            }
-        }
-        Node::RawText(raw) => {
-            let value = raw.to_string_best();
-            quote! {
-                __html.push_str(#value);
-            }
+/// Parse markup into statements that append to `__html`, a `&mut String`.
+fn render_nodes(input: TokenStream2) -> Result<Output, TokenStream> {
+    let self_closed: HashSet<&'static str> = [
+        "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source",
+        "track", "wbr",
+    ]
+    .into_iter()
+    .collect();
+
+    let config = ParserConfig::new()
+        .recover_block(true)
+        .always_self_closed_elements(self_closed);
+    let parser = Parser::new(config);
+    match parser.parse_simple(input) {
+        Ok(nodes) => {
+            let mut output = Output::default();
+            process_nodes(&nodes, &mut output);
+            Ok(output)
         }
-        Node::Block(block) => process_block(block),
+        Err(err) => Err(err.into_compile_error().into()),
+    }
+}
+
+fn process_nodes<C: CustomNode>(nodes: &[Node<C>], output: &mut Output) {
+    for node in nodes {
+        process_node(node, output);
+    }
+}
+
+fn process_node<C: CustomNode>(node: &Node<C>, output: &mut Output) {
+    match node {
+        Node::Element(element) => process_element(element, output),
+        Node::Text(text) => output.push_static(&text.value_string()),
+        Node::RawText(raw) => output.push_static(&raw.to_string_best()),
+        Node::Block(block) => output.push_code(process_block(block)),
         Node::Comment(comment) => {
-            let value = &comment.value;
-            quote! {
-                __html.push_str("<!--");
-                __html.push_str(#value);
-                __html.push_str("-->");
-            }
+            output.push_static("<!--");
+            output.push_static(&comment.value.value());
+            output.push_static("-->");
         }
         Node::Doctype(doctype) => {
-            let value = &doctype.value;
-            quote! {
-                __html.push_str("<!DOCTYPE ");
-                __html.push_str(#value);
-                __html.push_str(">");
-            }
+            output.push_static("<!DOCTYPE ");
+            output.push_static(&doctype.value.to_string_best());
+            output.push_static(">");
         }
-        Node::Fragment(fragment) => process_nodes(&fragment.children),
-        Node::Custom(_) => TokenStream2::new(),
+        Node::Fragment(fragment) => process_nodes(&fragment.children, output),
+        Node::Custom(_) => {}
     }
 }
 
-fn process_element<C: CustomNode>(element: &NodeElement<C>) -> TokenStream2 {
+fn process_element<C: CustomNode>(element: &NodeElement<C>, output: &mut Output) {
     let tag_name = element.open_tag.name.to_string();
 
     // Check if this is a component (starts with uppercase)
@@ -100,21 +167,17 @@ fn process_element<C: CustomNode>(element: &NodeElement<C>) -> TokenStream2 {
         .map(|c| c.is_uppercase())
         .unwrap_or(false)
     {
-        return process_component(element);
+        output.push_code(process_component(element));
+        return;
     }
 
-    let mut output = TokenStream2::new();
-
     // Opening tag
-    output.extend(quote! {
-        __html.push_str("<");
-        __html.push_str(#tag_name);
-    });
+    output.push_static("<");
+    output.push_static(&tag_name);
 
     // Process attributes
     for attr in &element.open_tag.attributes {
-        let attr_output = process_attribute(attr);
-        output.extend(attr_output);
+        process_attribute(attr, output);
     }
 
     // Check for self-closing elements
@@ -124,27 +187,18 @@ fn process_element<C: CustomNode>(element: &NodeElement<C>) -> TokenStream2 {
     ];
 
     if self_closing.contains(&tag_name.as_str()) {
-        output.extend(quote! {
-            __html.push_str(" />");
-        });
+        output.push_static(" />");
     } else {
-        output.extend(quote! {
-            __html.push_str(">");
-        });
+        output.push_static(">");
 
         // Process children
-        let children_output = process_nodes(&element.children);
-        output.extend(children_output);
+        process_nodes(&element.children, output);
 
         // Closing tag
-        output.extend(quote! {
-            __html.push_str("</");
-            __html.push_str(#tag_name);
-            __html.push_str(">");
-        });
+        output.push_static("</");
+        output.push_static(&tag_name);
+        output.push_static(">");
     }
-
-    output
 }
 
 fn process_component<C: CustomNode>(element: &NodeElement<C>) -> TokenStream2 {
@@ -180,14 +234,11 @@ fn process_component<C: CustomNode>(element: &NodeElement<C>) -> TokenStream2 {
 
     // Call the component function
     quote! {
-        {
-            let __component_result = #component_ident(#(#props),*);
-            __html.push_str(&__component_result.0);
-        }
+        ::acacia_core::RenderHtml::render_into(&#component_ident(#(#props),*), __html);
     }
 }
 
-fn process_attribute(attr: &NodeAttribute) -> TokenStream2 {
+fn process_attribute(attr: &NodeAttribute, output: &mut Output) {
     match attr {
         NodeAttribute::Attribute(attr) => {
             let name = attr.key.to_string();
@@ -201,39 +252,33 @@ fn process_attribute(attr: &NodeAttribute) -> TokenStream2 {
                 {
                     // Unwrap the block to get the inner expression for cleaner generated code
                     let condition = unwrap_block_expr(value);
-                    return quote! {
+                    let attribute = format!(" {}", name);
+                    output.push_code(quote! {
                         if #condition {
-                            __html.push_str(" ");
-                            __html.push_str(#name);
+                            __html.push_str(#attribute);
                         }
-                    };
+                    });
+                    return;
                 }
 
                 // Regular attribute with value
-                quote! {
-                    __html.push_str(" ");
-                    __html.push_str(#name);
-                    __html.push_str("=\"");
+                output.push_static(&format!(" {}=\"", name));
+                output.push_code(quote! {
                     __html.push_str(&::acacia_core::escape_html(&(#value).to_string()));
-                    __html.push_str("\"");
-                }
+                });
+                output.push_static("\"");
             } else {
                 // Boolean attribute without value
-                quote! {
-                    __html.push_str(" ");
-                    __html.push_str(#name);
-                }
+                output.push_static(&format!(" {}", name));
             }
         }
         NodeAttribute::Block(block) => {
             // Block attribute - this is for spreading HTMX actions like {submits(...)}
             if let Some(expr) = block.try_block() {
-                quote! {
-                    __html.push_str(" ");
+                output.push_static(" ");
+                output.push_code(quote! {
                     __html.push_str(&(#expr).to_string());
-                }
-            } else {
-                TokenStream2::new()
+                });
             }
         }
     }
@@ -278,7 +323,7 @@ fn process_block(block: &NodeBlock) -> TokenStream2 {
                     let else_branch = if_expr.else_branch.as_ref().map(|(_, else_expr)| {
                         quote! { else {
                             let __else_result: ::acacia_core::Fragment = #else_expr;
-                            __html.push_str(&__else_result.0);
+                            ::acacia_core::RenderHtml::render_into(&__else_result, __html);
                         }}
                     });
 
@@ -294,7 +339,7 @@ fn process_block(block: &NodeBlock) -> TokenStream2 {
 
         // Regular expression block - use RenderHtml trait for proper escaping
         quote! {
-            __html.push_str(&::acacia_core::RenderHtml::render_html(&(#(#stmts)*)));
+            ::acacia_core::RenderHtml::render_into(&(#(#stmts)*), __html);
         }
     } else {
         quote! {
@@ -316,10 +361,22 @@ fn parse_block_body(block: &syn::Block) -> TokenStream2 {
             if let Expr::Macro(mac) = expr {
                 let mac_path = &mac.mac.path;
                 let tokens = &mac.mac.tokens;
+
+                // Nested html! writes straight into this buffer
+                if mac_path.is_ident("html") {
+                    return match render_nodes(tokens.clone()) {
+                        Ok(output) => {
+                            let output = output.into_tokens();
+                            quote! { { #output } }
+                        }
+                        Err(err) => err.into(),
+                    };
+                }
+
                 return quote! {
                     {
                         let __nested = #mac_path!(#tokens);
-                        __html.push_str(&__nested.0);
+                        ::acacia_core::RenderHtml::render_into(&__nested, __html);
                     }
                 };
             }
@@ -328,7 +385,7 @@ fn parse_block_body(block: &syn::Block) -> TokenStream2 {
             return quote! {
                 {
                     let __nested: ::acacia_core::Fragment = #expr;
-                    __html.push_str(&__nested.0);
+                    ::acacia_core::RenderHtml::render_into(&__nested, __html);
                 }
             };
         }
@@ -340,7 +397,7 @@ fn parse_block_body(block: &syn::Block) -> TokenStream2 {
         output.extend(quote! {
             {
                 let __stmt_result: ::acacia_core::Fragment = { #stmt };
-                __html.push_str(&__stmt_result.0);
+                ::acacia_core::RenderHtml::render_into(&__stmt_result, __html);
             }
         });
     }
@@ -6,6 +6,7 @@ use quote::quote;
 mod form;
 mod html;
 mod model;
+mod render;
 mod route;
 
 #[cfg(feature = "tailwind")]
@@ -29,6 +30,30 @@ pub fn html(input: TokenStream) -> TokenStream {
     html::html_impl(input)
 }
 
+/// Like `html!`, but appends to an existing `&mut String` instead of building a `Fragment`.
+///
+/// Handy for implementing `RenderHtml::render_into`, so a component writes
+/// straight into its parent's buffer.
+///
+/// # Example
+/// ```ignore
+/// impl RenderHtml for Card {
+///     fn render_html(&self) -> String {
+///         let mut html = String::new();
+///         self.render_into(&mut html);
+///         html
+///     }
+///
+///     fn render_into(&self, out: &mut String) {
+///         html_into!(out, <div class="card">{&self.title}</div>)
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn html_into(input: TokenStream) -> TokenStream {
+    html::html_into_impl(input)
+}
+
 /// Mark a function as a component that returns a Fragment.
 ///
 /// # Example
@@ -51,12 +76,36 @@ pub fn component(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Register a page route (GET request that returns a full page).
 ///
+/// Tower layers can be attached with `layer = ...`, repeated as needed.
+/// `require = ...` takes a `Permission` (such as a role) the user must have.
+/// `timeout = ...` takes a `Duration` that replaces `Acacia::timeout` for this route.
+/// `title = "..."` sets the `<title>` of pages built with `into_page()`, with
+/// `{param}`s filled in from the path.
+/// `layout = ...` takes a `Layout`, usually a component, that wraps the content of those pages.
+/// `sitemap = false` leaves the page out of `/sitemap.xml`, as `require = ...` does too.
+/// A `Path<...>` argument must take as many values as the path has `{param}`s.
+/// A last segment of `{*rest}` matches the rest of the path, slashes included.
+/// Pages answer HEAD with their headers and no body, and an OPTIONS request to
+/// any route's path gets an `Allow` header listing the methods served there.
+///
 /// # Example
 /// ```ignore
 /// #[page("/")]
 /// async fn home(db: Db) -> Page {
 ///     html! { <h1>Welcome</h1> }.into_page()
 /// }
+///
+/// #[page("/tasks/{id}", title = "Task {id} – MyApp")]
+/// async fn task(Path(id): Path<i32>, db: Db) -> Result<Page> {
+///     let task = db.get::<Task>(id).await?.or_not_found()?;
+///     Ok(TaskDetail(&task).into_page())
+/// }
+///
+/// // `DOC("guide/install")` links to /docs/guide/install
+/// #[page("/docs/{*path}", title = "{path} – Docs")]
+/// async fn doc(Path(path): Path<String>) -> Result<Page> {
+///     Ok(DocPage(&path).into_page())
+/// }
 /// ```
 #[proc_macro_attribute]
 pub fn page(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -65,12 +114,30 @@ pub fn page(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Register an action route (POST/PUT/DELETE requests).
 ///
+/// Tower layers can be attached with `layer = ...`, repeated as needed.
+/// Integer literals with a `KB`, `MB` or `GB` suffix become byte counts.
+/// `require = ...` takes a `Permission` (such as a role) the user must have.
+/// `timeout = ...` takes a `Duration` that replaces `Acacia::timeout` for this route.
+/// A `Path<...>` argument must take as many values as the path has `{param}`s.
+/// `method = "PUT|PATCH"` serves several methods with one handler; the
+/// generated endpoint uses the first.
+///
 /// # Example
 /// ```ignore
 /// #[action("/tasks", method = "POST")]
 /// async fn create_task(form: Valid<NewTask>, db: Db) -> Fragment {
 ///     // ...
 /// }
+///
+/// #[action("/tasks/{id}", method = "PUT|PATCH")]
+/// async fn update_task(Path(id): Path<i32>, form: Valid<NewTask>, db: Db) -> Result<Fragment> {
+///     // ...
+/// }
+///
+/// #[action("/upload", method = "POST", layer = body_limit(10_MB))]
+/// async fn upload(form: Valid<NewAttachment>) -> Result<Fragment> {
+///     // ...
+/// }
 /// ```
 #[proc_macro_attribute]
 pub fn action(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -95,31 +162,163 @@ pub fn action(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// ```
 ///
 /// This generates a `task` module and re-exports `task::Entity` as `Task`.
+///
+/// Field types resolve in the surrounding module, so columns can use any type
+/// SeaORM supports, such as chrono's `NaiveDateTime` and `DateTime<Utc>`,
+/// `Decimal` for money, or `Vec<u8>` for binary data:
+/// ```ignore
+/// use chrono::{DateTime, NaiveDateTime, Utc};
+///
+/// #[model("events")]
+/// pub struct Event {
+///     #[key]
+///     pub id: i32,
+///     pub starts_at: NaiveDateTime,
+///     pub created_at: DateTime<Utc>,
+///     pub price: Decimal,
+///     pub thumbnail: Option<Vec<u8>>,
+/// }
+/// ```
+///
+/// Columns are named after their fields; `#[column("createdAt")]` names one
+/// explicitly, for existing tables. Code keeps using the field name: loading
+/// rows, creating the table, finders and `Query::order_by("created_at", ...)`
+/// all map it to the column.
+///
+/// `#[belongs_to(User)]` makes a field a foreign key to that model's `id`, with a
+/// SeaORM relation for `find_related`. `#[fk(users.id)]` adds only the foreign key,
+/// for tables without a model. Both take `on_delete` and `on_update`:
+/// ```ignore
+/// #[model("posts")]
+/// pub struct Post {
+///     #[key]
+///     pub id: i32,
+///     #[belongs_to(User, on_delete = "cascade")]
+///     pub author_id: i32,
+/// }
+/// ```
+///
+/// `#[unique]` and `#[index]` add a unique constraint or an index, plus a typed finder:
+/// ```ignore
+/// #[model("users")]
+/// pub struct User {
+///     #[key]
+///     pub id: i32,
+///     #[unique]
+///     pub email: String,
+///     #[index]
+///     pub team: String,
+/// }
+///
+/// let user: Option<User> = User::find_by_email(&db, "ada@example.com").await?;
+/// let members: Vec<User> = User::find_by_team(&db, "core").await?;
+/// ```
+///
+/// `#[version]` on an integer field turns on optimistic locking. `Db::update`
+/// only saves if the row still has the version the model was read with, and
+/// bumps it; otherwise the update fails with `AppError::Conflict`. Forms carry
+/// the version they were rendered with, so a stale edit is caught:
+/// ```ignore
+/// #[model("documents")]
+/// pub struct Document {
+///     #[key]
+///     pub id: i32,
+///     pub body: String,
+///     #[version]
+///     pub version: i32,
+/// }
+///
+/// db.update::<Document, _>(id, |doc| {
+///     doc.body = form.body;
+///     doc.version = form.version;
+/// }).await?;
+/// ```
+///
+/// `#[audited]` under `#[model]` records every insert, update and delete made
+/// through `Db` in an activity log, with who made it and what changed:
+/// ```ignore
+/// #[model("tasks")]
+/// #[audited]
+/// pub struct Task {
+///     #[key]
+///     pub id: i32,
+///     pub title: String,
+/// }
+///
+/// db.acting_as("ada@example.com").update::<Task, _>(id, |t| t.title = title).await?;
+/// let history = db.activity_for::<Task>(id).await?;
+/// ```
+///
+/// `#[model("tasks", validate)]` lets the model implement `Validate` itself.
+/// `Db::insert` and `Db::update` run it before saving, and reject the change
+/// with the field errors:
+/// ```ignore
+/// impl Validate for Task {
+///     fn validate(&self) -> std::result::Result<(), Vec<FieldError>> {
+///         if self.title.trim().is_empty() {
+///             return Err(vec![FieldError::new("title", "can't be blank")]);
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn model(attr: TokenStream, item: TokenStream) -> TokenStream {
     model::model_impl(attr, item)
 }
 
+/// Map the rows of a SQL view to a struct, for reports and summaries.
+///
+/// Works like `#[model]`, but read-only: `Db::all`, `Db::get`, `Query` and
+/// finders work, while `Db::insert` and `Db::update` don't compile, `Db::delete`
+/// returns an error, and the admin leaves it out. With `query`, `Db::migrate` creates
+/// the view after the tables; without it, the view must already exist. One
+/// field needs `#[key]`, with a value unique per row.
+///
+/// # Example
+/// ```ignore
+/// #[view("task_stats", query = "SELECT done, COUNT(*) AS count FROM tasks GROUP BY done")]
+/// pub struct TaskStats {
+///     #[key]
+///     pub done: bool,
+///     pub count: i64,
+/// }
+///
+/// let stats = db.all::<TaskStats>().await?;
+/// ```
+#[proc_macro_attribute]
+pub fn view(attr: TokenStream, item: TokenStream) -> TokenStream {
+    model::view_impl(attr, item)
+}
+
 // Keep the derive macro for backwards compatibility, but it just emits an error
 #[proc_macro_derive(Model, attributes(table, key))]
-pub fn derive_model(_input: TokenStream) -> TokenStream {
-    quote! {
-        compile_error!("Use #[model(\"table_name\")] attribute macro instead of #[derive(Model)]");
-    }
-    .into()
+pub fn derive_model(input: TokenStream) -> TokenStream {
+    model::derive_model_impl(input)
 }
 
 /// Define a form linked to a model for inserts/updates.
 ///
+/// Fields can carry `#[required]`, `#[max_len(n)]` and `#[pattern("regex")]`,
+/// which are checked server-side by `Valid<T>` and exposed as HTML attributes
+/// through `FormSchema::input_attrs`. Structs that also `#[derive(validator::Validate)]`
+/// get those rules checked as well (requires the `validator` feature).
+///
+/// `#[serde(...)]` attributes are kept for parsing, so `rename` and `rename_all`
+/// match other HTML field names (inputs and errors use them too) and `default`
+/// fills in missing fields.
+///
 /// # Example
 /// ```ignore
 /// #[form(Task)]
 /// pub struct NewTask {
+///     #[required]
+///     #[max_len(80)]
 ///     pub title: String,
 /// }
 /// ```
 ///
-/// This generates Deserialize and IntoActiveModel implementations.
+/// This generates Deserialize, Validate, FormSchema and IntoActiveModel implementations.
 #[proc_macro_attribute]
 pub fn form(attr: TokenStream, item: TokenStream) -> TokenStream {
     form::form_impl(attr, item)
@@ -135,11 +334,37 @@ pub fn form(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///     pub message: String,
 /// }
 /// ```
-#[proc_macro_derive(Form, attributes(for_model))]
+#[proc_macro_derive(Form, attributes(for_model, required, max_len, pattern, serde))]
 pub fn derive_form(input: TokenStream) -> TokenStream {
     form::derive_form_impl(input)
 }
 
+/// Derive `RenderHtml` for a struct, rendering it as a definition list of
+/// its fields, each label followed by the field's escaped value.
+///
+/// Handy for debug views and detail pages. Every field type must implement
+/// `RenderHtml`; `#[render(skip)]` leaves a field out and
+/// `#[render(label = "...")]` replaces the label derived from its name.
+///
+/// # Example
+/// ```ignore
+/// #[derive(RenderHtml)]
+/// pub struct OrderSummary {
+///     pub id: i32,
+///     pub customer: String,
+///     #[render(label = "Total (EUR)")]
+///     pub total: Decimal,
+///     #[render(skip)]
+///     pub internal_note: String,
+/// }
+///
+/// html! { <section>{summary}</section> }
+/// ```
+#[proc_macro_derive(RenderHtml, attributes(render))]
+pub fn derive_render_html(input: TokenStream) -> TokenStream {
+    render::derive_render_html_impl(input)
+}
+
 /// The `tw!` macro for composing Tailwind CSS classes.
 ///
 /// # Example
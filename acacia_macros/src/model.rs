@@ -17,20 +17,41 @@
 //! and re-exports `task::Entity` as `Task`.
 
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+use syn::{
+    ext::IdentExt, parse::ParseStream, parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr,
+    Token,
+};
 
 /// Attribute macro implementation for #[model("table_name")]
 pub fn model_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
-    // Parse the table name from the attribute
-    let table_name = if attr.is_empty() {
-        None
-    } else {
-        Some(parse_macro_input!(attr as LitStr).value())
-    };
+    let ModelArgs {
+        table_name,
+        validate,
+    } = parse_macro_input!(attr with ModelArgs::parse);
+    expand(table_name, validate, None, item)
+}
+
+/// Attribute macro implementation for #[view("view_name", query = "SELECT ...")]
+pub fn view_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let view = parse_macro_input!(attr with ViewArgs::parse);
+    expand(Some(view.name.clone()), false, Some(view), item)
+}
 
+/// The entity for a table, or for a read-only view when `view` is set.
+fn expand(
+    table_name: Option<String>,
+    validate: bool,
+    view: Option<ViewArgs>,
+    item: TokenStream,
+) -> TokenStream {
     let input = parse_macro_input!(item as DeriveInput);
     let name = &input.ident;
+    let audited = input
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("audited"));
     let vis = &input.vis;
 
     // Use provided table name or derive from struct name
@@ -45,65 +66,390 @@ pub fn model_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
         _ => panic!("model attribute only supports structs"),
     };
 
+    if view.is_some() {
+        if let Some(attr) = input
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("audited"))
+        {
+            return syn::Error::new_spanned(
+                attr,
+                "views are read-only, so there is nothing to audit",
+            )
+            .to_compile_error()
+            .into();
+        }
+        // SeaORM identifies rows by primary key, even ones that are never saved
+        if !fields
+            .iter()
+            .any(|field| field.attrs.iter().any(|attr| attr.path().is_ident("key")))
+        {
+            return syn::Error::new_spanned(
+                name,
+                "a view needs a `#[key]` field that is unique per row",
+            )
+            .to_compile_error()
+            .into();
+        }
+    }
+
     // Build the field definitions with SeaORM attributes
     let mut field_defs = Vec::new();
+    let mut columns = Vec::new();
+    let mut admin_columns = Vec::new();
+    let mut cells = Vec::new();
+    let mut relations = Vec::new();
+    let mut related = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut finders = Vec::new();
+    let mut version = None;
+    let mut column_names: Vec<String> = Vec::new();
 
     for field in fields {
         let field_name = field.ident.as_ref().unwrap();
+        // Keyword columns like `r#type` are named without the `r#`
+        let field_str = field_name.unraw().to_string();
+        let column_variant = format_ident!("{}", to_camel_case(&field_str));
+        for attr in &field.attrs {
+            if attr.path().is_ident("belongs_to") {
+                let belongs_to = match attr.parse_args_with(BelongsTo::parse) {
+                    Ok(belongs_to) => belongs_to,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+                let variant = format_ident!("{}", to_camel_case(field_str.trim_end_matches("_id")));
+                let target = belongs_to.module;
+                let entity = quote!(#target::Entity).to_string();
+                let from = format!("Column::{}", column_variant);
+                let to = quote!(#target::Column::Id).to_string();
+                let on_delete = belongs_to
+                    .actions
+                    .on_delete
+                    .map(|action| quote!(, on_delete = #action));
+                let on_update = belongs_to
+                    .actions
+                    .on_update
+                    .map(|action| quote!(, on_update = #action));
+                relations.push(quote! {
+                    #[sea_orm(belongs_to = #entity, from = #from, to = #to #on_delete #on_update)]
+                    #variant
+                });
+                // `Related` can only point at one relation per target
+                if !related.iter().any(|(existing, _)| *existing == entity) {
+                    let imp = quote! {
+                        impl ::sea_orm::Related<#target::Entity> for Entity {
+                            fn to() -> ::sea_orm::RelationDef {
+                                ::sea_orm::RelationTrait::def(&Relation::#variant)
+                            }
+                        }
+                    };
+                    related.push((entity, imp));
+                }
+            } else if attr.path().is_ident("fk") {
+                let fk = match attr.parse_args_with(Fk::parse) {
+                    Ok(fk) => fk,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+                let table = fk.table.to_string();
+                let column = fk.column.to_string();
+                let action = |action: Option<LitStr>, method: TokenStream2| {
+                    action.map(|action| {
+                        let action = format_ident!("{}", action.value());
+                        quote!(.#method(::sea_orm::sea_query::ForeignKeyAction::#action))
+                    })
+                };
+                let on_delete = action(fk.actions.on_delete, quote!(on_delete));
+                let on_update = action(fk.actions.on_update, quote!(on_update));
+                foreign_keys.push(quote! {
+                    stmt.foreign_key(
+                        ::sea_orm::sea_query::ForeignKey::create()
+                            .from_col(Column::#column_variant)
+                            .to(
+                                ::sea_orm::sea_query::Alias::new(#table),
+                                ::sea_orm::sea_query::Alias::new(#column),
+                            )
+                            #on_delete
+                            #on_update,
+                    );
+                });
+            }
+        }
         let field_type = &field.ty;
         let is_key = field.attrs.iter().any(|attr| attr.path().is_ident("key"));
         let type_str = quote!(#field_type).to_string();
+        columns.push(field_str.clone());
+        cells.push(match type_str.as_str() {
+            // Binary columns show their size rather than a list of numbers
+            "Vec < u8 >" => quote!(::acacia_core::table_bytes(Some(&self.#field_name))),
+            "Option < Vec < u8 > >" => {
+                quote!(::acacia_core::table_bytes(self.#field_name.as_deref()))
+            }
+            _ => quote!(::acacia_core::table_cell(&self.#field_name)),
+        });
 
+        let column = field_str.clone();
+        let input_type = crate::form::input_type(field_type);
+        let optional = matches!(
+            field_type,
+            syn::Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "Option")
+        );
+        admin_columns.push(quote! {
+            ::acacia_db::AdminColumn {
+                name: #column,
+                input_type: #input_type,
+                key: #is_key,
+                optional: #optional,
+            }
+        });
+
+        let is_unique = field
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("unique"));
+        let is_index = field.attrs.iter().any(|attr| attr.path().is_ident("index"));
+        let is_version = field
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("version"));
+        let mut column_attrs = Vec::new();
+        let mut column_name = field_str.clone();
+        if let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("column"))
+        {
+            match attr.parse_args::<LitStr>() {
+                Ok(column) => {
+                    column_name = column.value();
+                    column_attrs.push(quote!(column_name = #column));
+                }
+                Err(err) => return err.to_compile_error().into(),
+            }
+        }
+        // Two fields on one column would fail only once the table is created
+        if column_names.contains(&column_name) {
+            return syn::Error::new_spanned(
+                field_name,
+                format!("another field already uses the column `{}`", column_name),
+            )
+            .to_compile_error()
+            .into();
+        }
+        column_names.push(column_name);
         if is_key {
-            field_defs.push(quote! {
-                #[sea_orm(primary_key)]
-                pub #field_name: #field_type
-            });
+            column_attrs.push(quote!(primary_key));
+        } else if is_version {
+            // New rows start at version 0 without the form setting it
+            column_attrs.push(quote!(default_value = 0));
+            version = Some((field_name.clone(), column_variant.clone()));
         } else if type_str == "bool" {
             // Bool fields default to false
-            field_defs.push(quote! {
-                #[sea_orm(default_value = false)]
-                pub #field_name: #field_type
-            });
-        } else {
-            field_defs.push(quote! {
-                pub #field_name: #field_type
+            column_attrs.push(quote!(default_value = false));
+        }
+        if is_unique {
+            column_attrs.push(quote!(unique));
+        } else if is_index {
+            column_attrs.push(quote!(indexed));
+        }
+        let column_attrs =
+            (!column_attrs.is_empty()).then(|| quote!(#[sea_orm(#(#column_attrs),*)]));
+        field_defs.push(quote! {
+            #column_attrs
+            pub #field_name: #field_type
+        });
+
+        // Typed lookups, so callers never build SQL from strings
+        if is_unique || is_index {
+            let finder = format_ident!("find_by_{}", field_str);
+            let (output, fetch) = if is_unique {
+                (quote!(Option<Self>), quote!(one))
+            } else {
+                (quote!(Vec<Self>), quote!(all))
+            };
+            let doc = format!(
+                "Find the {} with this `{}`, reading from a replica if configured.",
+                if is_unique { "row" } else { "rows" },
+                field_name
+            );
+            finders.push(quote! {
+                #[doc = #doc]
+                pub async fn #finder(
+                    db: &::acacia_db::Db,
+                    value: impl Into<#field_type>,
+                ) -> ::acacia_db::Result<#output> {
+                    let select = ::sea_orm::QueryFilter::filter(
+                        <Entity as ::sea_orm::EntityTrait>::find(),
+                        ::sea_orm::ColumnTrait::eq(&Column::#column_variant, value.into()),
+                    );
+                    select.#fetch(db.reader()).await.map_err(Into::into)
+                }
             });
         }
     }
 
+    let audited = audited.then(|| quote!(.audited(#table_name)));
+
+    // `#[model(validate)]` leaves `Validate` for the user to implement
+    let default_validate = (!validate).then(|| quote!(impl ::acacia_core::Validate for Model {}));
+
+    let versioned = version.map(|(field, column)| {
+        quote! {
+            fn next_version(&mut self) -> Option<(Column, ::sea_orm::Value)> {
+                let current = self.#field;
+                self.#field += 1;
+                Some((Column::#column, current.into()))
+            }
+        }
+    });
+
+    // Floats have no `Eq`
+    let has_float = fields.iter().any(|field| {
+        let ty = &field.ty;
+        let ty = quote!(#ty).to_string();
+        ty.contains("f32") || ty.contains("f64")
+    });
+    let eq = (!has_float).then(|| quote!(Eq,));
+
     // Module name (snake_case of the struct name)
     let mod_name = format_ident!("{}", to_snake_case(&name.to_string()));
 
+    let name_str = name.to_string();
+    let related_impls = related.into_iter().map(|(_, imp)| imp);
+
+    // Saving, the table and the admin. Views have no `Validate`, so
+    // `Db::insert` and `Db::update` don't compile for one, and refuse the rest.
+    let (writes, registrations) = match &view {
+        Some(view) => {
+            let read_only = format!("`{}` is a read-only view", table_name);
+            let writes = quote! {
+                #[::sea_orm::prelude::async_trait::async_trait]
+                impl ActiveModelBehavior for ActiveModel {
+                    async fn before_save<C>(self, _db: &C, _insert: bool) -> Result<Self, ::sea_orm::DbErr>
+                    where
+                        C: ::sea_orm::ConnectionTrait,
+                    {
+                        Err(::sea_orm::DbErr::Custom(#read_only.to_string()))
+                    }
+
+                    async fn before_delete<C>(self, _db: &C) -> Result<Self, ::sea_orm::DbErr>
+                    where
+                        C: ::sea_orm::ConnectionTrait,
+                    {
+                        Err(::sea_orm::DbErr::Custom(#read_only.to_string()))
+                    }
+                }
+            };
+            let registration = view.query.as_ref().map(|query| {
+                quote! {
+                    // Register the view for auto-migration
+                    ::inventory::submit! {
+                        ::acacia_db::ViewRegistration {
+                            name: #table_name,
+                            query: #query,
+                        }
+                    }
+                }
+            });
+            (Some(writes), registration)
+        }
+        None => {
+            let writes = quote! {
+                impl ActiveModelBehavior for ActiveModel {}
+
+                #default_validate
+
+                impl ::acacia_db::Versioned for Model {
+                    #versioned
+                }
+
+                /// Index statements for `#[index]` fields
+                pub fn __create_index_stmts(schema: &::sea_orm::Schema) -> Vec<::sea_orm::sea_query::IndexCreateStatement> {
+                    schema
+                        .create_index_from_entity(Entity)
+                        .into_iter()
+                        .map(|mut index| index.if_not_exists().to_owned())
+                        .collect()
+                }
+
+                /// Create table statement for migrations
+                pub fn __create_table_stmt(schema: &::sea_orm::Schema) -> ::sea_orm::sea_query::TableCreateStatement {
+                    #[allow(unused_mut)]
+                    let mut stmt = schema.create_table_from_entity(Entity).if_not_exists().to_owned();
+                    #(#foreign_keys)*
+                    stmt
+                }
+            };
+            let registrations = quote! {
+                // Register entity for auto-migration
+                ::inventory::submit! {
+                    ::acacia_db::EntityRegistration::new(#mod_name::__create_table_stmt)
+                        .with_indexes(#mod_name::__create_index_stmts)
+                        #audited
+                }
+
+                // Register CRUD operations for the admin scaffold
+                ::inventory::submit! {
+                    ::acacia_db::AdminRegistration {
+                        name: #name_str,
+                        table: #table_name,
+                        columns: &[#(#admin_columns),*],
+                        list: ::acacia_db::admin::list::<#mod_name::Model>,
+                        get: ::acacia_db::admin::get::<#mod_name::Model>,
+                        create: ::acacia_db::admin::create::<#mod_name::Model>,
+                        update: ::acacia_db::admin::update::<#mod_name::Model>,
+                        delete: ::acacia_db::admin::delete::<#mod_name::Model>,
+                    }
+                }
+            };
+            (Some(writes), Some(registrations))
+        }
+    };
+
     // The attribute macro replaces the struct with a module + re-export
     let expanded = quote! {
         /// Generated SeaORM entity module
         #vis mod #mod_name {
-            use sea_orm::entity::prelude::*;
+            // Field types resolve where the struct was written, e.g. an imported
+            // `chrono::NaiveDateTime`; the explicit imports take precedence.
+            use super::*;
+            use sea_orm::entity::prelude::{
+                ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter,
+            };
             use serde::{Deserialize, Serialize};
+            use ::std::result::Result;
 
-            #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+            #[derive(Clone, Debug, PartialEq, #eq DeriveEntityModel, Serialize, Deserialize)]
             #[sea_orm(table_name = #table_name)]
             pub struct Model {
                 #(#field_defs,)*
             }
 
             #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-            pub enum Relation {}
+            pub enum Relation {
+                #(#relations,)*
+            }
+
+            #(#related_impls)*
 
-            impl ActiveModelBehavior for ActiveModel {}
+            #writes
 
-            /// Create table statement for migrations
-            pub fn __create_table_stmt(schema: &::sea_orm::Schema) -> ::sea_orm::sea_query::TableCreateStatement {
-                schema.create_table_from_entity(Entity).if_not_exists().to_owned()
+            impl Model {
+                #(#finders)*
             }
-        }
 
-        // Register entity for auto-migration
-        ::inventory::submit! {
-            ::acacia_db::EntityRegistration::new(#mod_name::__create_table_stmt)
+            impl ::acacia_core::TableRow for Model {
+                const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+
+                fn cell(&self, column: &str) -> String {
+                    match column {
+                        #(#columns => #cells,)*
+                        _ => String::new(),
+                    }
+                }
+            }
         }
 
+        #registrations
+
         // Re-export the Model with the original name for ergonomic usage:
         // `db.all::<Task>()` and `fn component(task: &Task)`
         #vis use #mod_name::Model as #name;
@@ -112,6 +458,167 @@ pub fn model_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
     expanded.into()
 }
 
+/// `#[model("tasks", validate)]`: an optional table name and flags.
+struct ModelArgs {
+    table_name: Option<String>,
+    validate: bool,
+}
+
+impl ModelArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = Self {
+            table_name: None,
+            validate: false,
+        };
+        if input.peek(LitStr) {
+            args.table_name = Some(input.parse::<LitStr>()?.value());
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        while !input.is_empty() {
+            let flag: Ident = input.parse()?;
+            match flag.to_string().as_str() {
+                "validate" => args.validate = true,
+                _ => return Err(syn::Error::new(flag.span(), "expected `validate`")),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// `#[view("task_stats", query = "SELECT ...")]`: a view's name and, to have
+/// migrations create it, its query.
+struct ViewArgs {
+    name: String,
+    query: Option<LitStr>,
+}
+
+impl ViewArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<LitStr>()?.value();
+        let mut query = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key: Ident = input.parse()?;
+            if key != "query" {
+                return Err(syn::Error::new(key.span(), "expected `query`"));
+            }
+            input.parse::<Token![=]>()?;
+            query = Some(input.parse()?);
+        }
+        Ok(Self { name, query })
+    }
+}
+
+/// `#[belongs_to(User, on_delete = "cascade")]`: a relation to another model's `id`.
+struct BelongsTo {
+    /// The target's entity module, e.g. `user` for `User`.
+    module: syn::Path,
+    actions: Actions,
+}
+
+impl BelongsTo {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut module: syn::Path = input.parse()?;
+        let last = module
+            .segments
+            .last_mut()
+            .ok_or_else(|| input.error("expected a model name"))?;
+        last.ident = format_ident!("{}", to_snake_case(&last.ident.to_string()));
+        let actions = Actions::parse(input)?;
+        Ok(Self { module, actions })
+    }
+}
+
+/// `#[fk(users.id, on_delete = "cascade")]`: a foreign key to any table's column.
+struct Fk {
+    table: Ident,
+    column: Ident,
+    actions: Actions,
+}
+
+impl Fk {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let table = input.parse()?;
+        input.parse::<Token![.]>()?;
+        let column = input.parse()?;
+        let actions = Actions::parse(input)?;
+        Ok(Self {
+            table,
+            column,
+            actions,
+        })
+    }
+}
+
+/// Referential actions, as SeaORM `ForeignKeyAction` variant names.
+#[derive(Default)]
+struct Actions {
+    on_delete: Option<LitStr>,
+    on_update: Option<LitStr>,
+}
+
+impl Actions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut actions = Actions::default();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let value: LitStr = input.parse()?;
+            let action = LitStr::new(foreign_key_action(&value)?, value.span());
+            match key.to_string().as_str() {
+                "on_delete" => actions.on_delete = Some(action),
+                "on_update" => actions.on_update = Some(action),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "expected `on_delete` or `on_update`",
+                    ))
+                }
+            }
+        }
+        Ok(actions)
+    }
+}
+
+fn foreign_key_action(value: &LitStr) -> syn::Result<&'static str> {
+    match value.value().to_lowercase().replace('_', " ").as_str() {
+        "cascade" => Ok("Cascade"),
+        "set null" => Ok("SetNull"),
+        "set default" => Ok("SetDefault"),
+        "restrict" => Ok("Restrict"),
+        "no action" => Ok("NoAction"),
+        _ => Err(syn::Error::new(
+            value.span(),
+            "expected \"cascade\", \"set null\", \"set default\", \"restrict\" or \"no action\"",
+        )),
+    }
+}
+
+/// Convert a snake_case name to CamelCase, as SeaORM does for column variants.
+fn to_camel_case(s: &str) -> String {
+    s.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
 // Keep this for the deprecated derive macro
 pub fn derive_model_impl(_input: TokenStream) -> TokenStream {
     quote! {
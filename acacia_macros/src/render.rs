@@ -0,0 +1,97 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+pub fn derive_render_html_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "RenderHtml can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "RenderHtml can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut rows = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut skip = false;
+        let mut label = humanize(&ident.to_string());
+        for attr in &field.attrs {
+            if !attr.path().is_ident("render") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("label") {
+                    label = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `skip` or `label = \"...\"`"))
+                }
+            })?;
+        }
+        if skip {
+            continue;
+        }
+        let dt = format!("<dt>{}</dt><dd>", escape(&label));
+        rows.push(quote! {
+            out.push_str(#dt);
+            ::acacia_core::RenderHtml::render_into(&self.#ident, out);
+            out.push_str("</dd>");
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::acacia_core::RenderHtml for #name #ty_generics #where_clause {
+            fn render_html(&self) -> String {
+                let mut html = String::new();
+                ::acacia_core::RenderHtml::render_into(self, &mut html);
+                html
+            }
+
+            fn render_into(&self, out: &mut String) {
+                out.push_str("<dl class=\"acacia-details\">");
+                #(#rows)*
+                out.push_str("</dl>");
+            }
+        }
+    })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Turn a field name into a label: `due_date` → `Due date`.
+fn humanize(name: &str) -> String {
+    let text = name.trim_start_matches("r#").replace('_', " ");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
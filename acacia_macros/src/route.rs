@@ -2,16 +2,114 @@
 
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse::Parse, parse::ParseStream, parse_macro_input, ItemFn, LitStr, Token};
+use syn::{
+    parse::Parse, parse::ParseStream, parse_macro_input, punctuated::Punctuated,
+    visit_mut::VisitMut, Expr, FnArg, GenericArgument, ItemFn, LitStr, Token, Type,
+};
 
 struct PageArgs {
     path: LitStr,
+    layers: Vec<Expr>,
+    requires: Vec<Expr>,
+    timeout: Option<Expr>,
+    title: Option<LitStr>,
+    layout: Option<Expr>,
+    sitemap: bool,
 }
 
 impl Parse for PageArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let path = input.parse()?;
-        Ok(PageArgs { path })
+        let mut layers = Vec::new();
+        let mut requires = Vec::new();
+        let mut timeout = None;
+        let mut title = None;
+        let mut layout = None;
+        let mut sitemap = true;
+
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            let key: syn::Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            if key == "layer" {
+                layers.push(parse_layer(input)?);
+            } else if key == "require" {
+                requires.push(input.parse()?);
+            } else if key == "timeout" {
+                timeout = Some(input.parse()?);
+            } else if key == "title" {
+                title = Some(input.parse()?);
+            } else if key == "layout" {
+                layout = Some(input.parse()?);
+            } else if key == "sitemap" {
+                sitemap = input.parse::<syn::LitBool>()?.value;
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `layer`, `require`, `timeout`, `title`, `layout` or `sitemap`",
+                ));
+            }
+        }
+
+        Ok(PageArgs {
+            path,
+            layers,
+            requires,
+            timeout,
+            title,
+            layout,
+            sitemap,
+        })
+    }
+}
+
+/// Parse a `layer = ...` expression, expanding size literals like `10_MB`.
+fn parse_layer(input: ParseStream) -> syn::Result<Expr> {
+    let mut expr: Expr = input.parse()?;
+    SizeLiterals.visit_expr_mut(&mut expr);
+    Ok(expr)
+}
+
+/// Wrap a handler in a permission check for each `require = ...`.
+fn guarded(fn_name: &syn::Ident, requires: &[Expr]) -> proc_macro2::TokenStream {
+    requires
+        .iter()
+        .fold(quote! { #fn_name }, |handler, permission| {
+            quote! { ::acacia_core::permission::require(#handler, #permission) }
+        })
+}
+
+/// A `.timeout(...)` call for the route registration, if one was given.
+fn route_timeout(timeout: &Option<Expr>) -> proc_macro2::TokenStream {
+    match timeout {
+        Some(timeout) => quote! { .timeout(#timeout) },
+        None => quote! {},
+    }
+}
+
+/// Rewrites `10_KB`, `10_MB` and `10_GB` into byte counts.
+struct SizeLiterals;
+
+impl VisitMut for SizeLiterals {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) = expr
+        {
+            let unit = match int.suffix() {
+                "KB" => Some(quote! { ::acacia_core::KB }),
+                "MB" => Some(quote! { ::acacia_core::MB }),
+                "GB" => Some(quote! { ::acacia_core::GB }),
+                _ => None,
+            };
+            if let Some(unit) = unit {
+                let digits = syn::LitInt::new(int.base10_digits(), int.span());
+                *expr = syn::parse_quote! { (#digits * #unit) };
+                return;
+            }
+        }
+        syn::visit_mut::visit_expr_mut(self, expr);
     }
 }
 
@@ -27,6 +125,11 @@ pub fn page_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
     let fn_asyncness = &item_fn.sig.asyncness;
 
     let path = &args.path;
+    let layers = &args.layers;
+    let handler = guarded(fn_name, &args.requires);
+    let timeout = route_timeout(&args.timeout);
+    // Pages behind a permission check stay out of the sitemap
+    let unlisted = (!args.sitemap || !args.requires.is_empty()).then(|| quote! { .unlisted() });
     let handler_name = format_ident!("__acacia_handler_{}", fn_name);
 
     // Generate SCREAMING_CASE name for the endpoint constant/function
@@ -34,17 +137,49 @@ pub fn page_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     // Extract path parameters from the path string (e.g., "/tasks/{id}" -> ["id"])
     let path_str = path.value();
-    let path_params: Vec<String> = path_str
-        .split('/')
-        .filter(|s| s.starts_with('{') && s.ends_with('}'))
-        .map(|s| s[1..s.len() - 1].to_string())
-        .collect();
+    let path_params = match path_params(path) {
+        Ok(params) => params,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    if let Err(err) = check_path_params(&path_params, fn_inputs) {
+        return err.to_compile_error().into();
+    }
+
+    let title = match &args.title {
+        Some(title) => {
+            if let Err(err) = check_title_params(title, &path_params) {
+                return err.to_compile_error().into();
+            }
+            quote! {
+                .layer(::axum::middleware::from_fn(
+                    |params: ::axum::extract::RawPathParams,
+                     req: ::axum::extract::Request,
+                     next: ::axum::middleware::Next| {
+                        ::acacia_core::with_page_title(#title, params, req, next)
+                    },
+                ))
+            }
+        }
+        None => quote! {},
+    };
+
+    let layout = match &args.layout {
+        Some(layout) => quote! {
+            .layer(::axum::middleware::from_fn(
+                |req: ::axum::extract::Request, next: ::axum::middleware::Next| {
+                    ::acacia_core::layout::with_layout(&#layout, req, next)
+                },
+            ))
+        },
+        None => quote! {},
+    };
 
     // Generate endpoint constant or function based on whether there are path params
     let endpoint_def = if path_params.is_empty() {
         // No params: generate a constant
         quote! {
-            #fn_vis const #endpoint_name: ::acacia_core::Endpoint = ::acacia_core::Endpoint::get_const(#path);
+            #fn_vis const #endpoint_name: ::acacia_core::Endpoint = ::acacia_core::Endpoint::get_const(#path).in_module(module_path!());
         }
     } else {
         // Has params: generate a function
@@ -64,7 +199,7 @@ pub fn page_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
                 url_expr.extend(quote! { url.push_str(#part); });
             } else {
                 let end_brace = part.find('}').unwrap();
-                let param_name = &part[..end_brace];
+                let param_name = part[..end_brace].trim_start_matches('*');
                 let rest = &part[end_brace + 1..];
                 let param_ident = format_ident!("{}", param_name);
                 url_expr.extend(quote! {
@@ -79,7 +214,7 @@ pub fn page_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
             #[allow(non_snake_case)]
             #fn_vis fn #endpoint_name(#(#url_fn_params),*) -> ::acacia_core::Endpoint {
                 let path = { #url_expr };
-                ::acacia_core::Endpoint::get(path)
+                ::acacia_core::Endpoint::get(path).in_module(module_path!())
             }
         }
     };
@@ -93,22 +228,142 @@ pub fn page_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
 
         // Route handler wrapper
         fn #handler_name() -> ::axum::routing::MethodRouter<::acacia_core::AppState> {
-            ::axum::routing::get(#fn_name)
+            ::axum::routing::get(#handler) #title #layout #(.layer(#layers))*
         }
 
         // Route registration
         ::inventory::submit! {
-            ::acacia_core::RouteDefinition::new(
-                #path,
-                ::acacia_core::Method::Get,
-                #handler_name,
-            )
+            ::acacia_core::RouteDefinition::page(#path, #handler_name)
+                .in_module(module_path!())
+                .named(stringify!(#fn_name))#timeout #unlisted
         }
     };
 
     expanded.into()
 }
 
+/// The `acacia_core::Method` variant for an uppercase method name.
+fn method_variant_for(method: &str) -> proc_macro2::TokenStream {
+    match method {
+        "GET" => quote! { ::acacia_core::Method::Get },
+        "PUT" => quote! { ::acacia_core::Method::Put },
+        "PATCH" => quote! { ::acacia_core::Method::Patch },
+        "DELETE" => quote! { ::acacia_core::Method::Delete },
+        _ => quote! { ::acacia_core::Method::Post },
+    }
+}
+
+/// The names of a route's `{param}`s, including a trailing `{*rest}` catch-all.
+fn path_params(path: &LitStr) -> syn::Result<Vec<String>> {
+    let value = path.value();
+    let segments: Vec<&str> = value.split('/').collect();
+    let mut params = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let Some(name) = segment
+            .strip_prefix('{')
+            .and_then(|segment| segment.strip_suffix('}'))
+        else {
+            continue;
+        };
+        let catch_all = name.strip_prefix('*');
+        if catch_all.is_some() && i + 1 != segments.len() {
+            return Err(syn::Error::new_spanned(
+                path,
+                format!("the catch-all {{{}}} must be the last segment", name),
+            ));
+        }
+        params.push(catch_all.unwrap_or(name).to_string());
+    }
+    Ok(params)
+}
+
+/// Check that a handler's `Path<...>` extractor matches the route's `{param}` count.
+///
+/// Only tuples and well-known scalar types are checked, since a struct can
+/// deserialize any set of params.
+fn check_path_params(
+    path_params: &[String],
+    inputs: &Punctuated<FnArg, Token![,]>,
+) -> syn::Result<()> {
+    for input in inputs {
+        let FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+        let Type::Path(type_path) = &*pat_type.ty else {
+            continue;
+        };
+        let Some(segment) = type_path.path.segments.last() else {
+            continue;
+        };
+        if segment.ident != "Path" {
+            continue;
+        }
+        let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+            continue;
+        };
+        let Some(GenericArgument::Type(inner)) = args.args.first() else {
+            continue;
+        };
+
+        let arity = match inner {
+            Type::Tuple(tuple) => tuple.elems.len(),
+            Type::Path(inner) if is_scalar(inner) => 1,
+            _ => continue,
+        };
+        if arity != path_params.len() {
+            let expected = match path_params.len() {
+                0 => "the route has no path parameters".to_string(),
+                1 => format!("the route has 1 path parameter ({{{}}})", path_params[0]),
+                n => format!(
+                    "the route has {} path parameters ({})",
+                    n,
+                    path_params
+                        .iter()
+                        .map(|p| format!("{{{}}}", p))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            return Err(syn::Error::new_spanned(
+                inner,
+                format!("Path extractor takes {} value(s), but {}", arity, expected),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that every `{param}` in a page title is a parameter of the route.
+fn check_title_params(title: &LitStr, path_params: &[String]) -> syn::Result<()> {
+    let value = title.value();
+    for part in value.split('{').skip(1) {
+        let Some(end) = part.find('}') else {
+            continue;
+        };
+        let name = &part[..end];
+        if !path_params.iter().any(|param| param == name) {
+            return Err(syn::Error::new_spanned(
+                title,
+                format!("the title uses {{{}}}, which isn't a path parameter", name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a type always deserializes from a single path parameter.
+fn is_scalar(type_path: &syn::TypePath) -> bool {
+    const SCALARS: &[&str] = &[
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+        "f32", "f64", "bool", "char", "String", "Uuid",
+    ];
+    type_path
+        .path
+        .segments
+        .last()
+        .is_some_and(|segment| SCALARS.iter().any(|scalar| segment.ident == scalar))
+}
+
 fn to_screaming_case(s: &str) -> String {
     let mut result = String::new();
     for (i, c) in s.chars().enumerate() {
@@ -120,15 +375,24 @@ fn to_screaming_case(s: &str) -> String {
     result
 }
 
+/// The methods an `#[action]` can serve.
+const ACTION_METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+
 struct ActionArgs {
     path: LitStr,
     method: Option<String>,
+    layers: Vec<Expr>,
+    requires: Vec<Expr>,
+    timeout: Option<Expr>,
 }
 
 impl Parse for ActionArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let path: LitStr = input.parse()?;
         let mut method = None;
+        let mut layers = Vec::new();
+        let mut requires = Vec::new();
+        let mut timeout = None;
 
         while input.peek(Token![,]) {
             input.parse::<Token![,]>()?;
@@ -136,11 +400,39 @@ impl Parse for ActionArgs {
             input.parse::<Token![=]>()?;
             if key == "method" {
                 let value: LitStr = input.parse()?;
+                for part in value.value().split('|') {
+                    if !ACTION_METHODS.contains(&part.trim().to_uppercase().as_str()) {
+                        return Err(syn::Error::new_spanned(
+                            &value,
+                            format!(
+                                "unknown method `{}`, expected GET, POST, PUT, PATCH or DELETE",
+                                part.trim()
+                            ),
+                        ));
+                    }
+                }
                 method = Some(value.value());
+            } else if key == "layer" {
+                layers.push(parse_layer(input)?);
+            } else if key == "require" {
+                requires.push(input.parse()?);
+            } else if key == "timeout" {
+                timeout = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `method`, `layer`, `require` or `timeout`",
+                ));
             }
         }
 
-        Ok(ActionArgs { path, method })
+        Ok(ActionArgs {
+            path,
+            method,
+            layers,
+            requires,
+            timeout,
+        })
     }
 }
 
@@ -156,17 +448,16 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
     let fn_asyncness = &item_fn.sig.asyncness;
 
     let path = &args.path;
+    let layers = &args.layers;
     let method_str = args.method.as_deref().unwrap_or("POST");
-    let method_upper = method_str.to_uppercase();
+    // `method = "PUT|PATCH"` serves every method listed; endpoints use the first
+    let methods: Vec<String> = method_str
+        .split('|')
+        .map(|method| method.trim().to_uppercase())
+        .collect();
+    let method_upper = methods[0].clone();
 
-    let method_variant = match method_upper.as_str() {
-        "GET" => quote! { ::acacia_core::Method::Get },
-        "POST" => quote! { ::acacia_core::Method::Post },
-        "PUT" => quote! { ::acacia_core::Method::Put },
-        "PATCH" => quote! { ::acacia_core::Method::Patch },
-        "DELETE" => quote! { ::acacia_core::Method::Delete },
-        _ => quote! { ::acacia_core::Method::Post },
-    };
+    let method_variant = method_variant_for(&method_upper);
 
     let axum_method = match method_upper.as_str() {
         "GET" => quote! { ::axum::routing::get },
@@ -177,22 +468,46 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
         _ => quote! { ::axum::routing::post },
     };
 
+    let handler = guarded(fn_name, &args.requires);
+    let timeout = route_timeout(&args.timeout);
     let handler_name = format_ident!("__acacia_handler_{}", fn_name);
+    let also = (methods.len() > 1).then(|| {
+        let variants = methods[1..].iter().map(|method| method_variant_for(method));
+        quote! { .also(&[#(#variants),*]) }
+    });
+    let method_router = if methods.len() > 1 {
+        let filters = methods.iter().map(|method| match method.as_str() {
+            "GET" => quote! { ::axum::routing::MethodFilter::GET },
+            "PUT" => quote! { ::axum::routing::MethodFilter::PUT },
+            "PATCH" => quote! { ::axum::routing::MethodFilter::PATCH },
+            "DELETE" => quote! { ::axum::routing::MethodFilter::DELETE },
+            _ => quote! { ::axum::routing::MethodFilter::POST },
+        });
+        let filter = filters.reduce(|all, filter| quote! { #all.or(#filter) });
+        quote! { ::axum::routing::on(#filter, #handler) }
+    } else {
+        quote! { #axum_method(#handler) }
+    };
 
     // Generate SCREAMING_CASE name for the endpoint constant/function
     let endpoint_name = format_ident!("{}", to_screaming_case(&fn_name.to_string()));
 
-    // Extract path parameters
+    // Extract path parameters from the path string (e.g., "/tasks/{id}" -> ["id"])
     let path_str = path.value();
-    let path_params: Vec<String> = path_str
-        .split('/')
-        .filter(|s| s.starts_with('{') && s.ends_with('}'))
-        .map(|s| s[1..s.len() - 1].to_string())
-        .collect();
+    let path_params = match path_params(path) {
+        Ok(params) => params,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    if let Err(err) = check_path_params(&path_params, fn_inputs) {
+        return err.to_compile_error().into();
+    }
 
     // Determine endpoint constructor based on method
     let endpoint_const_constructor = match method_upper.as_str() {
         "DELETE" => quote! { ::acacia_core::Endpoint::delete_const },
+        "PUT" => quote! { ::acacia_core::Endpoint::put_const },
+        "PATCH" => quote! { ::acacia_core::Endpoint::patch_const },
         "POST" => quote! { ::acacia_core::Endpoint::post_const },
         "GET" => quote! { ::acacia_core::Endpoint::get_const },
         _ => quote! { ::acacia_core::Endpoint::post_const },
@@ -200,6 +515,8 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let endpoint_fn_constructor = match method_upper.as_str() {
         "DELETE" => quote! { ::acacia_core::Endpoint::delete },
+        "PUT" => quote! { ::acacia_core::Endpoint::put },
+        "PATCH" => quote! { ::acacia_core::Endpoint::patch },
         "POST" => quote! { ::acacia_core::Endpoint::post },
         "GET" => quote! { ::acacia_core::Endpoint::get },
         _ => quote! { ::acacia_core::Endpoint::post },
@@ -209,7 +526,7 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
     let endpoint_def = if path_params.is_empty() {
         // No params: generate a constant
         quote! {
-            #fn_vis const #endpoint_name: ::acacia_core::Endpoint = #endpoint_const_constructor(#path);
+            #fn_vis const #endpoint_name: ::acacia_core::Endpoint = #endpoint_const_constructor(#path).in_module(module_path!());
         }
     } else {
         // Has params: generate a function
@@ -229,7 +546,7 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
                 url_expr.extend(quote! { url.push_str(#part); });
             } else {
                 let end_brace = part.find('}').unwrap();
-                let param_name = &part[..end_brace];
+                let param_name = part[..end_brace].trim_start_matches('*');
                 let rest = &part[end_brace + 1..];
                 let param_ident = format_ident!("{}", param_name);
                 url_expr.extend(quote! {
@@ -244,7 +561,7 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
             #[allow(non_snake_case)]
             #fn_vis fn #endpoint_name(#(#url_fn_params),*) -> ::acacia_core::Endpoint {
                 let path = { #url_expr };
-                #endpoint_fn_constructor(path)
+                #endpoint_fn_constructor(path).in_module(module_path!())
             }
         }
     };
@@ -258,7 +575,7 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
 
         // Route handler wrapper
         fn #handler_name() -> ::axum::routing::MethodRouter<::acacia_core::AppState> {
-            #axum_method(#fn_name)
+            #method_router #(.layer(#layers))*
         }
 
         // Route registration
@@ -268,6 +585,8 @@ pub fn action_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
                 #method_variant,
                 #handler_name,
             )
+            .in_module(module_path!())
+            .named(stringify!(#fn_name))#also #timeout
         }
     };
 
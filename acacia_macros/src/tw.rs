@@ -15,7 +15,7 @@ enum TwItem {
     /// Simple expression that evaluates to something implementing TwClass
     Simple(Expr),
     /// Conditional class: expr => condition
-    Conditional { class: Expr, condition: Expr },
+    Conditional { class: Expr, condition: Box<Expr> },
 }
 
 impl Parse for TwItem {
@@ -28,7 +28,7 @@ impl Parse for TwItem {
             let condition: Expr = input.parse()?;
             Ok(TwItem::Conditional {
                 class: expr,
-                condition,
+                condition: Box::new(condition),
             })
         } else {
             Ok(TwItem::Simple(expr))
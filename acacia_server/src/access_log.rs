@@ -0,0 +1,169 @@
+//! One log line per request, in Common Log Format or JSON.
+
+use axum::{
+    body::HttpBody,
+    extract::{ConnectInfo, Request},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// How each access log line is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `127.0.0.1 - - [14/Oct/2026:09:30:00 +0000] "GET /tasks HTTP/1.1" 200 1534`
+    Common,
+    /// One JSON object per line, with the duration and user agent too.
+    Json,
+}
+
+/// Request logging set with `Acacia::access_log`, written to stdout.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new().access_log(AccessLog::json().skip("/healthz").skip_assets())
+/// ```
+#[derive(Clone, Debug)]
+pub struct AccessLog {
+    format: LogFormat,
+    pub(crate) skip: Vec<String>,
+    pub(crate) skip_assets: bool,
+}
+
+impl AccessLog {
+    /// Log in the Common Log Format used by Apache and nginx.
+    pub fn common() -> Self {
+        Self::new(LogFormat::Common)
+    }
+
+    /// Log one JSON object per line, for log collectors.
+    pub fn json() -> Self {
+        Self::new(LogFormat::Json)
+    }
+
+    fn new(format: LogFormat) -> Self {
+        Self {
+            format,
+            skip: Vec::new(),
+            skip_assets: false,
+        }
+    }
+
+    /// Don't log requests for `path`; a trailing `*` matches every path
+    /// starting with the rest, e.g. `"/api/internal/*"`.
+    pub fn skip(mut self, path: &str) -> Self {
+        self.skip.push(path.to_string());
+        self
+    }
+
+    /// Don't log requests for static assets and the bundled scripts.
+    pub fn skip_assets(mut self) -> Self {
+        self.skip_assets = true;
+        self
+    }
+
+    fn skips(&self, path: &str) -> bool {
+        self.skip.iter().any(|skip| match skip.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == skip,
+        })
+    }
+}
+
+pub(crate) async fn log(config: Arc<AccessLog>, req: Request, next: Next) -> Response {
+    let path = req.uri().path();
+    if config.skips(path) {
+        return next.run(req).await;
+    }
+
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let target = req
+        .uri()
+        .path_and_query()
+        .map_or(path, |target| target.as_str())
+        .to_string();
+    let version = format!("{:?}", req.version());
+    let remote = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|agent| agent.to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(req).await;
+    let status = response.status().as_u16();
+    let bytes = response.body().size_hint().exact();
+    let (date, time) = timestamps(SystemTime::now());
+
+    match config.format {
+        LogFormat::Common => println!(
+            "{} - - [{}] \"{} {} {}\" {} {}",
+            remote.as_deref().unwrap_or("-"),
+            date,
+            method,
+            target,
+            version,
+            status,
+            bytes.map_or("-".to_string(), |bytes| bytes.to_string()),
+        ),
+        LogFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "time": time,
+                "remote": remote,
+                "method": method,
+                "path": target,
+                "version": version,
+                "status": status,
+                "bytes": bytes,
+                "duration_ms": start.elapsed().as_secs_f64() * 1000.0,
+                "user_agent": user_agent,
+            })
+        ),
+    }
+    response
+}
+
+/// `now` as a Common Log Format date and as RFC 3339, both in UTC.
+fn timestamps(now: SystemTime) -> (String, String) {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    let (hour, minute, second) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    // Days since the epoch to a calendar date, from Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (
+        format!(
+            "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            hour,
+            minute,
+            second
+        ),
+        format!(
+            "{}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year, month, day, hour, minute, second
+        ),
+    )
+}
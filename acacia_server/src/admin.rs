@@ -0,0 +1,302 @@
+//! Admin CRUD pages for every registered model.
+
+use acacia_core::{escape_html, AppError, AppState, Page};
+use acacia_db::{AdminColumn, AdminRegistration, Db};
+use axum::{
+    extract::{Path, Request},
+    http::request::Parts,
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Extension, Form, Router,
+};
+use std::sync::Arc;
+
+type Guard = dyn Fn(&Parts) -> bool + Send + Sync;
+
+/// Admin configuration stored on the builder.
+#[derive(Clone)]
+pub(crate) struct AdminConfig {
+    path: String,
+    guard: Option<Arc<Guard>>,
+}
+
+/// Prefix the admin is mounted at, for building links.
+#[derive(Clone)]
+struct Base(Arc<str>);
+
+impl AdminConfig {
+    pub(crate) fn new(path: &str) -> Self {
+        Self {
+            path: path.trim_end_matches('/').to_string(),
+            guard: None,
+        }
+    }
+
+    pub(crate) fn with_guard(
+        mut self,
+        guard: impl Fn(&Parts) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.guard = Some(Arc::new(guard));
+        self
+    }
+
+    pub(crate) fn at(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Routes for the admin, to be nested at [`AdminConfig::path`].
+    pub(crate) fn router(self) -> Router<AppState> {
+        let guard = self.guard.clone();
+        Router::new()
+            .route("/", get(index))
+            .route("/:table", get(list).post(create))
+            .route("/:table/new", get(new))
+            .route("/:table/:id", get(edit).post(update))
+            .route("/:table/:id/delete", post(delete))
+            .layer(Extension(Base(self.path.into())))
+            .layer(axum::middleware::from_fn(
+                move |req: Request, next: Next| {
+                    let guard = guard.clone();
+                    async move { check_guard(guard.as_deref(), req, next).await }
+                },
+            ))
+    }
+}
+
+/// Reject requests the guard refuses.
+///
+/// Without a guard the admin is only reachable in debug builds, so a
+/// forgotten guard never exposes the database in production.
+async fn check_guard(guard: Option<&Guard>, req: Request, next: Next) -> Response {
+    let (parts, body) = req.into_parts();
+    let allowed = match guard {
+        Some(guard) => guard(&parts),
+        None => cfg!(debug_assertions),
+    };
+    if !allowed {
+        return AppError::Forbidden.into_response();
+    }
+    next.run(Request::from_parts(parts, body)).await
+}
+
+fn registration(table: &str) -> acacia_core::Result<&'static AdminRegistration> {
+    inventory::iter::<AdminRegistration>
+        .into_iter()
+        .find(|registration| registration.table == table)
+        .ok_or(AppError::NotFound)
+}
+
+async fn index(Extension(Base(base)): Extension<Base>) -> Page {
+    let mut models: Vec<&AdminRegistration> =
+        inventory::iter::<AdminRegistration>.into_iter().collect();
+    models.sort_by_key(|registration| registration.name);
+
+    let mut html = String::from("<main class=\"acacia-admin\"><h1>Admin</h1><ul>");
+    for registration in models {
+        html.push_str(&format!(
+            "<li><a href=\"{}/{}\">{}</a></li>",
+            base,
+            registration.table,
+            escape_html(registration.name)
+        ));
+    }
+    html.push_str("</ul></main>");
+    Page::with_title(html, "Admin")
+}
+
+async fn list(
+    Extension(Base(base)): Extension<Base>,
+    Path(table): Path<String>,
+    db: Db,
+) -> acacia_core::Result<Page> {
+    let registration = registration(&table)?;
+    let rows = (registration.list)(db).await?;
+    let prefix = format!("{}/{}", base, registration.table);
+
+    let mut html = format!(
+        "<main class=\"acacia-admin\"><p><a href=\"{}\">Admin</a></p><h1>{}</h1>\
+         <p><a href=\"{}/new\">New {}</a></p><table><thead><tr>",
+        base,
+        escape_html(registration.name),
+        prefix,
+        escape_html(registration.name)
+    );
+    for column in registration.columns {
+        html.push_str(&format!("<th scope=\"col\">{}</th>", column.name));
+    }
+    html.push_str("<th scope=\"col\"></th></tr></thead><tbody>");
+    for row in &rows {
+        html.push_str("<tr>");
+        for column in registration.columns {
+            html.push_str(&format!("<td>{}</td>", escape_html(&cell(row, column))));
+        }
+        match registration.key() {
+            Some(key) => html.push_str(&format!(
+                "<td><a href=\"{}/{}\">Edit</a></td>",
+                prefix,
+                escape_html(&cell(row, key))
+            )),
+            None => html.push_str("<td></td>"),
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</tbody></table></main>");
+    Ok(Page::with_title(html, registration.name))
+}
+
+async fn new(
+    Extension(Base(base)): Extension<Base>,
+    Path(table): Path<String>,
+) -> acacia_core::Result<Page> {
+    let registration = registration(&table)?;
+    let prefix = format!("{}/{}", base, registration.table);
+    let html = format!(
+        "<main class=\"acacia-admin\"><p><a href=\"{}\">{}</a></p><h1>New {}</h1>{}</main>",
+        prefix,
+        escape_html(registration.name),
+        escape_html(registration.name),
+        form(registration, &prefix, None)
+    );
+    Ok(Page::with_title(html, registration.name))
+}
+
+async fn create(
+    Extension(Base(base)): Extension<Base>,
+    Path(table): Path<String>,
+    db: Db,
+    Form(values): Form<Vec<(String, String)>>,
+) -> acacia_core::Result<Redirect> {
+    let registration = registration(&table)?;
+    (registration.create)(db, form_json(registration, &values)?).await?;
+    Ok(Redirect::to(&format!("{}/{}", base, registration.table)))
+}
+
+async fn edit(
+    Extension(Base(base)): Extension<Base>,
+    Path((table, id)): Path<(String, String)>,
+    db: Db,
+) -> acacia_core::Result<Page> {
+    let registration = registration(&table)?;
+    let row = (registration.get)(db, id.clone())
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let prefix = format!("{}/{}", base, registration.table);
+    let action = format!("{}/{}", prefix, id);
+    let html = format!(
+        "<main class=\"acacia-admin\"><p><a href=\"{}\">{}</a></p><h1>Edit {}</h1>{}\
+         <form method=\"post\" action=\"{}/delete\"><button type=\"submit\">Delete</button></form></main>",
+        prefix,
+        escape_html(registration.name),
+        escape_html(registration.name),
+        form(registration, &escape_html(&action), Some(&row)),
+        escape_html(&action)
+    );
+    Ok(Page::with_title(html, registration.name))
+}
+
+async fn update(
+    Extension(Base(base)): Extension<Base>,
+    Path((table, id)): Path<(String, String)>,
+    db: Db,
+    Form(values): Form<Vec<(String, String)>>,
+) -> acacia_core::Result<Redirect> {
+    let registration = registration(&table)?;
+    (registration.update)(db, id, form_json(registration, &values)?).await?;
+    Ok(Redirect::to(&format!("{}/{}", base, registration.table)))
+}
+
+async fn delete(
+    Extension(Base(base)): Extension<Base>,
+    Path((table, id)): Path<(String, String)>,
+    db: Db,
+) -> acacia_core::Result<Redirect> {
+    let registration = registration(&table)?;
+    (registration.delete)(db, id).await?;
+    Ok(Redirect::to(&format!("{}/{}", base, registration.table)))
+}
+
+/// A row's value for a column as plain text.
+fn cell(row: &serde_json::Value, column: &AdminColumn) -> String {
+    match row.get(column.name) {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Render the create/edit form; primary keys are never editable.
+fn form(registration: &AdminRegistration, action: &str, row: Option<&serde_json::Value>) -> String {
+    let mut html = format!("<form method=\"post\" action=\"{}\">", action);
+    for column in registration.columns.iter().filter(|column| !column.key) {
+        let value = row.map(|row| cell(row, column)).unwrap_or_default();
+        let mut attrs = format!(
+            "id=\"{}\" name=\"{}\" type=\"{}\"",
+            column.name, column.name, column.input_type
+        );
+        match column.input_type {
+            "checkbox" => {
+                attrs.push_str(" value=\"true\"");
+                if value == "true" {
+                    attrs.push_str(" checked");
+                }
+            }
+            "number" => attrs.push_str(&format!(" step=\"any\" value=\"{}\"", value)),
+            _ => attrs.push_str(&format!(
+                " value=\"{}\"",
+                html_escape::encode_double_quoted_attribute(&value)
+            )),
+        }
+        if !column.optional && column.input_type != "checkbox" {
+            attrs.push_str(" required");
+        }
+        html.push_str(&format!(
+            "<p><label for=\"{}\">{}</label> <input {} /></p>",
+            column.name, column.name, attrs
+        ));
+    }
+    html.push_str("<button type=\"submit\">Save</button></form>");
+    html
+}
+
+/// Convert submitted text into a JSON object matching the model's field types.
+fn form_json(
+    registration: &AdminRegistration,
+    values: &[(String, String)],
+) -> acacia_core::Result<serde_json::Value> {
+    let mut object = serde_json::Map::new();
+    for column in registration.columns.iter().filter(|column| !column.key) {
+        let value = values
+            .iter()
+            .rev()
+            .find(|(name, _)| name == column.name)
+            .map(|(_, value)| value.as_str());
+        let json = match (column.input_type, value.unwrap_or("")) {
+            // Unchecked boxes are not submitted at all
+            ("checkbox", _) => serde_json::Value::Bool(value.is_some()),
+            (_, "") if column.optional => serde_json::Value::Null,
+            ("number", text) => parse_number(text).ok_or_else(|| {
+                AppError::BadRequest(format!("{}: expected a number", column.name))
+            })?,
+            (_, text) => serde_json::Value::String(text.to_string()),
+        };
+        object.insert(column.name.to_string(), json);
+    }
+    Ok(serde_json::Value::Object(object))
+}
+
+fn parse_number(text: &str) -> Option<serde_json::Value> {
+    let text = text.trim();
+    if let Ok(int) = text.parse::<i64>() {
+        return Some(int.into());
+    }
+    text.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(serde_json::Value::Number)
+}
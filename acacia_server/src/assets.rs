@@ -0,0 +1,326 @@
+//! Static files, read from disk or embedded in the binary.
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::SystemTime};
+
+use acacia_core::AppState;
+use axum::{
+    body::Bytes,
+    extract::Request,
+    http::{header, HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower_http::services::ServeDir;
+
+/// Static files set with `Acacia::assets`, served under `/static` by default.
+///
+/// Text files (HTML, CSS, JavaScript, JSON, SVG, ...) are gzipped once when the
+/// app starts and kept in memory, so clients that accept gzip get them
+/// compressed without per-request work. Files compressed at build time take
+/// precedence: with `app.css.br` or `app.css.gz` next to `app.css`, requests
+/// for `app.css` get the Brotli or gzip copy, from disk or embedded.
+///
+/// # Example
+/// ```ignore
+/// // static/app.css is served at /static/app.css
+/// Acacia::new().assets(Assets::dir("static"))
+/// ```
+pub struct Assets {
+    prefix: String,
+    dir: String,
+    #[cfg(feature = "embed")]
+    embedded: Option<&'static include_dir::Dir<'static>>,
+}
+
+impl Assets {
+    /// Serve the files in `dir`, read from disk on each request.
+    pub fn dir(dir: &str) -> Self {
+        Self {
+            prefix: "/static".to_string(),
+            dir: dir.to_string(),
+            #[cfg(feature = "embed")]
+            embedded: None,
+        }
+    }
+
+    /// Serve files embedded in the binary, for single-file deployments.
+    ///
+    /// Debug builds still read from `dir` when it exists, so edits show up without
+    /// a rebuild.
+    ///
+    /// # Example
+    /// ```ignore
+    /// static ASSETS: Dir = include_dir!("$CARGO_MANIFEST_DIR/static");
+    ///
+    /// Acacia::new().assets(Assets::embed("static", &ASSETS))
+    /// ```
+    #[cfg(feature = "embed")]
+    pub fn embed(dir: &str, files: &'static include_dir::Dir<'static>) -> Self {
+        Self {
+            embedded: Some(files),
+            ..Self::dir(dir)
+        }
+    }
+
+    /// Serve under `prefix` instead of `/static`.
+    pub fn at(mut self, prefix: &str) -> Self {
+        self.prefix = format!("/{}", prefix.trim_matches('/'));
+        self
+    }
+
+    pub(crate) fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub(crate) fn router(self) -> Router<AppState> {
+        #[cfg(feature = "embed")]
+        if let Some(files) = self.embedded {
+            let on_disk = cfg!(debug_assertions) && std::path::Path::new(&self.dir).is_dir();
+            if !on_disk {
+                return embedded::router(&self.prefix, files);
+            }
+        }
+        let on_disk = Arc::new(OnDisk::load(&self.prefix, &self.dir));
+        let files = ServeDir::new(self.dir)
+            .precompressed_br()
+            .precompressed_gzip();
+        Router::new()
+            .nest_service(&self.prefix, files)
+            .layer(axum::middleware::from_fn(move |req, next| {
+                serve_gzipped(on_disk.clone(), req, next)
+            }))
+    }
+}
+
+/// Gzip copies of the files under an assets directory, made at startup and
+/// keyed by their path relative to it.
+struct OnDisk {
+    prefix: String,
+    dir: PathBuf,
+    files: HashMap<String, Gzipped>,
+}
+
+struct Gzipped {
+    /// The original's length and modification time, to notice edits made
+    /// since the copy was taken.
+    len: u64,
+    modified: Option<SystemTime>,
+    contents: Bytes,
+}
+
+impl OnDisk {
+    fn load(prefix: &str, dir: &str) -> Self {
+        let mut on_disk = Self {
+            prefix: prefix.to_string(),
+            dir: PathBuf::from(dir),
+            files: HashMap::new(),
+        };
+        on_disk.walk(PathBuf::from(dir), "");
+        on_disk
+    }
+
+    fn walk(&mut self, dir: PathBuf, relative: &str) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = format!("{}{}", relative, name);
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                self.walk(entry.path(), &format!("{}/", path));
+                continue;
+            }
+            // A `.gz` made at build time is served by `ServeDir` instead
+            if dir.join(format!("{}.gz", name)).is_file() {
+                continue;
+            }
+            let Some(contents) = std::fs::read(entry.path())
+                .ok()
+                .and_then(|contents| gzipped(&path, &contents))
+            else {
+                continue;
+            };
+            let gzipped = Gzipped {
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+                contents,
+            };
+            self.files.insert(path, gzipped);
+        }
+    }
+}
+
+async fn serve_gzipped(on_disk: Arc<OnDisk>, req: Request, next: Next) -> Response {
+    let path = req
+        .uri()
+        .path()
+        .strip_prefix(on_disk.prefix.as_str())
+        .and_then(|path| path.strip_prefix('/'));
+    let Some((path, gzipped)) = path.and_then(|path| Some(path).zip(on_disk.files.get(path)))
+    else {
+        return next.run(req).await;
+    };
+    let accepted = accepted_encodings(req.headers());
+    let wants_gzip = matches!(*req.method(), Method::GET | Method::HEAD)
+        && accepts(accepted, "gzip")
+        && !(accepts(accepted, "br") && on_disk.dir.join(format!("{}.br", path)).is_file());
+    let current = std::fs::metadata(on_disk.dir.join(path)).is_ok_and(|metadata| {
+        metadata.len() == gzipped.len && metadata.modified().ok() == gzipped.modified
+    });
+    if !wants_gzip || !current {
+        return next.run(req).await;
+    }
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    (
+        [
+            (header::CONTENT_TYPE, mime.as_ref()),
+            (header::CONTENT_ENCODING, "gzip"),
+            (header::VARY, "accept-encoding"),
+        ],
+        gzipped.contents.clone(),
+    )
+        .into_response()
+}
+
+/// A gzip copy of `contents` when its type compresses well and the copy is
+/// smaller.
+fn gzipped(path: &str, contents: &[u8]) -> Option<Bytes> {
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let compressible = mime.type_() == "text"
+        || mime
+            .suffix()
+            .is_some_and(|suffix| suffix == "xml" || suffix == "json")
+        || matches!(
+            mime.essence_str(),
+            "application/javascript" | "application/json" | "application/xml" | "application/wasm"
+        );
+    if !compressible {
+        return None;
+    }
+    let compressed = crate::gzip::gzip(contents);
+    (compressed.len() < contents.len()).then(|| Bytes::from(compressed))
+}
+
+fn accepted_encodings(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// Whether an `Accept-Encoding` value allows `encoding`, ignoring
+/// preferences other than `q=0`.
+fn accepts(accepted: &str, encoding: &str) -> bool {
+    accepted.split(',').any(|entry| {
+        let mut parts = entry.split(';').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        let refused = parts.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q == 0.0)
+        });
+        (name.eq_ignore_ascii_case(encoding) || name == "*") && !refused
+    })
+}
+
+#[cfg(feature = "embed")]
+mod embedded {
+    use std::{collections::HashMap, sync::Arc};
+
+    use acacia_core::{AppError, AppState};
+    use axum::{
+        body::Bytes,
+        extract::Path,
+        http::{header, HeaderMap},
+        response::{IntoResponse, Response},
+        routing::get,
+        Router,
+    };
+    use include_dir::Dir;
+
+    use super::{accepted_encodings, accepts, gzipped};
+
+    /// Precompressed copies looked for next to a file, most compact first.
+    const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+    pub(super) fn router(prefix: &str, files: &'static Dir<'static>) -> Router<AppState> {
+        let mut compressed = HashMap::new();
+        gzip_all(files, &mut compressed);
+        let compressed = Arc::new(compressed);
+        Router::new().route(
+            &format!("{}/*path", prefix),
+            get(move |Path(path): Path<String>, headers: HeaderMap| {
+                serve(files, compressed.clone(), path, headers)
+            }),
+        )
+    }
+
+    /// Gzip every file without a `.gz` made at build time, keyed by path.
+    fn gzip_all(dir: &'static Dir<'static>, compressed: &mut HashMap<String, Bytes>) {
+        for file in dir.files() {
+            let path = file.path().to_string_lossy().replace('\\', "/");
+            if dir.get_file(format!("{}.gz", path)).is_some() {
+                continue;
+            }
+            if let Some(contents) = gzipped(&path, file.contents()) {
+                compressed.insert(path, contents);
+            }
+        }
+        for dir in dir.dirs() {
+            gzip_all(dir, compressed);
+        }
+    }
+
+    async fn serve(
+        files: &'static Dir<'static>,
+        compressed: Arc<HashMap<String, Bytes>>,
+        path: String,
+        headers: HeaderMap,
+    ) -> Response {
+        let Some(file) = files.get_file(&path) else {
+            return AppError::NotFound.into_response();
+        };
+        let mime = mime_guess::from_path(&path).first_or_octet_stream();
+        let accepted = accepted_encodings(&headers);
+        for (encoding, extension) in ENCODINGS {
+            if !accepts(accepted, encoding) {
+                continue;
+            }
+            if let Some(precompressed) = files.get_file(format!("{}.{}", path, extension)) {
+                return (
+                    [
+                        (header::CONTENT_TYPE, mime.as_ref()),
+                        (header::CONTENT_ENCODING, encoding),
+                        (header::VARY, "accept-encoding"),
+                    ],
+                    precompressed.contents(),
+                )
+                    .into_response();
+            }
+        }
+        if let Some(gzipped) = compressed.get(&path).filter(|_| accepts(accepted, "gzip")) {
+            return (
+                [
+                    (header::CONTENT_TYPE, mime.as_ref()),
+                    (header::CONTENT_ENCODING, "gzip"),
+                    (header::VARY, "accept-encoding"),
+                ],
+                gzipped.clone(),
+            )
+                .into_response();
+        }
+        (
+            [
+                (header::CONTENT_TYPE, mime.as_ref()),
+                (header::VARY, "accept-encoding"),
+            ],
+            file.contents(),
+        )
+            .into_response()
+    }
+}
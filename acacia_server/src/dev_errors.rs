@@ -0,0 +1,102 @@
+//! The detailed page shown for server errors in debug builds outside the `Prod` profile.
+
+use crate::report::RequestInfo;
+use acacia_core::{escape_html, ErrorReport};
+
+/// The error page, as a full document or as a fragment for htmx requests.
+pub(crate) fn page(report: &ErrorReport, info: &RequestInfo, htmx: bool) -> String {
+    let details = render(report, info);
+    if htmx {
+        return details;
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n\
+         <title>{} {}</title>\n</head>\n<body style=\"margin: 0; background: #fff;\">\n{}\n</body>\n</html>",
+        report.status.as_u16(),
+        escape_html(&report.message),
+        details
+    )
+}
+
+fn render(report: &ErrorReport, info: &RequestInfo) -> String {
+    let mut html = format!(
+        r#"<div class="acacia-dev-error" role="alert" style="font-family: system-ui, sans-serif; padding: 24px; color: #1f2937;">
+<h1 style="margin: 0 0 4px; color: #991b1b; font-size: 24px;">{} {}</h1>
+<p style="margin: 0 0 16px; color: #6b7280;">Shown because this is a debug build outside the Prod profile.</p>"#,
+        report.status.as_u16(),
+        escape_html(&report.message)
+    );
+
+    if !report.causes.is_empty() {
+        html.push_str(&heading("Caused by"));
+        html.push_str("<ol style=\"margin: 0; padding-left: 20px;\">");
+        for cause in &report.causes {
+            html.push_str(&format!("<li><code>{}</code></li>", escape_html(cause)));
+        }
+        html.push_str("</ol>");
+    }
+
+    html.push_str(&heading("Request"));
+    let mut request = vec![(
+        "Request".to_string(),
+        format!("{} {}", info.method, info.uri),
+    )];
+    if let Some(route) = &info.route {
+        request.push(("Route".to_string(), route.clone()));
+    }
+    if let Some(handler) = &info.handler {
+        request.push(("Handler".to_string(), handler.clone()));
+    }
+    html.push_str(&table(&request));
+
+    html.push_str(&heading("Headers"));
+    let headers: Vec<(String, String)> = info
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+    html.push_str(&table(&headers));
+
+    if let Some(form) = &info.form {
+        html.push_str(&heading("Form data"));
+        html.push_str(&table(form));
+    }
+
+    if let Some(backtrace) = &report.backtrace {
+        html.push_str(&format!(
+            "<details style=\"margin-top: 20px;\"><summary style=\"cursor: pointer; font-weight: 600;\">Backtrace</summary>\
+             <pre style=\"margin: 8px 0 0; padding: 12px; overflow-x: auto; background: #f3f4f6; font-size: 12px;\">{}</pre></details>",
+            escape_html(backtrace)
+        ));
+    }
+
+    html.push_str("</div>");
+    html
+}
+
+fn heading(title: &str) -> String {
+    format!(
+        "<h2 style=\"margin: 20px 0 8px; font-size: 16px;\">{}</h2>",
+        title
+    )
+}
+
+fn table(rows: &[(String, String)]) -> String {
+    let mut html =
+        String::from("<table style=\"border-collapse: collapse; font-size: 14px;\"><tbody>");
+    for (name, value) in rows {
+        html.push_str(&format!(
+            "<tr><th style=\"padding: 2px 16px 2px 0; text-align: left; vertical-align: top; color: #6b7280;\">{}</th>\
+             <td style=\"padding: 2px 0;\"><code>{}</code></td></tr>",
+            escape_html(name),
+            escape_html(value)
+        ));
+    }
+    html.push_str("</tbody></table>");
+    html
+}
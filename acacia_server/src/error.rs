@@ -0,0 +1,51 @@
+//! Errors that stop the server from starting.
+
+use acacia_auth::SessionError;
+use acacia_db::DbError;
+
+/// Why [`Acacia::serve`](crate::Acacia::serve) failed.
+#[derive(Debug, thiserror::Error)]
+pub enum AcaciaError {
+    #[error("Invalid address `{addr}`: {source}")]
+    Address {
+        addr: String,
+        source: std::net::AddrParseError,
+    },
+
+    #[error("Failed to bind {addr}: {source}")]
+    Bind {
+        addr: std::net::SocketAddr,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to bind {}: {source}", .path.display())]
+    BindUnix {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to connect to database: {0}")]
+    Database(#[from] sea_orm::DbErr),
+
+    /// Running migrations failed, or `MigratePolicy::Check` found the schema out of date.
+    #[error("Failed to migrate the database: {0}")]
+    Migration(#[from] DbError),
+
+    #[error("Failed to initialize session store: {0}")]
+    Sessions(#[from] SessionError),
+
+    #[error("Failed to read htmx from {path}: {source}")]
+    Htmx {
+        path: String,
+        source: std::io::Error,
+    },
+
+    /// The Tokio runtime for `Acacia::run` couldn't be started.
+    #[error("Failed to start the runtime: {0}")]
+    Runtime(std::io::Error),
+
+    /// The certificate or private key given to `Tls` couldn't be used.
+    #[cfg(feature = "tls")]
+    #[error("TLS error: {0}")]
+    Tls(String),
+}
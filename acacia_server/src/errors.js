@@ -0,0 +1,9 @@
+// htmx skips error responses; swap them when the server retargeted them
+// to an error container.
+htmx.on("htmx:beforeSwap", function (event) {
+    var xhr = event.detail.xhr;
+    if (xhr.status >= 400 && xhr.getResponseHeader("HX-Retarget")) {
+        event.detail.shouldSwap = true;
+        event.detail.isError = false;
+    }
+});
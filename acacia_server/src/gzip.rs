@@ -0,0 +1,199 @@
+//! Gzip compression for assets compressed once at startup.
+//!
+//! A single DEFLATE block with the fixed Huffman codes and LZ77 matches found
+//! through hash chains: not as small as `gzip -9`, but quick and dependency-free.
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// Earlier positions tried per match, trading compression for speed.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// `data` as a gzip member.
+pub(crate) fn gzip(data: &[u8]) -> Vec<u8> {
+    // No file name or modification time, and an unknown OS
+    let mut out = Bits::new(vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255]);
+    deflate(data, &mut out);
+    let mut bytes = out.finish();
+    bytes.extend(crc32(data).to_le_bytes());
+    bytes.extend((data.len() as u32).to_le_bytes());
+    bytes
+}
+
+fn deflate(data: &[u8], out: &mut Bits) {
+    // The final block, with fixed Huffman codes
+    out.write(1, 1);
+    out.write(1, 2);
+
+    let mut chains = Chains::new(data);
+    let mut i = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_distance) = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let max = (data.len() - i).min(MAX_MATCH);
+            let mut candidate = chains.head[chains.hash(i)];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + max])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    (best_len, best_distance) = (len, i - candidate);
+                    if len == max {
+                        break;
+                    }
+                }
+                candidate = chains.prev[candidate % WINDOW];
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            let code = LENGTH_BASE
+                .iter()
+                .rposition(|&base| base as usize <= best_len);
+            let code = code.unwrap_or_default();
+            literal(out, 257 + code as u16);
+            out.write(
+                (best_len - LENGTH_BASE[code] as usize) as u32,
+                LENGTH_EXTRA[code] as u32,
+            );
+            let code = DISTANCE_BASE
+                .iter()
+                .rposition(|&base| base as usize <= best_distance)
+                .unwrap_or_default();
+            out.huffman(code as u32, 5);
+            out.write(
+                (best_distance - DISTANCE_BASE[code] as usize) as u32,
+                DISTANCE_EXTRA[code] as u32,
+            );
+            for j in i..i + best_len {
+                chains.insert(j);
+            }
+            i += best_len;
+        } else {
+            literal(out, data[i] as u16);
+            chains.insert(i);
+            i += 1;
+        }
+    }
+    literal(out, 256);
+}
+
+/// Earlier positions sharing the hash of their first three bytes.
+struct Chains<'a> {
+    data: &'a [u8],
+    head: Vec<usize>,
+    prev: Vec<usize>,
+}
+
+impl<'a> Chains<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            prev: vec![usize::MAX; WINDOW],
+        }
+    }
+
+    fn hash(&self, i: usize) -> usize {
+        let d = self.data;
+        let value = (d[i] as usize) << 16 | (d[i + 1] as usize) << 8 | d[i + 2] as usize;
+        (value.wrapping_mul(2654435761) >> 8) & ((1 << HASH_BITS) - 1)
+    }
+
+    fn insert(&mut self, i: usize) {
+        if i + MIN_MATCH <= self.data.len() {
+            let h = self.hash(i);
+            self.prev[i % WINDOW] = self.head[h];
+            self.head[h] = i;
+        }
+    }
+}
+
+/// Write a literal, length or end-of-block symbol with its fixed code.
+fn literal(out: &mut Bits, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => out.huffman(0x30 + symbol, 8),
+        144..=255 => out.huffman(0x190 + symbol - 144, 9),
+        256..=279 => out.huffman(symbol - 256, 7),
+        _ => out.huffman(0xc0 + symbol - 280, 8),
+    }
+}
+
+/// A bit stream, filled from the least significant bit of each byte.
+struct Bits {
+    bytes: Vec<u8>,
+    pending: u64,
+    count: u32,
+}
+
+impl Bits {
+    fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            pending: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        self.pending |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting from their most significant bit.
+    fn huffman(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+        self.bytes
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    !data.iter().fold(!0u32, |crc, &byte| {
+        table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
@@ -1,36 +1,149 @@
 //! Server module for Acacia, providing the main application builder.
 
-use acacia_core::{AppState, RouteDefinition};
-use acacia_db::{Db, MigratePolicy};
-use axum::{
-    response::IntoResponse,
-    routing::get,
-    Router,
-};
+use acacia_auth::SessionConfig;
+use acacia_core::{AppState, ErrorReport, Htmx, Profile, RequestContext, RouteDefinition};
+use acacia_db::{Db, DbConfig, MigratePolicy};
+use axum::extract::Request;
+use axum::handler::Handler;
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, get, MethodRouter, Route};
+use axum::Router;
 use sea_orm::Database;
+use std::convert::Infallible;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::{Stream, StreamExt};
+use tower::{Layer, Service};
+
+mod access_log;
+mod admin;
+mod assets;
+mod dev_errors;
+mod error;
+mod gzip;
+mod listen;
+mod module;
+mod normalize;
+mod report;
+mod security;
+mod sitemap;
+#[cfg(feature = "tls")]
+mod tls;
+
+#[cfg(feature = "embed")]
+pub use ::include_dir::{self, include_dir, Dir};
+pub use access_log::{AccessLog, LogFormat};
+use admin::AdminConfig;
+pub use assets::Assets;
+pub use error::AcaciaError;
+pub use listen::{Http, Server};
+pub use module::AcaciaModule;
+use normalize::PathConfig;
+pub use normalize::TrailingSlash;
+pub use report::RequestInfo;
+use report::{ErrorHook, Reporting};
+pub use security::{Csp, Security};
+pub use sitemap::Sitemap;
+use sitemap::SitemapConfig;
+#[cfg(feature = "tls")]
+pub use tls::Tls;
 
 /// HTMX library content (minified).
 const HTMX_JS: &str = include_str!("htmx.min.js");
 
+/// Swaps in error responses the server retargeted to an error container.
+const ERRORS_JS: &str = include_str!("errors.js");
+
+/// Client for elements subscribed with `live(topic)`.
+const LIVE_JS: &str = include_str!("live.js");
+
+/// A layer added with `Acacia::layer`, applied once the routes are assembled.
+type RouterLayer = Box<dyn FnOnce(Router<AppState>) -> Router<AppState> + Send>;
+
+/// Fills in each request's context, set with `Acacia::context`.
+type ContextHook = dyn Fn(&axum::http::request::Parts, &mut RequestContext) + Send + Sync;
+
 /// The main Acacia application builder.
 pub struct Acacia {
-    database_url: Option<String>,
+    database: Option<DbConfig>,
     migrate_policy: MigratePolicy,
+    sitemap: Option<SitemapConfig>,
+    admin: Option<AdminConfig>,
+    assets: Option<Assets>,
+    state: AppState,
+    htmx: Htmx,
+    profile: Profile,
+    stylesheet: Option<String>,
+    live_secret: Option<String>,
+    not_found: Option<MethodRouter<AppState>>,
+    routes: Router<AppState>,
+    mounts: Vec<(String, AcaciaModule)>,
+    paths: PathConfig,
+    sessions: Option<SessionConfig>,
+    security: Option<Security>,
+    timeout: Option<Duration>,
+    access_log: Option<AccessLog>,
+    on_error: Option<Arc<ErrorHook>>,
+    context: Option<Arc<ContextHook>>,
+    layers: Vec<RouterLayer>,
+    http: Http,
+    workers: Option<usize>,
+    #[cfg(feature = "tls")]
+    tls: Option<Tls>,
+    #[cfg(feature = "oauth")]
+    oauth: acacia_auth::OAuth,
+    #[cfg(feature = "magic-link")]
+    magic_link: Option<acacia_auth::MagicLink>,
 }
 
 impl Acacia {
     /// Create a new Acacia application.
     pub fn new() -> Self {
         Self {
-            database_url: None,
+            database: None,
             migrate_policy: MigratePolicy::Auto,
+            sitemap: None,
+            admin: None,
+            assets: None,
+            state: AppState::new(),
+            htmx: Htmx::Bundled,
+            profile: Profile::detect(),
+            stylesheet: None,
+            live_secret: None,
+            not_found: None,
+            routes: Router::new(),
+            mounts: Vec::new(),
+            paths: PathConfig::default(),
+            sessions: None,
+            security: None,
+            timeout: None,
+            access_log: None,
+            on_error: None,
+            context: None,
+            layers: Vec::new(),
+            http: Http::new(),
+            workers: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "oauth")]
+            oauth: acacia_auth::OAuth::new(),
+            #[cfg(feature = "magic-link")]
+            magic_link: None,
         }
     }
 
-    /// Set the database connection URL.
-    pub fn database(mut self, url: &str) -> Self {
-        self.database_url = Some(url.to_string());
+    /// Set the database connection URL, or a [`DbConfig`] with read replicas.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().database("sqlite://app.db?mode=rwc")
+    /// ```
+    pub fn database(mut self, config: impl Into<DbConfig>) -> Self {
+        self.database = Some(config.into());
         self
     }
 
@@ -40,18 +153,702 @@ impl Acacia {
         self
     }
 
-    /// Start serving the application.
-    pub async fn serve(self, addr: &str) {
+    /// Serve `/sitemap.xml` listing every page route without path parameters,
+    /// except those with `require = ...` or `sitemap = false`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().sitemap("https://example.com")
+    /// ```
+    pub fn sitemap(mut self, base_url: &str) -> Self {
+        self.sitemap = Some(SitemapConfig::new(base_url));
+        self
+    }
+
+    /// Serve `/sitemap.xml` with static page routes plus dynamic URLs.
+    ///
+    /// The closure receives a [`Db`] handle, so a database must be configured.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .database("sqlite://blog.db?mode=rwc")
+    ///     .sitemap_with("https://example.com", |db: Db| async move {
+    ///         let mut sitemap = Sitemap::new();
+    ///         sitemap.add_all(db.all::<Post>().await?, |post| SHOW_POST(post.id));
+    ///         Ok(sitemap)
+    ///     })
+    /// ```
+    pub fn sitemap_with<F, Fut>(mut self, base_url: &str, dynamic: F) -> Self
+    where
+        F: Fn(Db) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = acacia_core::Result<Sitemap>> + Send + 'static,
+    {
+        self.sitemap = Some(SitemapConfig::new(base_url).with_dynamic(dynamic));
+        self
+    }
+
+    /// Run with the dev or prod profile instead of the one from [`Profile::detect`].
+    ///
+    /// In prod, pages link the [`Acacia::stylesheet`] instead of the Tailwind
+    /// CDN, are minified, and get a `Csp::new().default_src_self()` policy,
+    /// which still allows inline `style` attributes, unless [`Acacia::security`]
+    /// is set. In debug builds outside prod, server
+    /// errors show a detailed page with their causes, backtrace, route and request.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().profile(Profile::Prod)
+    /// ```
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Link pages to a stylesheet, such as the compiled Tailwind CSS.
+    ///
+    /// With the `tailwind` feature, dev pages use the Tailwind CDN instead, so
+    /// classes work without a build step.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .assets(Assets::dir("static"))
+    ///     .stylesheet("/static/app.css")
+    /// ```
+    pub fn stylesheet(mut self, href: &str) -> Self {
+        self.stylesheet = Some(href.to_string());
+        self
+    }
+
+    /// Sign `live(topic)` subscriptions with `secret` instead of a random key.
+    ///
+    /// Needed when several instances serve the app, so a page rendered by one
+    /// can subscribe through another, and to keep subscriptions valid across restarts.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().live_secret(&std::env::var("LIVE_SECRET")?)
+    /// ```
+    pub fn live_secret(mut self, secret: &str) -> Self {
+        self.live_secret = Some(secret.to_string());
+        self
+    }
+
+    /// Choose where pages load htmx from.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().htmx(Htmx::Version("2.0.3".into()))
+    /// Acacia::new().htmx(Htmx::File("vendor/htmx.js".into()))
+    /// Acacia::new().htmx(Htmx::None)
+    /// ```
+    pub fn htmx(mut self, htmx: Htmx) -> Self {
+        self.htmx = htmx;
+        self
+    }
+
+    /// Give every visitor a cookie session, extracted in handlers as `Session`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().sessions(SessionConfig::default())
+    ///
+    /// Acacia::new()
+    ///     .database("sqlite://app.db?mode=rwc")
+    ///     .sessions(SessionConfig {
+    ///         store: Arc::new(DbStore::new()),
+    ///         ..SessionConfig::default()
+    ///     })
+    /// ```
+    pub fn sessions(mut self, config: SessionConfig) -> Self {
+        self.sessions = Some(config);
+        self
+    }
+
+    /// Let users sign in with an OAuth2 provider at `/auth/{name}`.
+    ///
+    /// After the provider redirects back, the [`oauth_login`](Self::oauth_login)
+    /// handler runs with the verified `OAuthProfile`. Sessions are enabled if needed.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .oauth(Provider::github(&env("GITHUB_ID")?, &env("GITHUB_SECRET")?))
+    ///     .oauth_login(login)
+    /// ```
+    #[cfg(feature = "oauth")]
+    pub fn oauth(mut self, provider: acacia_auth::Provider) -> Self {
+        self.oauth.add(provider);
+        self
+    }
+
+    /// Handle a successful OAuth login, e.g. by finding or creating the user.
+    ///
+    /// Without one, the profile is stored in the session and the user is sent to `/`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// async fn login(profile: OAuthProfile, session: Session, db: Db) -> Result<Redirect> {
+    ///     let user = db.find_or_create_user(&profile).await?;
+    ///     session.insert("user_id", &user.id)?;
+    ///     Ok(Redirect::to("/dashboard"))
+    /// }
+    /// ```
+    #[cfg(feature = "oauth")]
+    pub fn oauth_login<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        self.oauth.login(get(handler));
+        self
+    }
+
+    /// Let users sign in with a link emailed to them, sent with the configured mailer.
+    ///
+    /// Needs a database for the link tokens; sessions are enabled if needed.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .database("sqlite://app.db?mode=rwc")
+    ///     .mailer(Mailer::mailhog("noreply@example.com"))
+    ///     .magic_link(MagicLink::new("https://example.com").login(login))
+    /// ```
+    #[cfg(feature = "magic-link")]
+    pub fn magic_link(mut self, magic_link: acacia_auth::MagicLink) -> Self {
+        self.magic_link = Some(magic_link);
+        self
+    }
+
+    /// Serve static files, from disk or embedded in the binary.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().assets(Assets::dir("static"))
+    /// ```
+    pub fn assets(mut self, assets: Assets) -> Self {
+        self.assets = Some(assets);
+        self
+    }
+
+    /// Add security headers, and optionally a Content-Security-Policy, to every response.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().security(Security::default().csp(Csp::new().script_src_self()))
+    /// ```
+    pub fn security(mut self, security: Security) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Cancel route handlers that take longer than `timeout`, responding with a 504 error.
+    ///
+    /// Long-running routes can set their own limit with `timeout = ...`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().timeout(Duration::from_secs(30))
+    ///
+    /// #[action("/reports", method = "POST", timeout = Duration::from_secs(300))]
+    /// async fn generate_report(db: Db) -> Result<Fragment> { ... }
+    /// ```
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Log every request to stdout, in Common Log Format or JSON.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().access_log(AccessLog::common().skip("/healthz").skip_assets())
+    /// ```
+    pub fn access_log(mut self, access_log: AccessLog) -> Self {
+        self.access_log = Some(access_log);
+        self
+    }
+
+    /// Call `hook` for every 5xx response with the error and the request,
+    /// e.g. to forward errors to Sentry or a webhook.
+    ///
+    /// The hook runs on the request's task, so slow work should be spawned.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().on_error(|err, req| {
+    ///     let line = format!("{} {} failed: {}", req.method, req.uri, err.message);
+    ///     tokio::spawn(async move { notify_webhook(line).await });
+    /// })
+    /// ```
+    pub fn on_error<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ErrorReport, &RequestInfo) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(hook));
+        self
+    }
+
+    /// Fill in the [`RequestContext`] components read with `ctx()`, once per
+    /// request before its handler runs.
+    ///
+    /// The hook sees the request's extensions, including the `Session`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .sessions(SessionConfig::new())
+    ///     .context(|req, ctx| {
+    ///         if let Some(session) = req.extensions.get::<Session>() {
+    ///             ctx.user = session.get("username");
+    ///         }
+    ///     })
+    /// ```
+    pub fn context<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&axum::http::request::Parts, &mut RequestContext) + Send + Sync + 'static,
+    {
+        self.context = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set HTTP protocol and connection options, such as enabling HTTP/2.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().http(Http::new().http2().max_concurrent_streams(500))
+    /// ```
+    pub fn http(mut self, http: Http) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Set the number of worker threads of the runtime started by [`Acacia::run`].
+    ///
+    /// Defaults to one per CPU core. Apps using `#[tokio::main]` or
+    /// [`Acacia::serve_on`] run on the workers of their own runtime instead.
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn main() {
+    ///     if let Err(err) = Acacia::new().workers(4).run("0.0.0.0:3000") {
+    ///         eprintln!("{}", err);
+    ///     }
+    /// }
+    /// ```
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Serve HTTPS with a certificate and private key.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().tls(Tls::from_pem_files("cert.pem", "key.pem")?)
+    /// ```
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, tls: Tls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Choose how paths ending in `/` are handled (matched exactly by default).
+    ///
+    /// # Example
+    /// ```ignore
+    /// // `/tasks/` redirects to `/tasks`
+    /// Acacia::new().trailing_slash(TrailingSlash::Redirect)
+    /// ```
+    pub fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+        self.paths.trailing_slash = trailing_slash;
+        self
+    }
+
+    /// Redirect paths with uppercase letters to their lowercase form.
+    ///
+    /// Applies to the whole path, so leave it off when path parameters are
+    /// case-sensitive, such as tokens.
+    pub fn lowercase_paths(mut self) -> Self {
+        self.paths.lowercase = true;
+        self
+    }
+
+    /// Render unmatched URLs with `handler` instead of an empty 404.
+    ///
+    /// The response status is always 404, whatever the handler returns.
+    ///
+    /// # Example
+    /// ```ignore
+    /// async fn not_found() -> Page {
+    ///     html! { <main><h1>Page not found</h1></main> }.into_page()
+    /// }
+    ///
+    /// Acacia::new().not_found(not_found)
+    /// ```
+    pub fn not_found<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        self.not_found = Some(any(handler).layer(axum::middleware::map_response(
+            |mut response: Response| async move {
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                response
+            },
+        )));
+        self
+    }
+
+    /// Serve the routes of an existing axum router alongside the generated ones.
+    ///
+    /// The routes share the app state and middleware, so they can extract `Db`
+    /// and `Session`. A route that is also defined with `#[page]` or `#[action]`
+    /// panics at startup.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let legacy = Router::new().route("/api/health", get(|| async { "ok" }));
+    ///
+    /// Acacia::new().merge(legacy)
+    /// ```
+    pub fn merge(mut self, router: Router<AppState>) -> Self {
+        self.routes = self.routes.merge(router);
+        self
+    }
+
+    /// Serve an existing axum router under `path`, like [`Acacia::merge`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().nest("/grpc", grpc_web_router())
+    /// ```
+    pub fn nest(mut self, path: &str, router: Router<AppState>) -> Self {
+        let path = format!("/{}", path.trim_matches('/'));
+        self.routes = self.routes.nest(&path, router);
+        self
+    }
+
+    /// Serve an [`AcaciaModule`] under `path`, with its endpoints linking there.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .mount("/blog", blog::module())
+    ///     .mount("/account", accounts::module())
+    /// ```
+    pub fn mount(mut self, path: &str, module: AcaciaModule) -> Self {
+        match path.trim_matches('/') {
+            // Mounted at the root, the routes keep their own paths
+            "" => self.routes = self.routes.merge(module.routes),
+            path => self.mounts.push((format!("/{}", path), module)),
+        }
+        self
+    }
+
+    /// Wrap every route in a tower layer, such as one from `tower-http`.
+    ///
+    /// Like `Router::layer`, each layer wraps the routes and everything added
+    /// before it, including the framework's own middleware, so it sees
+    /// responses with security headers already set.
+    ///
+    /// # Example
+    /// ```ignore
+    /// use tower_http::compression::CompressionLayer;
+    ///
+    /// Acacia::new()
+    ///     .layer(CompressionLayer::new())
+    ///     .layer(sentry_tower::NewSentryLayer::new_from_top())
+    /// ```
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        self.layers
+            .push(Box::new(move |router| router.layer(layer)));
+        self
+    }
+
+    /// Register a service that handlers can extract with `Inject<T>`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .state(Services { mailer, cache })
+    ///     .state(reqwest::Client::new())
+    /// ```
+    pub fn state<T: Send + Sync + 'static>(mut self, service: T) -> Self {
+        self.state.insert(service);
+        self
+    }
+
+    /// Set the mailer handlers extract with a `Mailer` argument.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().mailer(if cfg!(debug_assertions) {
+    ///     Mailer::log("noreply@example.com")
+    /// } else {
+    ///     Mailer::smtp(&std::env::var("SMTP_URL")?, "noreply@example.com")?
+    /// })
+    /// ```
+    pub fn mailer(self, mailer: acacia_core::Mailer) -> Self {
+        self.state(mailer)
+    }
+
+    /// Render error responses with `renderer` instead of the built-in alert.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .error_renderer(|err| html! { <p class="alert">{err.message()}</p> })
+    ///     .status_renderer(StatusCode::NOT_FOUND, |_| NotFound())
+    /// ```
+    pub fn error_renderer(
+        self,
+        renderer: impl Fn(&acacia_core::AppError) -> acacia_core::Fragment + Send + Sync + 'static,
+    ) -> Self {
+        acacia_core::set_error_renderer(renderer);
+        self
+    }
+
+    /// Render errors with one status with `renderer`, overriding [`error_renderer`](Self::error_renderer).
+    pub fn status_renderer(
+        self,
+        status: http::StatusCode,
+        renderer: impl Fn(&acacia_core::AppError) -> acacia_core::Fragment + Send + Sync + 'static,
+    ) -> Self {
+        acacia_core::set_status_renderer(status, renderer);
+        self
+    }
+
+    /// Swap errors from htmx requests into the element matching `selector`.
+    ///
+    /// By default failed htmx requests leave the page untouched.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().error_target("#errors")
+    /// ```
+    pub fn error_target(self, selector: &str) -> Self {
+        acacia_core::set_error_target(selector);
+        self
+    }
+
+    /// Mount list, create, edit and delete pages for every model at `path`.
+    ///
+    /// Without an [`admin_guard`](Self::admin_guard) the pages are only
+    /// served in debug builds.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .database("sqlite://todos.db?mode=rwc")
+    ///     .admin("/admin")
+    /// ```
+    pub fn admin(mut self, path: &str) -> Self {
+        let admin = AdminConfig::new(path);
+        self.admin = Some(match self.admin.take() {
+            Some(existing) => existing.at(admin.path()),
+            None => admin,
+        });
+        self
+    }
+
+    /// Decide which requests may use the admin pages; refused requests get 403.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new()
+    ///     .admin("/admin")
+    ///     .admin_guard(|parts| parts.headers.get("x-admin-token").is_some_and(|t| t == TOKEN))
+    /// ```
+    pub fn admin_guard(
+        mut self,
+        guard: impl Fn(&axum::http::request::Parts) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let admin = self
+            .admin
+            .take()
+            .unwrap_or_else(|| AdminConfig::new("/admin"));
+        self.admin = Some(admin.with_guard(guard));
+        self
+    }
+
+    /// Start serving the application, until the server fails.
+    ///
+    /// # Example
+    /// ```ignore
+    /// match Acacia::new().serve("0.0.0.0:3000").await {
+    ///     Err(AcaciaError::Bind { addr, .. }) => eprintln!("{} is already in use", addr),
+    ///     Err(err) => eprintln!("{}", err),
+    ///     Ok(()) => {}
+    /// }
+    /// ```
+    pub async fn serve(self, addr: &str) -> Result<(), AcaciaError> {
+        self.bind(addr).await?.run().await;
+        Ok(())
+    }
+
+    /// Start a Tokio runtime with [`Acacia::workers`] threads and serve on it,
+    /// blocking until the server fails. For a plain `fn main`, without `#[tokio::main]`.
+    pub fn run(self, addr: &str) -> Result<(), AcaciaError> {
+        let mut runtime = tokio::runtime::Builder::new_multi_thread();
+        if let Some(workers) = self.workers {
+            runtime.worker_threads(workers);
+        }
+        let runtime = runtime.enable_all().build().map_err(AcaciaError::Runtime)?;
+        runtime.block_on(self.serve(addr))
+    }
+
+    /// Serve on an existing runtime, such as one shared with other services.
+    ///
+    /// Returns the task serving the app; it ends only if the server fails.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let runtime = tokio::runtime::Builder::new_multi_thread()
+    ///     .worker_threads(2)
+    ///     .enable_all()
+    ///     .build()?;
+    /// let server = Acacia::new().serve_on(runtime.handle(), "0.0.0.0:3000");
+    /// if let Ok(Err(err)) = runtime.block_on(server) {
+    ///     eprintln!("{}", err);
+    /// }
+    /// ```
+    pub fn serve_on(
+        self,
+        handle: &tokio::runtime::Handle,
+        addr: &str,
+    ) -> tokio::task::JoinHandle<Result<(), AcaciaError>> {
+        let addr = addr.to_string();
+        handle.spawn(async move { self.serve(&addr).await })
+    }
+
+    /// Build the application and bind `addr`, without accepting connections yet.
+    ///
+    /// Bind port 0 to let the OS pick a free port, then read it back with
+    /// [`Server::local_addr`]; tests can run many servers side by side this way.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let server = Acacia::new().bind("127.0.0.1:0").await?;
+    /// let url = format!("http://{}", server.local_addr());
+    /// tokio::spawn(server.run());
+    ///
+    /// let body = reqwest::get(&url).await?.text().await?;
+    /// ```
+    pub async fn bind(self, addr: &str) -> Result<Server, AcaciaError> {
+        let http = self.http.clone();
+        #[cfg(feature = "tls")]
+        let transport = match &self.tls {
+            Some(tls) => listen::Transport::Tls(tls.acceptor(http.http2)),
+            None => listen::Transport::Plain,
+        };
+        #[cfg(not(feature = "tls"))]
+        let transport = listen::Transport::Plain;
+        let app = self.app().await?;
+
+        // Parse address and serve
+        let socket_addr: SocketAddr = addr.parse().map_err(|source| AcaciaError::Address {
+            addr: addr.to_string(),
+            source,
+        })?;
+        let listener = tokio::net::TcpListener::bind(socket_addr)
+            .await
+            .map_err(|source| AcaciaError::Bind {
+                addr: socket_addr,
+                source,
+            })?;
+        let local_addr = listener.local_addr().map_err(|source| AcaciaError::Bind {
+            addr: socket_addr,
+            source,
+        })?;
+        Ok(Server {
+            listener,
+            local_addr,
+            app,
+            http,
+            transport,
+        })
+    }
+
+    /// Serve the application on a Unix domain socket, for running behind a
+    /// reverse proxy on the same machine.
+    ///
+    /// A socket left over from a previous run is replaced.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Acacia::new().serve_unix("/run/app.sock").await?;
+    /// ```
+    #[cfg(unix)]
+    pub async fn serve_unix(self, path: impl AsRef<std::path::Path>) -> Result<(), AcaciaError> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let http = self.http.clone();
+        let app = self.app().await?;
+        let path = path.as_ref();
+        let bind_error = |source| AcaciaError::BindUnix {
+            path: path.to_path_buf(),
+            source,
+        };
+        // Only remove sockets, never a regular file at a mistyped path
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if metadata.file_type().is_socket() {
+                std::fs::remove_file(path).map_err(bind_error)?;
+            }
+        }
+        let listener = tokio::net::UnixListener::bind(path).map_err(bind_error)?;
+        println!("🌿 Acacia server running at unix:{}", path.display());
+
+        listen::serve_unix(listener, app, http).await;
+        Ok(())
+    }
+
+    /// Connect the database, run migrations and assemble the routes, returning
+    /// the app as an axum `Router` instead of serving it.
+    ///
+    /// For running under another server or runtime, such as a custom hyper
+    /// setup or a serverless adapter. The app handles every request, including
+    /// its own 404s, so use it as the fallback when adding routes around it.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let app = Acacia::new().database("sqlite://app.db?mode=rwc").into_router().await?;
+    ///
+    /// let router = Router::new()
+    ///     .route("/metrics", get(metrics))
+    ///     .fallback_service(app);
+    /// axum::serve(listener, router).await?;
+    /// ```
+    pub async fn into_router(self) -> Result<Router, AcaciaError> {
+        Ok(Router::new().fallback_service(self.app().await?))
+    }
+
+    /// Build the application service: connect the database, run migrations
+    /// and collect the routes.
+    async fn app(self) -> Result<listen::App, AcaciaError> {
         // Connect to database if configured
-        let db_conn = if let Some(url) = &self.database_url {
-            let conn = Database::connect(url)
-                .await
-                .expect("Failed to connect to database");
-
-            // Run migrations if auto
-            if matches!(self.migrate_policy, MigratePolicy::Auto) {
-                let db = Db::new(conn.clone());
-                db.migrate().await.expect("Failed to run migrations");
+        let mut replicas = Vec::new();
+        let db_conn = if let Some(config) = &self.database {
+            let conn = Database::connect(config.options(&config.primary)).await?;
+            for url in &config.replicas {
+                replicas.push(Database::connect(config.options(url)).await?);
+            }
+
+            let db = Db::new(conn.clone());
+            match self.migrate_policy {
+                MigratePolicy::Auto => db.migrate().await?,
+                MigratePolicy::Fresh => db.migrate_fresh().await?,
+                MigratePolicy::Check => db.check_schema().await?,
+                MigratePolicy::None => {}
             }
 
             Some(conn)
@@ -63,36 +860,221 @@ impl Acacia {
         let mut router = Router::new();
 
         // Add HTMX serving route
-        router = router.route("/__acacia__/htmx.min.js", get(serve_htmx));
+        let htmx_js = match &self.htmx {
+            Htmx::File(path) => {
+                std::fs::read_to_string(path).map_err(|source| AcaciaError::Htmx {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+            _ => HTMX_JS.to_string(),
+        };
+        router = router.route(
+            "/__acacia__/htmx.min.js",
+            get(move || serve_htmx(htmx_js.clone())),
+        );
+        // Production pages get a strict policy unless the app sets its own
+        let security = match self.security {
+            None if self.profile.is_prod() => {
+                // `style` attributes stay allowed: the built-in error fragment and
+                // many templates use them, and unlike scripts they can't run code
+                let csp = Csp::new()
+                    .default_src_self()
+                    .directive("style-src-attr", "'unsafe-inline'");
+                // The morph and preload extensions load from jsDelivr
+                let csp = match &self.htmx {
+                    Htmx::None => csp,
+                    Htmx::Url(url) => csp
+                        .script_src_self()
+                        .script_src("https://cdn.jsdelivr.net")
+                        .script_src(url),
+                    _ => csp.script_src_self().script_src("https://cdn.jsdelivr.net"),
+                };
+                Some(Security::default().csp(csp))
+            }
+            security => security,
+        };
+        acacia_core::set_htmx(self.htmx);
+        if self.profile.is_prod() && self.stylesheet.is_none() {
+            eprintln!(
+                "⚠️  Running in the Prod profile without Acacia::stylesheet, so pages load no styles"
+            );
+        }
+        acacia_core::set_profile(self.profile);
+        acacia_core::set_stylesheet(self.stylesheet);
+        if let Some(secret) = &self.live_secret {
+            acacia_core::broadcast::set_secret(secret.as_bytes());
+        }
+        router = router.route("/__acacia__/errors.js", get(serve_errors));
+        router = router.route("/__acacia__/live.js", get(serve_live));
+        router = router.route("/__acacia__/live/:topic", get(live_events));
+
+        if let Some(sitemap) = self.sitemap {
+            router = router.route("/sitemap.xml", get(sitemap.handler()));
+        }
+
+        if let Some(admin) = self.admin {
+            let path = admin.path().to_string();
+            router = router.nest(&path, admin.router());
+        }
+
+        let mut access_log = self.access_log;
+        if let Some(assets) = self.assets {
+            if let Some(log) = access_log.as_mut().filter(|log| log.skip_assets) {
+                log.skip.push(format!("{}/*", assets.prefix()));
+            }
+            router = router.merge(assets.router());
+        }
+
+        router = router.merge(self.routes);
+
+        acacia_core::set_mounts(
+            self.mounts
+                .iter()
+                .map(|(path, module)| (module.module, path.clone()))
+                .collect(),
+        );
+        for (path, module) in self.mounts {
+            router = router.nest(&path, module.routes);
+        }
 
         // Add all registered routes
+        let mut allowed: Vec<(String, Vec<acacia_core::Method>)> = Vec::new();
         for route_def in inventory::iter::<RouteDefinition> {
-            let handler = (route_def.handler)();
-            // Convert Acacia path format {param} to Axum format :param
-            let axum_path = route_def.path.replace('{', ":").replace('}', "");
-            router = router.route(&axum_path, handler);
+            let mut handler = (route_def.handler)();
+            if let Some(timeout) = route_def.timeout.or(self.timeout) {
+                handler = handler.layer(axum::middleware::from_fn(move |req, next| {
+                    time_limit(timeout, req, next)
+                }));
+            }
+            let path = axum_path(&route_def.url());
+            router = router.route(&path, handler);
+            match allowed.iter_mut().find(|(existing, _)| *existing == path) {
+                Some((_, methods)) => methods.extend(route_def.methods()),
+                None => allowed.push((path, route_def.methods().collect())),
+            }
+        }
+        // OPTIONS lists what each path serves, across the routes sharing it
+        for (path, methods) in allowed {
+            let allow = allow_header(&methods);
+            router = router.route(
+                &path,
+                axum::routing::options(move || async move {
+                    (StatusCode::NO_CONTENT, [(axum::http::header::ALLOW, allow)])
+                }),
+            );
+        }
+
+        if let Some(not_found) = self.not_found {
+            router = router.fallback(not_found);
+        }
+
+        // Sign-in flows keep their state in the session
+        #[allow(unused_mut)]
+        let mut sessions = self.sessions;
+        #[cfg(feature = "oauth")]
+        if !self.oauth.is_empty() {
+            router = router.merge(self.oauth.router());
+            sessions = Some(sessions.unwrap_or_default());
+        }
+        #[cfg(feature = "magic-link")]
+        if let Some(magic_link) = self.magic_link {
+            router = router.merge(magic_link.router());
+            sessions = Some(sessions.unwrap_or_default());
+        }
+
+        // Inside the session layer, so the context hook can read the session
+        let context = self.context;
+        router = router.layer(axum::middleware::from_fn(move |req, next| {
+            with_request_context(context.clone(), req, next)
+        }));
+
+        if let Some(sessions) = sessions {
+            sessions.store.init(db_conn.as_ref()).await?;
+            router = router.layer(axum::middleware::from_fn(move |req, next| {
+                sessions.clone().middleware(req, next)
+            }));
         }
 
         // Create app state
-        let state = if let Some(conn) = db_conn {
-            AppState::with_db(conn)
-        } else {
-            AppState::new()
-        };
+        let mut state = self.state;
+        state.db = db_conn;
+        state.replicas = replicas;
 
+        // Report server errors, and in debug builds outside Prod show them with the request
+        let reporting = Arc::new(Reporting {
+            hook: self.on_error,
+            detailed: self.profile.shows_error_details(),
+        });
+        if reporting.hook.is_some() || reporting.detailed {
+            router = router.layer(axum::middleware::from_fn(move |req, next| {
+                report::report(reporting.clone(), req, next)
+            }));
+        }
+        // Pick fragment or full page bodies based on request headers
+        router = router.layer(axum::middleware::from_fn(acacia_core::negotiate));
+        if let Some(security) = security {
+            router = router.layer(axum::middleware::from_fn(move |req, next| {
+                security::secure(security.clone(), req, next)
+            }));
+        }
+        for layer in self.layers {
+            router = layer(router);
+        }
         let app = router.with_state(state);
 
-        // Parse address and serve
-        let socket_addr: SocketAddr = addr.parse().expect("Invalid address");
-        println!("🌿 Acacia server running at http://{}", socket_addr);
+        // Rewrite the method before routing, so method dispatch sees it
+        let app = axum::middleware::from_fn(acacia_core::method_override).layer(app);
+        let paths = self.paths;
+        let app = if paths.enabled() {
+            let normalize =
+                axum::middleware::from_fn(move |req, next| normalize::normalize(paths, req, next));
+            tower::util::BoxCloneService::new(normalize.layer(app))
+        } else {
+            tower::util::BoxCloneService::new(app)
+        };
 
-        let listener = tokio::net::TcpListener::bind(socket_addr)
-            .await
-            .expect("Failed to bind address");
+        // Inside the access log, so turned away requests are logged
+        let app = match self.http.max_requests {
+            Some(max) => {
+                let permits = std::sync::Arc::new(tokio::sync::Semaphore::new(max));
+                let app = axum::middleware::from_fn(move |req, next| {
+                    shed_load(permits.clone(), req, next)
+                })
+                .layer(app);
+                tower::util::BoxCloneService::new(app)
+            }
+            None => app,
+        };
 
-        axum::serve(listener, app)
-            .await
-            .expect("Server error");
+        // Outermost, so redirects and early rejections are logged too
+        if let Some(mut log) = access_log {
+            if log.skip_assets {
+                log.skip.push("/__acacia__/*".to_string());
+            }
+            let log = std::sync::Arc::new(log);
+            let app =
+                axum::middleware::from_fn(move |req, next| access_log::log(log.clone(), req, next))
+                    .layer(app);
+            return Ok(tower::util::BoxCloneService::new(app));
+        }
+        Ok(app)
+    }
+
+    /// Like [`Acacia::serve`], printing the error and exiting if the server fails.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     Acacia::new().serve_or_exit("0.0.0.0:3000").await;
+    /// }
+    /// ```
+    pub async fn serve_or_exit(self, addr: &str) {
+        if let Err(err) = self.serve(addr).await {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
     }
 }
 
@@ -102,10 +1084,112 @@ impl Default for Acacia {
     }
 }
 
+/// Run the rest of the request with its `RequestContext` in scope.
+async fn with_request_context(
+    hook: Option<Arc<ContextHook>>,
+    req: Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let (parts, body) = req.into_parts();
+    let mut context = RequestContext::from_headers(&parts.headers);
+    if let Some(hook) = &hook {
+        hook(&parts, &mut context);
+    }
+    acacia_core::with_context(context, next.run(Request::from_parts(parts, body))).await
+}
+
+/// Convert Acacia's `{param}` and `{*rest}` to axum's `:param` and `*rest`.
+fn axum_path(path: &str) -> String {
+    path.replace("{*", "*").replace('{', ":").replace('}', "")
+}
+
+/// The `Allow` header for a path serving `methods`, with the HEAD and OPTIONS
+/// answered for them.
+fn allow_header(methods: &[acacia_core::Method]) -> String {
+    let mut allow: Vec<String> = Vec::new();
+    for method in methods {
+        allow.push(method.to_string());
+        if *method == acacia_core::Method::Get {
+            allow.push("HEAD".to_string());
+        }
+    }
+    allow.push("OPTIONS".to_string());
+    allow.dedup();
+    allow.join(", ")
+}
+
+/// End a handler that runs past `timeout` with a 504 error.
+async fn time_limit(
+    timeout: Duration,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    match tokio::time::timeout(timeout, next.run(req)).await {
+        Ok(response) => response,
+        Err(_) => acacia_core::AppError::custom(
+            StatusCode::GATEWAY_TIMEOUT,
+            "The request took too long to complete",
+        )
+        .into_response(),
+    }
+}
+
+/// Answer 503 when `Http::max_requests` requests are already being handled.
+async fn shed_load(
+    permits: std::sync::Arc<tokio::sync::Semaphore>,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let Ok(_permit) = permits.try_acquire() else {
+        let mut response = acacia_core::AppError::custom(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The server is busy, please try again in a moment",
+        )
+        .into_response();
+        response.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from_static("1"),
+        );
+        return response;
+    };
+    next.run(req).await
+}
+
+/// Serve the handler for retargeted error responses.
+async fn serve_errors() -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, "application/javascript")],
+        ERRORS_JS,
+    )
+}
+
+/// Serve the client for live elements.
+async fn serve_live() -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, "application/javascript")],
+        LIVE_JS,
+    )
+}
+
+/// Stream fragments broadcast to a topic as server-sent events.
+async fn live_events(
+    axum::extract::Path(topic): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, acacia_core::AppError> {
+    // Only topics a page rendered with `live` can be subscribed to, via the
+    // signature it adds to the stream URL
+    let sig = query.get("sig").map(String::as_str).unwrap_or_default();
+    if !acacia_core::broadcast::verify(&topic, sig) {
+        return Err(acacia_core::AppError::Forbidden);
+    }
+    let stream = acacia_core::subscribe(&topic).map(|html| Ok(Event::default().data(html)));
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 /// Serve the HTMX library.
-async fn serve_htmx() -> impl IntoResponse {
+async fn serve_htmx(js: String) -> impl IntoResponse {
     (
         [(axum::http::header::CONTENT_TYPE, "application/javascript")],
-        HTMX_JS,
+        js,
     )
 }
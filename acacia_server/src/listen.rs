@@ -0,0 +1,310 @@
+//! Accepting connections and serving HTTP on them.
+
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request};
+use axum::response::Response;
+use hyper::server::conn::http1;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::service::TowerToHyperService;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::util::BoxCloneService;
+use tower::ServiceExt;
+
+/// The finished application, as built by `Acacia::app`.
+pub(crate) type App = BoxCloneService<Request, Response, Infallible>;
+
+/// HTTP protocol and connection options, set with `Acacia::http`.
+///
+/// The defaults serve HTTP/1.1 with persistent connections, like a plain
+/// `axum::serve`. Pages that poll or hold many live elements open benefit
+/// from HTTP/2, which multiplexes their requests over one connection.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new().http(
+///     Http::new()
+///         .http2()
+///         .max_concurrent_streams(500)
+///         .ping_interval(Duration::from_secs(30)),
+/// )
+/// ```
+#[derive(Clone, Debug)]
+pub struct Http {
+    pub(crate) http2: bool,
+    keep_alive: bool,
+    header_timeout: Option<Duration>,
+    ping_interval: Option<Duration>,
+    max_concurrent_streams: Option<u32>,
+    max_connections: Option<usize>,
+    pub(crate) max_requests: Option<usize>,
+    #[cfg(feature = "tls")]
+    tls_handshake_timeout: Duration,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Http {
+    pub fn new() -> Self {
+        Self {
+            http2: false,
+            keep_alive: true,
+            header_timeout: None,
+            ping_interval: None,
+            max_concurrent_streams: None,
+            max_connections: None,
+            max_requests: None,
+            #[cfg(feature = "tls")]
+            tls_handshake_timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Accept HTTP/2 as well: negotiated with TLS, or with prior knowledge without it.
+    pub fn http2(mut self) -> Self {
+        self.http2 = true;
+        self
+    }
+
+    /// Whether HTTP/1.1 connections stay open for further requests (on by default).
+    pub fn keep_alive(mut self, keep_alive: bool) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Close HTTP/1 connections that don't send their request headers within
+    /// `timeout` (30 seconds by default).
+    pub fn header_timeout(mut self, timeout: Duration) -> Self {
+        self.header_timeout = Some(timeout);
+        self
+    }
+
+    /// Ping idle HTTP/2 connections every `interval`, closing those that
+    /// stop answering.
+    pub fn ping_interval(mut self, interval: Duration) -> Self {
+        self.ping_interval = Some(interval);
+        self
+    }
+
+    /// The most requests a client may have in flight on one HTTP/2 connection.
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// The most connections open at once. Further clients wait to be
+    /// accepted until one closes.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = Some(max);
+        self
+    }
+
+    /// The most requests handled at once. Requests beyond it are turned away
+    /// with `503 Service Unavailable` and `Retry-After: 1`, so a burst of
+    /// polling can't pile up on the database.
+    pub fn max_requests(mut self, max: usize) -> Self {
+        self.max_requests = Some(max);
+        self
+    }
+
+    /// Close TLS connections that don't finish their handshake within
+    /// `timeout` (10 seconds by default), freeing their connection slot.
+    #[cfg(feature = "tls")]
+    pub fn tls_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.tls_handshake_timeout = timeout;
+        self
+    }
+
+    /// Waits for a free connection slot, if connections are limited.
+    fn connection_limit(&self) -> ConnectionLimit {
+        ConnectionLimit(
+            self.max_connections
+                .map(|max| std::sync::Arc::new(Semaphore::new(max))),
+        )
+    }
+
+    fn connections(&self) -> Connections {
+        if !self.http2 {
+            // The auto-detecting builder ignores `http1_only` on upgradable connections
+            let mut http1 = http1::Builder::new();
+            http1.timer(TokioTimer::new()).keep_alive(self.keep_alive);
+            if let Some(timeout) = self.header_timeout {
+                http1.header_read_timeout(timeout);
+            }
+            return Connections::Http1(http1);
+        }
+        let mut builder = Builder::new(TokioExecutor::new());
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .keep_alive(self.keep_alive);
+        if let Some(timeout) = self.header_timeout {
+            builder.http1().header_read_timeout(timeout);
+        }
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .keep_alive_interval(self.ping_interval)
+            .max_concurrent_streams(self.max_concurrent_streams);
+        Connections::Auto(builder)
+    }
+}
+
+/// Permits for open connections, from `Http::max_connections`.
+struct ConnectionLimit(Option<std::sync::Arc<Semaphore>>);
+
+impl ConnectionLimit {
+    /// Wait for a free slot, held until the returned permit is dropped.
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.0 {
+            // The semaphore is never closed
+            Some(limit) => limit.clone().acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+}
+
+/// The configured connection builder, shared by every connection.
+enum Connections {
+    Http1(http1::Builder),
+    Auto(Builder<TokioExecutor>),
+}
+
+impl Connections {
+    /// Serve requests on one connection until the client closes it.
+    ///
+    /// Requests from TCP clients carry their address as `ConnectInfo<SocketAddr>`.
+    async fn serve<I>(&self, io: I, peer: Option<SocketAddr>, app: App)
+    where
+        I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let service = TowerToHyperService::new(app.map_request(
+            move |req: Request<hyper::body::Incoming>| {
+                let mut req = req.map(Body::new);
+                if let Some(peer) = peer {
+                    req.extensions_mut().insert(ConnectInfo(peer));
+                }
+                req
+            },
+        ));
+        let io = TokioIo::new(io);
+        // Clients dropping the connection aren't an error worth reporting
+        let _ = match self {
+            Connections::Http1(http1) => http1
+                .serve_connection(io, service)
+                .with_upgrades()
+                .await
+                .map_err(Into::into),
+            Connections::Auto(builder) => builder.serve_connection_with_upgrades(io, service).await,
+        };
+    }
+}
+
+/// How accepted TCP connections are secured.
+pub(crate) enum Transport {
+    Plain,
+    #[cfg(feature = "tls")]
+    Tls(tokio_rustls::TlsAcceptor),
+}
+
+/// A server bound to its address, from [`Acacia::bind`](crate::Acacia::bind).
+pub struct Server {
+    pub(crate) listener: TcpListener,
+    pub(crate) local_addr: SocketAddr,
+    pub(crate) app: App,
+    pub(crate) http: Http,
+    pub(crate) transport: Transport,
+}
+
+impl Server {
+    /// The address the server is listening on, with the port the OS picked for port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Accept connections until the task running it is dropped.
+    pub async fn run(self) {
+        let scheme = match self.transport {
+            Transport::Plain => "http",
+            #[cfg(feature = "tls")]
+            Transport::Tls(_) => "https",
+        };
+        println!(
+            "🌿 Acacia server running at {}://{}",
+            scheme, self.local_addr
+        );
+        serve_tcp(self.listener, self.app, self.http, self.transport).await
+    }
+}
+
+async fn serve_tcp(listener: TcpListener, app: App, http: Http, transport: Transport) {
+    let connections = std::sync::Arc::new(http.connections());
+    let limit = http.connection_limit();
+    #[cfg(feature = "tls")]
+    let handshake_timeout = http.tls_handshake_timeout;
+    loop {
+        let permit = limit.acquire().await;
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(_) => {
+                wait_after_accept_error().await;
+                continue;
+            }
+        };
+        let _ = stream.set_nodelay(true);
+        let (app, connections) = (app.clone(), connections.clone());
+        match &transport {
+            Transport::Plain => {
+                tokio::spawn(async move {
+                    connections.serve(stream, Some(peer), app).await;
+                    drop(permit);
+                });
+            }
+            #[cfg(feature = "tls")]
+            Transport::Tls(acceptor) => {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // A failed or stalled handshake only concerns that client
+                    let handshake = acceptor.accept(stream);
+                    if let Ok(Ok(stream)) = tokio::time::timeout(handshake_timeout, handshake).await
+                    {
+                        connections.serve(stream, Some(peer), app).await;
+                    }
+                    drop(permit);
+                });
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+pub(crate) async fn serve_unix(listener: tokio::net::UnixListener, app: App, http: Http) {
+    let connections = std::sync::Arc::new(http.connections());
+    let limit = http.connection_limit();
+    loop {
+        let permit = limit.acquire().await;
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let (app, connections) = (app.clone(), connections.clone());
+                tokio::spawn(async move {
+                    connections.serve(stream, None, app).await;
+                    drop(permit);
+                });
+            }
+            Err(_) => wait_after_accept_error().await,
+        }
+    }
+}
+
+/// Out of file descriptors and the like; wait instead of spinning.
+async fn wait_after_accept_error() {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
@@ -0,0 +1,24 @@
+// Connects elements marked with data-acacia-live to their broadcast topic
+// and swaps in every fragment published to it.
+htmx.onLoad(function (root) {
+    var elements = Array.from(root.querySelectorAll("[data-acacia-live]"));
+    if (root.matches("[data-acacia-live]")) {
+        elements.push(root);
+    }
+    elements.forEach(function (elt) {
+        if (elt.acaciaLive) {
+            return;
+        }
+        var source = new EventSource(elt.getAttribute("data-acacia-live"));
+        elt.acaciaLive = source;
+        source.onmessage = function (event) {
+            if (!document.body.contains(elt)) {
+                source.close();
+                return;
+            }
+            htmx.swap(elt, event.data, {
+                swapStyle: elt.getAttribute("data-acacia-swap") || "beforeend",
+            });
+        };
+    });
+});
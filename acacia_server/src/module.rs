@@ -0,0 +1,45 @@
+//! Reusable sub-applications mounted under a path prefix.
+
+use acacia_core::AppState;
+use axum::Router;
+
+/// The pages, actions and models of a Rust module, mounted with [`Acacia::mount`](crate::Acacia::mount).
+///
+/// Every `#[page]` and `#[action]` defined in the module or its submodules is
+/// served under the mount prefix, and their endpoints link there. Models are
+/// registered as soon as the crate is linked, so their tables are migrated
+/// with the app's.
+///
+/// # Example
+/// ```ignore
+/// // In the blog crate
+/// pub fn module() -> AcaciaModule {
+///     AcaciaModule::new(module_path!())
+/// }
+///
+/// #[page("/")]
+/// async fn posts(db: Db) -> Result<Fragment> { ... }
+///
+/// // In the app; `posts` is served at `/blog`
+/// Acacia::new().mount("/blog", blog::module())
+/// ```
+pub struct AcaciaModule {
+    pub(crate) module: &'static str,
+    pub(crate) routes: Router<AppState>,
+}
+
+impl AcaciaModule {
+    /// A module for the routes defined under `module`, usually `module_path!()`.
+    pub fn new(module: &'static str) -> Self {
+        Self {
+            module,
+            routes: Router::new(),
+        }
+    }
+
+    /// Serve the routes of an axum router under the mount prefix too.
+    pub fn merge(mut self, router: Router<AppState>) -> Self {
+        self.routes = self.routes.merge(router);
+        self
+    }
+}
@@ -0,0 +1,58 @@
+//! Redirects that give every page a single canonical URL.
+
+use axum::{
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+
+/// How requests for a path ending in `/`, like `/tasks/`, are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// Permanently redirect to the path without the slash.
+    Redirect,
+    /// Match paths exactly, so `/tasks/` is a 404 unless registered.
+    #[default]
+    Strict,
+}
+
+/// Path normalization applied before routing.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PathConfig {
+    pub(crate) trailing_slash: TrailingSlash,
+    pub(crate) lowercase: bool,
+}
+
+impl PathConfig {
+    /// Whether any normalization is turned on; paths are left alone by default.
+    pub(crate) fn enabled(self) -> bool {
+        self.trailing_slash == TrailingSlash::Redirect || self.lowercase
+    }
+}
+
+/// Redirect requests whose path is not in canonical form.
+///
+/// Redirects are 308s, so form submissions keep their method and body.
+pub(crate) async fn normalize(config: PathConfig, req: Request, next: Next) -> Response {
+    let path = req.uri().path();
+    let mut canonical = path.to_string();
+    if config.trailing_slash == TrailingSlash::Redirect && canonical.len() > 1 {
+        let trimmed = canonical.trim_end_matches('/');
+        canonical = if trimmed.is_empty() { "/" } else { trimmed }.to_string();
+    }
+    if config.lowercase {
+        canonical = canonical.to_lowercase();
+    }
+    // A leading `//` would redirect to another host
+    if canonical.starts_with("//") {
+        canonical = format!("/{}", canonical.trim_start_matches('/'));
+    }
+
+    if canonical == path {
+        return next.run(req).await;
+    }
+    if let Some(query) = req.uri().query() {
+        canonical = format!("{}?{}", canonical, query);
+    }
+    Redirect::permanent(&canonical).into_response()
+}
@@ -0,0 +1,130 @@
+//! Server errors reported with the request that raised them.
+
+use crate::dev_errors;
+use acacia_core::{ErrorReport, RouteDefinition};
+use axum::body::Body;
+use axum::extract::{MatchedPath, Request};
+use axum::http::{header, HeaderMap, HeaderValue, Method, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+/// Called by `Acacia::on_error` for every server error.
+pub(crate) type ErrorHook = dyn Fn(&ErrorReport, &RequestInfo) + Send + Sync;
+
+/// Shown in place of headers and form fields that look like credentials.
+const REDACTED: &str = "[redacted]";
+
+/// The request a server error was raised for, passed to `Acacia::on_error`.
+///
+/// Cookies, the `Authorization` header and form fields named like
+/// passwords, secrets or tokens are replaced with `[redacted]`.
+#[derive(Clone, Debug)]
+pub struct RequestInfo {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    /// The route as registered, e.g. `/tasks/{id}`.
+    pub route: Option<String>,
+    /// The page or action function, e.g. `app::tasks::show`.
+    pub handler: Option<String>,
+    /// The fields of a URL-encoded form body, for bodies up to
+    /// `MAX_INSPECTED_FORM`.
+    pub form: Option<Vec<(String, String)>>,
+}
+
+/// What is done with server errors, set up in `Acacia::app`.
+pub(crate) struct Reporting {
+    pub(crate) hook: Option<Arc<ErrorHook>>,
+    /// Replace the response body with the development error page.
+    pub(crate) detailed: bool,
+}
+
+/// Middleware handing 5xx responses to the hook and the development error page.
+pub(crate) async fn report(reporting: Arc<Reporting>, req: Request, next: Next) -> Response {
+    let htmx = req.headers().contains_key("HX-Request");
+    // Larger forms are left to the route, and reported without their fields
+    let (req, form) = match acacia_core::read_small_form(req).await {
+        Ok(read) => read,
+        Err(err) => return err.into_response(),
+    };
+    let form = form.and_then(|bytes| serde_urlencoded::from_bytes(&bytes).ok());
+    let (parts, body) = req.into_parts();
+    let mut info = RequestInfo {
+        method: parts.method.clone(),
+        uri: parts.uri.clone(),
+        headers: redact_headers(&parts.headers),
+        route: parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string()),
+        handler: None,
+        form: form.map(redact_form),
+    };
+
+    let mut response = next.run(Request::from_parts(parts, body)).await;
+    let Some(report) = response.extensions_mut().remove::<ErrorReport>() else {
+        return response;
+    };
+    if let Some(def) = info
+        .route
+        .as_deref()
+        .and_then(|route| handler(&info.method, route))
+    {
+        info.route = Some(def.url());
+        info.handler = Some(format!("{}::{}", def.module, def.name));
+    }
+
+    if let Some(hook) = &reporting.hook {
+        hook(&report, &info);
+    }
+    if reporting.detailed {
+        if htmx {
+            // Swap the report into the page unless an error target is configured
+            let headers = response.headers_mut();
+            if headers.get("HX-Reswap").is_some_and(|swap| swap == "none") {
+                headers.insert("HX-Retarget", HeaderValue::from_static("body"));
+                headers.insert("HX-Reswap", HeaderValue::from_static("innerHTML"));
+            }
+        }
+        response.headers_mut().remove(header::CONTENT_LENGTH);
+        *response.body_mut() = Body::from(dev_errors::page(&report, &info, htmx));
+    }
+    response
+}
+
+/// The handler registered for `route`, as axum matched it.
+fn handler(method: &Method, route: &str) -> Option<&'static RouteDefinition> {
+    inventory::iter::<RouteDefinition>.into_iter().find(|def| {
+        crate::axum_path(&def.url()) == route
+            && def
+                .methods()
+                .any(|served| served.to_string() == method.as_str())
+    })
+}
+
+fn redact_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in [header::COOKIE, header::AUTHORIZATION] {
+        if headers.contains_key(&name) {
+            headers.insert(name, HeaderValue::from_static(REDACTED));
+        }
+    }
+    headers
+}
+
+fn redact_form(form: Vec<(String, String)>) -> Vec<(String, String)> {
+    form.into_iter()
+        .map(|(name, value)| {
+            let lower = name.to_ascii_lowercase();
+            if ["password", "secret", "token"]
+                .iter()
+                .any(|word| lower.contains(word))
+            {
+                (name, REDACTED.to_string())
+            } else {
+                (name, value)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,241 @@
+//! Security headers and Content-Security-Policy.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use rand::{rngs::OsRng, RngCore};
+use std::time::Duration;
+
+/// Security headers added to every response, set with `Acacia::security`.
+///
+/// By default responses get `X-Content-Type-Options: nosniff`,
+/// `X-Frame-Options: DENY` and `Referrer-Policy: strict-origin-when-cross-origin`.
+/// A header already set by a handler is left alone, so routes can override any of them.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new().security(
+///     Security::default()
+///         .csp(Csp::new().default_src_self().script_src_self())
+///         .hsts(Duration::from_secs(365 * 24 * 60 * 60)),
+/// )
+/// ```
+#[derive(Clone, Debug)]
+pub struct Security {
+    headers: Vec<(HeaderName, HeaderValue)>,
+    csp: Option<Csp>,
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Self::none()
+            .header("x-content-type-options", "nosniff")
+            .header("x-frame-options", "DENY")
+            .header("referrer-policy", "strict-origin-when-cross-origin")
+    }
+}
+
+impl Security {
+    /// No headers at all, to build up from scratch.
+    pub fn none() -> Self {
+        Self {
+            headers: Vec::new(),
+            csp: None,
+        }
+    }
+
+    /// Set a header, replacing an earlier value for the same name.
+    ///
+    /// # Panics
+    /// If `name` or `value` isn't a valid header.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        let name = HeaderName::try_from(name).expect("Invalid header name");
+        let value = HeaderValue::try_from(value).expect("Invalid header value");
+        self.headers.retain(|(existing, _)| *existing != name);
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Stop sending a header, e.g. `without("x-frame-options")` to allow framing.
+    pub fn without(mut self, name: &str) -> Self {
+        self.headers
+            .retain(|(existing, _)| !existing.as_str().eq_ignore_ascii_case(name));
+        self
+    }
+
+    /// `X-Frame-Options`, such as `"DENY"` or `"SAMEORIGIN"`.
+    pub fn frame_options(self, value: &str) -> Self {
+        self.header("x-frame-options", value)
+    }
+
+    /// `Referrer-Policy`, such as `"no-referrer"`.
+    pub fn referrer_policy(self, value: &str) -> Self {
+        self.header("referrer-policy", value)
+    }
+
+    /// Tell browsers to only use HTTPS for `max_age`, including subdomains.
+    pub fn hsts(self, max_age: Duration) -> Self {
+        self.header(
+            "strict-transport-security",
+            &format!("max-age={}; includeSubDomains", max_age.as_secs()),
+        )
+    }
+
+    pub fn csp(mut self, csp: Csp) -> Self {
+        self.csp = Some(csp);
+        self
+    }
+}
+
+/// A Content-Security-Policy.
+///
+/// When the policy restricts scripts or styles, each request gets a fresh
+/// nonce that is added to the policy and to the script tags of `Page`.
+///
+/// # Example
+/// ```ignore
+/// Csp::new()
+///     .default_src_self()
+///     .script_src_self()
+///     .img_src("https://images.example.com")
+///     .frame_ancestors("'none'")
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Csp {
+    directives: Vec<(String, Vec<String>)>,
+    report_only: bool,
+}
+
+impl Csp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `source` to `directive`, e.g. `directive("media-src", "https://cdn.example.com")`.
+    pub fn directive(mut self, directive: &str, source: &str) -> Self {
+        match self
+            .directives
+            .iter_mut()
+            .find(|(name, _)| name == directive)
+        {
+            Some((_, sources)) => sources.push(source.to_string()),
+            None => self
+                .directives
+                .push((directive.to_string(), vec![source.to_string()])),
+        }
+        self
+    }
+
+    pub fn default_src(self, source: &str) -> Self {
+        self.directive("default-src", source)
+    }
+
+    pub fn default_src_self(self) -> Self {
+        self.default_src("'self'")
+    }
+
+    pub fn script_src(self, source: &str) -> Self {
+        self.directive("script-src", source)
+    }
+
+    pub fn script_src_self(self) -> Self {
+        self.script_src("'self'")
+    }
+
+    pub fn style_src(self, source: &str) -> Self {
+        self.directive("style-src", source)
+    }
+
+    pub fn style_src_self(self) -> Self {
+        self.style_src("'self'")
+    }
+
+    pub fn img_src(self, source: &str) -> Self {
+        self.directive("img-src", source)
+    }
+
+    pub fn connect_src(self, source: &str) -> Self {
+        self.directive("connect-src", source)
+    }
+
+    pub fn frame_ancestors(self, source: &str) -> Self {
+        self.directive("frame-ancestors", source)
+    }
+
+    /// Report violations to `url` without blocking anything.
+    pub fn report_only(mut self, url: &str) -> Self {
+        self.report_only = true;
+        self.directive("report-uri", url)
+    }
+
+    fn header_name(&self) -> &'static str {
+        if self.report_only {
+            "content-security-policy-report-only"
+        } else {
+            "content-security-policy"
+        }
+    }
+
+    /// Whether a directive governs scripts or styles, so a nonce is needed.
+    fn uses_nonce(&self) -> bool {
+        self.directives
+            .iter()
+            .any(|(name, _)| is_nonce_directive(name))
+    }
+
+    fn render(&self, nonce: Option<&str>) -> String {
+        self.directives
+            .iter()
+            .map(|(name, sources)| {
+                let mut value = format!("{} {}", name, sources.join(" "));
+                if let Some(nonce) = nonce.filter(|_| is_nonce_directive(name)) {
+                    value.push_str(&format!(" 'nonce-{}'", nonce));
+                }
+                value
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+fn is_nonce_directive(name: &str) -> bool {
+    matches!(name, "default-src" | "script-src" | "style-src")
+}
+
+fn new_nonce() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Add the configured headers, running the handler with the request's nonce.
+pub(crate) async fn secure(security: Security, req: Request, next: Next) -> Response {
+    let nonce = security
+        .csp
+        .as_ref()
+        .filter(|csp| csp.uses_nonce())
+        .map(|_| new_nonce());
+    let mut response = match &nonce {
+        Some(nonce) => acacia_core::with_csp_nonce(nonce.clone(), next.run(req)).await,
+        None => next.run(req).await,
+    };
+
+    let headers = response.headers_mut();
+    for (name, value) in security.headers {
+        if !headers.contains_key(&name) {
+            headers.insert(name, value);
+        }
+    }
+    if let Some(csp) = &security.csp {
+        let name = HeaderName::from_static(csp.header_name());
+        if !headers.contains_key(&name) {
+            if let Ok(value) = HeaderValue::try_from(csp.render(nonce.as_deref())) {
+                headers.insert(name, value);
+            }
+        }
+    }
+    response
+}
@@ -0,0 +1,135 @@
+//! `/sitemap.xml` generation from registered page routes.
+
+use acacia_core::{AppState, Endpoint, RouteDefinition, RouteKind};
+use acacia_db::Db;
+use axum::{extract::State, response::IntoResponse};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+type DynamicUrls =
+    dyn Fn(Db) -> Pin<Box<dyn Future<Output = acacia_core::Result<Sitemap>> + Send>> + Send + Sync;
+
+/// A list of URLs to publish in the sitemap, beyond the static page routes.
+///
+/// # Example
+/// ```ignore
+/// Acacia::new()
+///     .sitemap_with("https://example.com", |db: Db| async move {
+///         let mut sitemap = Sitemap::new();
+///         sitemap.add_all(db.all::<Post>().await?, |post| SHOW_POST(post.id));
+///         Ok(sitemap)
+///     })
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Sitemap {
+    paths: Vec<String>,
+}
+
+impl Sitemap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a single endpoint.
+    pub fn add(&mut self, endpoint: Endpoint) -> &mut Self {
+        self.paths.push(endpoint.href().into_owned());
+        self
+    }
+
+    /// Add one endpoint per item, e.g. a URL for every row of a model.
+    pub fn add_all<T>(
+        &mut self,
+        items: impl IntoIterator<Item = T>,
+        endpoint: impl Fn(&T) -> Endpoint,
+    ) -> &mut Self {
+        for item in items {
+            self.add(endpoint(&item));
+        }
+        self
+    }
+}
+
+/// Sitemap configuration stored on the builder.
+#[derive(Clone)]
+pub(crate) struct SitemapConfig {
+    base_url: String,
+    dynamic: Option<Arc<DynamicUrls>>,
+}
+
+impl SitemapConfig {
+    pub(crate) fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            dynamic: None,
+        }
+    }
+
+    pub(crate) fn with_dynamic<F, Fut>(mut self, dynamic: F) -> Self
+    where
+        F: Fn(Db) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = acacia_core::Result<Sitemap>> + Send + 'static,
+    {
+        self.dynamic = Some(Arc::new(move |db| Box::pin(dynamic(db))));
+        self
+    }
+
+    /// Handler serving the rendered sitemap.
+    pub(crate) fn handler(
+        self,
+    ) -> impl Fn(State<AppState>) -> Pin<Box<dyn Future<Output = axum::response::Response> + Send>>
+           + Clone
+           + Send
+           + Sync
+           + 'static {
+        let config = Arc::new(self);
+        move |State(state): State<AppState>| {
+            let config = config.clone();
+            Box::pin(async move {
+                match config.render(state).await {
+                    Ok(xml) => {
+                        ([(http::header::CONTENT_TYPE, "application/xml")], xml).into_response()
+                    }
+                    Err(err) => err.into_response(),
+                }
+            })
+        }
+    }
+
+    async fn render(&self, state: AppState) -> acacia_core::Result<String> {
+        // Parameterless pages are known statically, minus guarded and opted-out ones
+        let mut paths: Vec<String> = inventory::iter::<RouteDefinition>
+            .into_iter()
+            .filter(|route| route.kind == RouteKind::Page && route.listed && !route.has_params())
+            .map(|route| route.url())
+            .collect();
+        paths.sort();
+
+        if let Some(dynamic) = &self.dynamic {
+            let conn = state.db.ok_or_else(|| {
+                acacia_core::AppError::Internal("Sitemap requires a database".to_string())
+            })?;
+            paths.extend(dynamic(Db::new(conn)).await?.paths);
+        }
+
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        );
+        for path in paths {
+            xml.push_str("  <url><loc>");
+            xml.push_str(&xml_escape(&format!("{}{}", self.base_url, path)));
+            xml.push_str("</loc></url>\n");
+        }
+        xml.push_str("</urlset>\n");
+        Ok(xml)
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
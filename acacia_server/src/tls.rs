@@ -0,0 +1,76 @@
+//! Serving HTTPS with rustls.
+
+use crate::AcaciaError;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A certificate and private key to serve HTTPS with, set with `Acacia::tls`.
+///
+/// HTTP/2 is offered to clients during the handshake when enabled with
+/// [`Http::http2`](crate::Http::http2).
+///
+/// # Example
+/// ```ignore
+/// Acacia::new()
+///     .tls(Tls::from_pem_files("cert.pem", "key.pem")?)
+///     .http(Http::new().http2())
+///     .serve("0.0.0.0:443")
+///     .await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tls {
+    config: ServerConfig,
+}
+
+impl Tls {
+    /// A PEM certificate chain and private key.
+    pub fn from_pem(cert: &[u8], key: &[u8]) -> Result<Self, AcaciaError> {
+        let certs = CertificateDer::pem_slice_iter(cert)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AcaciaError::Tls(format!("invalid certificate: {}", e)))?;
+        if certs.is_empty() {
+            return Err(AcaciaError::Tls("no certificate found".to_string()));
+        }
+        let key = PrivateKeyDer::from_pem_slice(key)
+            .map_err(|e| AcaciaError::Tls(format!("invalid private key: {}", e)))?;
+        Self::new(certs, key)
+    }
+
+    /// Read a PEM certificate chain and private key from files.
+    pub fn from_pem_files(
+        cert: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> Result<Self, AcaciaError> {
+        let read = |path: &Path| {
+            std::fs::read(path)
+                .map_err(|e| AcaciaError::Tls(format!("failed to read {}: {}", path.display(), e)))
+        };
+        Self::from_pem(&read(cert.as_ref())?, &read(key.as_ref())?)
+    }
+
+    fn new(
+        certs: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Result<Self, AcaciaError> {
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+                .map_err(|e| AcaciaError::Tls(e.to_string()))?;
+        Ok(Self { config })
+    }
+
+    /// The acceptor for new connections, offering HTTP/2 if `http2` is set.
+    pub(crate) fn acceptor(&self, http2: bool) -> tokio_rustls::TlsAcceptor {
+        let mut config = self.config.clone();
+        config.alpn_protocols = if http2 {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        };
+        tokio_rustls::TlsAcceptor::from(Arc::new(config))
+    }
+}
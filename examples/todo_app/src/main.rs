@@ -10,6 +10,8 @@ pub struct Task {
 
 #[form(Task)]
 struct NewTask {
+    #[required]
+    #[max_len(200)]
     title: String,
 }
 
@@ -21,7 +23,7 @@ fn TaskItem(task: &Task) -> Fragment {
             <input
                 type="checkbox"
                 checked={task.done}
-                {submits(TOGGLE_TASK(task_id)).target(Target::Parent)}
+                {submits(TOGGLE_TASK(task_id)).target(Target::Closest("li".into()))}
             />
             <span class={tw!("flex-1", "line-through opacity-50" => task.done)}>
                 {&task.title}
@@ -49,9 +51,8 @@ async fn home(db: Db) -> Result<Page> {
                 class="flex gap-2"
             >
                 <input
-                    name="title"
+                    {NewTask::input_attrs("title")}
                     placeholder="New task..."
-                    required
                     class="flex-1 p-2 border border-gray-300 rounded"
                 />
                 <button
@@ -88,6 +89,6 @@ async fn main() {
     Acacia::new()
         .database("sqlite://tasks.db?mode=rwc")
         .migrate(MigratePolicy::Auto)
-        .serve("0.0.0.0:3000")
+        .serve_or_exit("0.0.0.0:3000")
         .await;
 }